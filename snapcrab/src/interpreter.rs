@@ -4,9 +4,16 @@
 //! The interpreter evaluates MIR instructions directly, handling function calls,
 //! control flow, and memory operations without code generation overhead.
 
+mod allocator;
 pub mod check;
+mod float;
 pub mod function;
 mod intrinsics;
 pub mod native;
+pub mod panic;
 mod place;
 mod rvalue;
+pub mod scheduler;
+mod sync;
+pub mod thread;
+pub mod thread_local;
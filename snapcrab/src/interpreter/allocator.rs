@@ -0,0 +1,164 @@
+//! Global allocator shims.
+//!
+//! The `alloc` crate reaches the global allocator through a handful of
+//! foreign functions (`__rust_alloc`, `__rust_dealloc`, ...). Calls to them are
+//! redirected to the interpreter heap instead of the native allocator, so that
+//! the interpreter can access and track every heap block the program creates.
+
+use crate::memory::{ThreadMemory, pointer_width};
+use crate::ty::struct_field;
+use crate::value::Value;
+use anyhow::Result;
+use rustc_public::CrateDef;
+use rustc_public::mir::mono::Instance;
+use rustc_public::ty::{RigidTy, Ty, TyKind};
+use tracing::debug;
+
+/// The allocator entry points intercepted by the interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllocatorFn {
+    /// `__rust_alloc(size, align) -> *mut u8`
+    Alloc,
+    /// `__rust_alloc_zeroed(size, align) -> *mut u8`
+    AllocZeroed,
+    /// `__rust_dealloc(ptr, size, align)`
+    Dealloc,
+    /// `__rust_realloc(ptr, old_size, align, new_size) -> *mut u8`
+    Realloc,
+}
+
+impl AllocatorFn {
+    /// Return the allocator entry point implemented by `instance`, if any.
+    pub fn from_instance(instance: Instance) -> Option<Self> {
+        if !instance.is_foreign_item() {
+            return None;
+        }
+        match instance.name().as_str() {
+            "alloc::alloc::__rust_alloc" => Some(AllocatorFn::Alloc),
+            "alloc::alloc::__rust_alloc_zeroed" => Some(AllocatorFn::AllocZeroed),
+            "alloc::alloc::__rust_dealloc" => Some(AllocatorFn::Dealloc),
+            "alloc::alloc::__rust_realloc" => Some(AllocatorFn::Realloc),
+            _ => None,
+        }
+    }
+
+    /// Execute the allocator call against the interpreter heap.
+    ///
+    /// Sizes are `usize` and alignments are `ptr::Alignment`, which has the
+    /// same representation as `usize`.
+    pub fn eval(self, args: &[Value], memory: &mut ThreadMemory) -> Result<Value> {
+        debug!("Allocator call: {self:?}");
+        let arg = |idx: usize| args[idx].as_type::<usize>().unwrap();
+        match self {
+            // Heap blocks are always zero-initialized.
+            AllocatorFn::Alloc | AllocatorFn::AllocZeroed => {
+                let address = memory.allocate(arg(0), arg(1))?;
+                Ok(Value::from_type(address))
+            }
+            AllocatorFn::Dealloc => {
+                memory.deallocate(arg(0), arg(1), arg(2))?;
+                Ok(Value::unit().clone())
+            }
+            AllocatorFn::Realloc => {
+                let address = memory.reallocate(arg(0), arg(1), arg(2), arg(3))?;
+                Ok(Value::from_type(address))
+            }
+        }
+    }
+}
+
+/// The methods of the `System` allocator intercepted by the interpreter.
+///
+/// `std` allocates some of its own data, like thread handles, with `System`
+/// rather than with the global allocator. These blocks are served by the
+/// interpreter heap as well, since the program accesses them directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemAllocFn {
+    /// `GlobalAlloc::alloc(&self, Layout) -> *mut u8`
+    Alloc,
+    /// `GlobalAlloc::alloc_zeroed(&self, Layout) -> *mut u8`
+    AllocZeroed,
+    /// `GlobalAlloc::dealloc(&self, *mut u8, Layout)`
+    Dealloc,
+    /// `GlobalAlloc::realloc(&self, *mut u8, Layout, usize) -> *mut u8`
+    Realloc,
+    /// `Allocator::allocate(&self, Layout) -> Result<NonNull<[u8]>, AllocError>`
+    /// and its zeroed variant
+    Allocate,
+    /// `Allocator::deallocate(&self, NonNull<u8>, Layout)`
+    Deallocate,
+}
+
+impl SystemAllocFn {
+    /// Return the `System` allocator method implemented by `instance`, if any.
+    pub fn from_instance(instance: Instance) -> Option<Self> {
+        match instance.def.name().as_str() {
+            "<std::alloc::System as std::alloc::GlobalAlloc>::alloc" => Some(SystemAllocFn::Alloc),
+            "<std::alloc::System as std::alloc::GlobalAlloc>::alloc_zeroed" => {
+                Some(SystemAllocFn::AllocZeroed)
+            }
+            "<std::alloc::System as std::alloc::GlobalAlloc>::dealloc" => {
+                Some(SystemAllocFn::Dealloc)
+            }
+            "<std::alloc::System as std::alloc::GlobalAlloc>::realloc" => {
+                Some(SystemAllocFn::Realloc)
+            }
+            "<std::alloc::System as std::alloc::Allocator>::allocate"
+            | "<std::alloc::System as std::alloc::Allocator>::allocate_zeroed" => {
+                Some(SystemAllocFn::Allocate)
+            }
+            "<std::alloc::System as std::alloc::Allocator>::deallocate" => {
+                Some(SystemAllocFn::Deallocate)
+            }
+            _ => None,
+        }
+    }
+
+    /// Execute the allocator call against the interpreter heap.
+    ///
+    /// The arguments are flattened into the arguments of the matching global
+    /// allocator entry point, i.e., the receiver is skipped and each `Layout`
+    /// is split into its size and alignment.
+    pub fn eval(
+        self,
+        args: &[Value],
+        instance: Instance,
+        memory: &mut ThreadMemory,
+    ) -> Result<Value> {
+        debug!("System allocator call: {self:?}");
+        let fn_abi = instance.fn_abi()?;
+        let mut flat_args = Vec::with_capacity(args.len() + 1);
+        for (arg, arg_abi) in args.iter().zip(fn_abi.args.iter()).skip(1) {
+            if is_layout(arg_abi.ty) {
+                let (size, _) = struct_field(arg_abi.ty, 0)?;
+                let (align, _) = struct_field(arg_abi.ty, 1)?;
+                let ptr_width = pointer_width();
+                flat_args.push(Value::from_bytes(&arg.as_bytes()[size..size + ptr_width]));
+                flat_args.push(Value::from_bytes(&arg.as_bytes()[align..align + ptr_width]));
+            } else {
+                flat_args.push(arg.clone());
+            }
+        }
+        match self {
+            SystemAllocFn::Alloc => AllocatorFn::Alloc.eval(&flat_args, memory),
+            SystemAllocFn::AllocZeroed => AllocatorFn::AllocZeroed.eval(&flat_args, memory),
+            SystemAllocFn::Dealloc | SystemAllocFn::Deallocate => {
+                AllocatorFn::Dealloc.eval(&flat_args, memory)
+            }
+            SystemAllocFn::Realloc => AllocatorFn::Realloc.eval(&flat_args, memory),
+            SystemAllocFn::Allocate => {
+                // `Ok(NonNull<[u8]>)` is represented by the slice pointer,
+                // since `Err` uses its null niche.
+                let address = AllocatorFn::Alloc.eval(&flat_args, memory)?;
+                Ok(Value::from_bytes(
+                    &[address.as_bytes(), flat_args[0].as_bytes()].concat(),
+                ))
+            }
+        }
+    }
+}
+
+/// Whether `ty` is `core::alloc::Layout`.
+fn is_layout(ty: Ty) -> bool {
+    matches!(ty.kind(), TyKind::RigidTy(RigidTy::Adt(def, _)) if def.name() == "std::alloc::Layout" || def.name() == "core::alloc::Layout")
+}
@@ -1,65 +1,213 @@
-//! Value validity checking.
+//! Value validity checking and the configuration of undefined behavior checks.
 //!
 //! Validates that interpreter values satisfy Rust's type invariants before
 //! passing them to native code or after transmute operations.
+//! Each category of checks can be turned into a warning or disabled.
 
 use crate::value::{Value, uint_from_bytes};
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use rustc_public::abi::{
     FieldsShape, Primitive, Scalar, TagEncoding, ValueAbi, VariantFields, VariantsShape,
 };
 use rustc_public::target::MachineInfo;
 use rustc_public::ty::{RigidTy, Ty, TyKind, VariantIdx};
 use rustc_public_bridge::IndexedVal;
+use std::str::FromStr;
+use tracing::warn;
 
 /// Which categories of undefined behavior checks to perform.
 #[derive(Clone, Debug)]
 pub struct CheckConfig {
-    pub validity: bool,
-    pub alignment: bool,
-    pub bounds: bool,
+    pub validity: CheckLevel,
+    pub alignment: CheckLevel,
+    pub bounds: CheckLevel,
+    /// Validate every value read from a place as a typed operand. This is
+    /// stricter and slower than `validity`, so it is off by default.
+    pub load_validity: CheckLevel,
+    /// The value of `cfg!(ub_checks)` for code that checks it at runtime,
+    /// such as the unsafe preconditions in `core`.
+    pub ub_checks: bool,
+    /// The value of `cfg!(overflow_checks)` for code that checks it at runtime.
+    pub overflow_checks: bool,
+    /// The value of `cfg!(contract_checks)` for code that checks it at runtime.
+    pub contract_checks: bool,
 }
 
 impl Default for CheckConfig {
     fn default() -> Self {
         Self {
-            validity: true,
-            alignment: true,
-            bounds: true,
+            validity: CheckLevel::Error,
+            alignment: CheckLevel::Error,
+            bounds: CheckLevel::Error,
+            load_validity: CheckLevel::Off,
+            ub_checks: true,
+            overflow_checks: true,
+            contract_checks: false,
         }
     }
 }
 
 impl CheckConfig {
-    /// Parse `--skip-check=validity,alignment` style flags.
-    pub fn with_skipped(skipped: &[String]) -> Self {
-        let mut config = Self::default();
-        for s in skipped {
-            for item in s.split(',') {
-                match item.trim() {
-                    "validity" => config.validity = false,
-                    "alignment" => config.alignment = false,
-                    "bounds" => config.bounds = false,
-                    _ => {}
+    /// Set the level of a check category.
+    pub fn set_level(&mut self, kind: CheckKind, level: CheckLevel) {
+        match kind {
+            CheckKind::Validity => self.validity = level,
+            CheckKind::Alignment => self.alignment = level,
+            CheckKind::Bounds => self.bounds = level,
+            CheckKind::LoadValidity => self.load_validity = level,
+        }
+    }
+}
+
+/// A category of undefined behavior checks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckKind {
+    /// Values must be valid for their type, e.g. a `bool` is 0 or 1.
+    Validity,
+    /// Memory accesses must be aligned to the type of the access.
+    Alignment,
+    /// Memory accesses and indexing must be within the bounds of their allocation.
+    Bounds,
+    /// Values read from places must be valid for their type.
+    LoadValidity,
+}
+
+impl CheckKind {
+    pub const ALL: [CheckKind; 4] = [
+        CheckKind::Validity,
+        CheckKind::Alignment,
+        CheckKind::Bounds,
+        CheckKind::LoadValidity,
+    ];
+}
+
+impl FromStr for CheckKind {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.trim() {
+            "validity" => Ok(CheckKind::Validity),
+            "alignment" => Ok(CheckKind::Alignment),
+            "bounds" => Ok(CheckKind::Bounds),
+            "load-validity" => Ok(CheckKind::LoadValidity),
+            _ => bail!(
+                "unknown check `{name}`, expected one of: validity, alignment, bounds, load-validity"
+            ),
+        }
+    }
+}
+
+/// What to do when a check finds a violation.
+///
+/// Unless the level is `Error`, the interpreter carries on like native code
+/// would. For instance, an out-of-bounds access reads or writes the host
+/// memory past the allocation. Accesses to memory that the host doesn't have
+/// mapped are still errors, since the interpreter itself would crash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckLevel {
+    /// Stop the interpretation with an error.
+    Error,
+    /// Log the violation as a warning and continue.
+    Warn,
+    /// Don't report the violation, and continue.
+    Off,
+}
+
+impl CheckLevel {
+    /// Run `check` according to this level.
+    ///
+    /// With `Warn`, a violation is logged and `Ok` is returned, so the caller
+    /// carries on as if the check had passed.
+    pub fn check(self, check: impl FnOnce() -> Result<()>) -> Result<()> {
+        match self {
+            CheckLevel::Error => check(),
+            CheckLevel::Warn => {
+                if let Err(violation) = check() {
+                    warn!("{violation}");
                 }
+                Ok(())
             }
+            CheckLevel::Off => Ok(()),
+        }
+    }
+}
+
+impl FromStr for CheckLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(level: &str) -> Result<Self> {
+        match level.trim() {
+            "error" => Ok(CheckLevel::Error),
+            "warn" => Ok(CheckLevel::Warn),
+            "off" => Ok(CheckLevel::Off),
+            _ => bail!("unknown check level `{level}`, expected one of: error, warn, off"),
         }
-        config
     }
 }
 
+/// Parse a `name=level` check setting, where `name` may also be `all`.
+pub fn parse_check_setting(setting: &str) -> Result<(Vec<CheckKind>, CheckLevel)> {
+    let (name, level) = setting
+        .split_once('=')
+        .with_context(|| format!("expected `<check>=<level>`, found `{setting}`"))?;
+    let kinds = if name.trim() == "all" {
+        CheckKind::ALL.to_vec()
+    } else {
+        vec![name.parse()?]
+    };
+    Ok((kinds, level.parse()?))
+}
+
 /// Validate that a value is valid for its type.
 ///
 /// Checks scalar `valid_range` constraints recursively through the type
 /// layout. Returns an error describing the first invalidity found.
 pub fn validate_value(value: &Value, ty: Ty, config: &CheckConfig) -> Result<()> {
-    if !config.validity {
-        return Ok(());
-    }
-    validate_recursive(value.as_bytes(), ty)
+    config
+        .validity
+        .check(|| validate_recursive(value.as_bytes(), ty, false))
 }
 
-fn validate_recursive(bytes: &[u8], ty: Ty) -> Result<()> {
+/// Validate a value read from a place of type `ty`.
+///
+/// This is only done if the `load_validity` check is enabled.
+pub fn validate_load(value: &Value, ty: Ty, config: &CheckConfig) -> Result<()> {
+    config.load_validity.check(|| {
+        validate_recursive(value.as_bytes(), ty, true).map_err(|error| {
+            anyhow!("Undefined behavior: read an invalid value of type `{ty}`: {error}")
+        })
+    })
+}
+
+/// Whether a value of type `ty` can never be constructed.
+///
+/// This only detects types without any variant, such as `!` and empty enums.
+pub fn is_uninhabited(ty: Ty) -> Result<bool> {
+    let layout = ty.layout()?.shape();
+    Ok(matches!(layout.variants, VariantsShape::Empty))
+}
+
+/// Whether the all-zeros bit pattern is a valid value of type `ty`.
+pub fn is_zero_valid(ty: Ty) -> Result<bool> {
+    let size = ty.layout()?.shape().size.bytes();
+    Ok(validate_recursive(&vec![0; size], ty, false).is_ok())
+}
+
+/// Validate `bytes` as a value of type `ty`.
+///
+/// The `strict` mode also rejects values that are inside the valid range of
+/// the layout, such as surrogate chars and enum tags without a variant.
+fn validate_recursive(bytes: &[u8], ty: Ty, strict: bool) -> Result<()> {
+    if strict && let TyKind::RigidTy(RigidTy::Char) = ty.kind() {
+        let val = uint_from_bytes(bytes);
+        if char::from_u32(val as u32).is_none() {
+            bail!("Invalid char value {val:#x}");
+        }
+    }
+    if strict && ty.kind().is_enum() {
+        // The tag must match a variant even if the layout is a single scalar.
+        return validate_aggregate(bytes, ty, strict);
+    }
     let layout = ty.layout()?;
     let shape = layout.shape();
 
@@ -80,7 +228,7 @@ fn validate_recursive(bytes: &[u8], ty: Ty) -> Result<()> {
             )?;
         }
         ValueAbi::Aggregate { .. } => {
-            validate_aggregate(bytes, ty)?;
+            validate_aggregate(bytes, ty, strict)?;
         }
         _ => {}
     }
@@ -89,7 +237,7 @@ fn validate_recursive(bytes: &[u8], ty: Ty) -> Result<()> {
 }
 
 /// Validate an aggregate value by dispatching on its variant shape.
-fn validate_aggregate(bytes: &[u8], ty: Ty) -> Result<()> {
+fn validate_aggregate(bytes: &[u8], ty: Ty, strict: bool) -> Result<()> {
     let layout = ty.layout()?;
     let shape = layout.shape();
 
@@ -100,7 +248,7 @@ fn validate_aggregate(bytes: &[u8], ty: Ty) -> Result<()> {
                 FieldsShape::Arbitrary { offsets } => offsets,
                 _ => return Ok(()),
             };
-            validate_fields(bytes, &field_tys, offsets)?;
+            validate_fields(bytes, &field_tys, offsets, strict)?;
         }
         VariantsShape::Multiple {
             tag,
@@ -118,11 +266,11 @@ fn validate_aggregate(bytes: &[u8], ty: Ty) -> Result<()> {
             validate_scalar(&bytes[tag_off..tag_off + tag_sz], tag, &ty)?;
 
             // Determine active variant and validate its fields.
-            let active_variant =
-                active_variant_idx(&bytes[tag_off..tag_off + tag_sz], tag_encoding, tag_sz);
+            let tag_bytes = &bytes[tag_off..tag_off + tag_sz];
+            let active_variant = active_variant_idx(tag_bytes, tag_encoding, tag_sz, ty, strict)?;
             if let Some(variant_idx) = active_variant {
                 let field_tys = resolve_field_types(ty, variant_idx);
-                validate_variant_fields(bytes, &field_tys, variants, variant_idx)?;
+                validate_variant_fields(bytes, &field_tys, variants, variant_idx, strict)?;
             }
         }
         _ => {}
@@ -136,6 +284,7 @@ fn validate_fields(
     bytes: &[u8],
     field_tys: &[Ty],
     offsets: &[rustc_public::target::MachineSize],
+    strict: bool,
 ) -> Result<()> {
     for (i, field_ty) in field_tys.iter().enumerate() {
         if i >= offsets.len() {
@@ -150,7 +299,7 @@ fn validate_fields(
             continue;
         }
         if offset + field_size <= bytes.len() {
-            validate_recursive(&bytes[offset..offset + field_size], *field_ty)?;
+            validate_recursive(&bytes[offset..offset + field_size], *field_ty, strict)?;
         }
     }
     Ok(())
@@ -162,6 +311,7 @@ fn validate_variant_fields(
     field_tys: &[Ty],
     variants: &[VariantFields],
     variant_idx: VariantIdx,
+    strict: bool,
 ) -> Result<()> {
     let idx = variant_idx.to_index();
     if idx >= variants.len() {
@@ -181,7 +331,7 @@ fn validate_variant_fields(
             continue;
         }
         if offset + field_size <= bytes.len() {
-            validate_recursive(&bytes[offset..offset + field_size], *field_ty)?;
+            validate_recursive(&bytes[offset..offset + field_size], *field_ty, strict)?;
         }
     }
     Ok(())
@@ -205,15 +355,33 @@ fn resolve_field_types(ty: Ty, variant_idx: VariantIdx) -> Vec<Ty> {
     }
 }
 
-/// Determine which variant is active from the tag bytes.
+/// Determine which variant of `ty` is active from the tag bytes.
+///
+/// The `strict` mode rejects direct tags that don't match any variant.
 fn active_variant_idx(
     tag_bytes: &[u8],
     encoding: &TagEncoding,
     tag_sz: usize,
-) -> Option<VariantIdx> {
+    ty: Ty,
+    strict: bool,
+) -> Result<Option<VariantIdx>> {
     let tag_val = uint_from_bytes(&tag_bytes[..tag_sz]);
-    match encoding {
-        TagEncoding::Direct => Some(VariantIdx::to_val(tag_val as usize)),
+    let max_tag = u128::MAX >> (128 - tag_sz * 8);
+    let variant = match encoding {
+        TagEncoding::Direct if !strict => Some(VariantIdx::to_val(tag_val as usize)),
+        TagEncoding::Direct => {
+            // The tag is the discriminant of the variant, which must exist.
+            let TyKind::RigidTy(RigidTy::Adt(def, _)) = ty.kind() else {
+                return Ok(None);
+            };
+            let variant = (0..def.num_variants())
+                .map(VariantIdx::to_val)
+                .find(|idx| def.discriminant_for_variant(*idx).val & max_tag == tag_val);
+            if variant.is_none() {
+                bail!("Invalid enum tag {tag_val:#x} for type `{ty}`");
+            }
+            variant
+        }
         TagEncoding::Niche {
             untagged_variant,
             niche_variants,
@@ -222,7 +390,6 @@ fn active_variant_idx(
             let niche_start_idx = niche_variants.start().to_index();
             let niche_end_idx = niche_variants.end().to_index();
             let variant_count = niche_end_idx - niche_start_idx + 1;
-            let max_tag = u128::MAX >> (128 - tag_sz * 8);
             let relative = tag_val.wrapping_sub(*niche_start) & max_tag;
             if relative < variant_count as u128 {
                 Some(VariantIdx::to_val(niche_start_idx + relative as usize))
@@ -230,7 +397,8 @@ fn active_variant_idx(
                 Some(*untagged_variant)
             }
         }
-    }
+    };
+    Ok(variant)
 }
 
 fn validate_scalar(bytes: &[u8], scalar: &Scalar, ty: &Ty) -> Result<()> {
@@ -271,3 +439,34 @@ fn scalar_size(scalar: &Scalar, target: &MachineInfo) -> usize {
     };
     prim.size(target).bytes()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_check_setting() {
+        let (kinds, level) = parse_check_setting("bounds=warn").unwrap();
+        assert_eq!(kinds, [CheckKind::Bounds]);
+        assert_eq!(level, CheckLevel::Warn);
+        let (kinds, level) = parse_check_setting("all=off").unwrap();
+        assert_eq!(kinds, CheckKind::ALL);
+        assert_eq!(level, CheckLevel::Off);
+    }
+
+    #[test]
+    fn test_parse_check_setting_rejects_unknown() {
+        assert!(parse_check_setting("bound=warn").is_err());
+        assert!(parse_check_setting("bounds=ignore").is_err());
+        assert!(parse_check_setting("bounds").is_err());
+        assert!("overflow".parse::<CheckKind>().is_err());
+    }
+
+    #[test]
+    fn test_check_level() {
+        let violation = || bail!("violation");
+        assert!(CheckLevel::Error.check(violation).is_err());
+        assert!(CheckLevel::Warn.check(violation).is_ok());
+        assert!(CheckLevel::Off.check(|| unreachable!()).is_ok());
+    }
+}
@@ -0,0 +1,290 @@
+//! Floating-point operations.
+//!
+//! Floats are evaluated with the host types of the same format, which follow
+//! the same IEEE 754 semantics as the compiled program. Values are converted
+//! from and to their bit representation, so the interpreter never depends on
+//! the alignment of a float inside a [`Value`].
+
+use crate::value::Value;
+use anyhow::{Result, bail};
+use rustc_public::mir::{BinOp, UnOp};
+use rustc_public::ty::{FloatTy, IntTy, RigidTy, UintTy};
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// A host floating-point type that mirrors one of the Rust float types.
+pub(super) trait HostFloat:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Read the float from its bit representation.
+    fn from_value(value: &Value) -> Self;
+
+    /// Store the float in its bit representation.
+    fn to_value(self) -> Value;
+
+    /// Convert to `f128`, which is exact for every float type.
+    fn to_f128(self) -> f128;
+
+    /// Convert from `f128`, rounding to the nearest representable value.
+    fn from_f128(value: f128) -> Self;
+
+    /// Convert to a signed integer, saturating at the bounds of `i128`.
+    fn to_i128(self) -> i128;
+
+    /// Convert to an unsigned integer, saturating at the bounds of `u128`.
+    fn to_u128(self) -> u128;
+
+    /// Convert from a signed integer, rounding to the nearest value.
+    fn from_i128(value: i128) -> Self;
+
+    /// Convert from an unsigned integer, rounding to the nearest value.
+    fn from_u128(value: u128) -> Self;
+}
+
+macro_rules! host_float {
+    ($float:ty, $bits:ty) => {
+        impl HostFloat for $float {
+            fn from_value(value: &Value) -> Self {
+                <$float>::from_bits(value.read_uint() as $bits)
+            }
+
+            fn to_value(self) -> Value {
+                Value::from_bytes(&self.to_bits().to_le_bytes())
+            }
+
+            fn to_f128(self) -> f128 {
+                self as f128
+            }
+
+            fn from_f128(value: f128) -> Self {
+                value as $float
+            }
+
+            fn to_i128(self) -> i128 {
+                self as i128
+            }
+
+            fn to_u128(self) -> u128 {
+                self as u128
+            }
+
+            fn from_i128(value: i128) -> Self {
+                value as $float
+            }
+
+            fn from_u128(value: u128) -> Self {
+                value as $float
+            }
+        }
+    };
+}
+
+host_float!(f16, u16);
+host_float!(f32, u32);
+host_float!(f64, u64);
+host_float!(f128, u128);
+
+/// Dispatch `$func::<T>($args)` to the host type `T` that matches `$float_ty`.
+macro_rules! with_float_ty {
+    ($float_ty:expr, $func:ident($($args:expr),*)) => {
+        match $float_ty {
+            FloatTy::F16 => $func::<f16>($($args),*),
+            FloatTy::F32 => $func::<f32>($($args),*),
+            FloatTy::F64 => $func::<f64>($($args),*),
+            FloatTy::F128 => $func::<f128>($($args),*),
+        }
+    };
+}
+
+pub(super) use with_float_ty;
+
+/// Evaluates a binary operation on floats of type `float_ty`.
+pub(super) fn eval_float_binop(
+    op: BinOp,
+    left: &Value,
+    right: &Value,
+    float_ty: FloatTy,
+) -> Result<Value> {
+    with_float_ty!(float_ty, float_binop(op, left, right))
+}
+
+fn float_binop<T: HostFloat>(op: BinOp, l: &Value, r: &Value) -> Result<Value> {
+    let left = T::from_value(l);
+    let right = T::from_value(r);
+    match op {
+        BinOp::Add => Ok((left + right).to_value()),
+        BinOp::Sub => Ok((left - right).to_value()),
+        BinOp::Mul => Ok((left * right).to_value()),
+        BinOp::Div => Ok((left / right).to_value()),
+        BinOp::Rem => Ok((left % right).to_value()),
+        BinOp::Eq => Ok(Value::from_bool(left == right)),
+        BinOp::Ne => Ok(Value::from_bool(left != right)),
+        BinOp::Lt => Ok(Value::from_bool(left < right)),
+        BinOp::Le => Ok(Value::from_bool(left <= right)),
+        BinOp::Gt => Ok(Value::from_bool(left > right)),
+        BinOp::Ge => Ok(Value::from_bool(left >= right)),
+        _ => bail!("Unsupported float binary operation: {op:?}"),
+    }
+}
+
+/// Evaluates a unary operation on a float of type `float_ty`.
+pub(super) fn eval_float_unop(op: UnOp, operand: &Value, float_ty: FloatTy) -> Result<Value> {
+    match op {
+        UnOp::Neg => with_float_ty!(float_ty, float_neg(operand)),
+        _ => bail!("Unsupported float unary operation: {op:?}"),
+    }
+}
+
+fn float_neg<T: HostFloat>(v: &Value) -> Result<Value> {
+    Ok((-T::from_value(v)).to_value())
+}
+
+/// Casts a float to an integer type, saturating at the bounds of the target
+/// type. NaN is converted to `0`.
+pub(super) fn float_to_int(value: &Value, src: FloatTy, dst: &RigidTy) -> Result<Value> {
+    with_float_ty!(src, cast_float_to_int(value, dst))
+}
+
+fn cast_float_to_int<T: HostFloat>(value: &Value, dst: &RigidTy) -> Result<Value> {
+    let float = T::from_value(value);
+    // Casting to the widest integer type saturates at its bounds, so clamping
+    // the result to a smaller type gives the same result as a direct cast.
+    let bytes = match dst {
+        RigidTy::Int(int_ty) => {
+            let bits = int_bits(*int_ty);
+            let (min, max) = (i128::MIN >> (128 - bits), i128::MAX >> (128 - bits));
+            float.to_i128().clamp(min, max).to_le_bytes()
+        }
+        RigidTy::Uint(uint_ty) => {
+            let bits = uint_bits(*uint_ty);
+            float.to_u128().min(u128::MAX >> (128 - bits)).to_le_bytes()
+        }
+        _ => bail!("Unsupported float to int cast target: {dst:?}"),
+    };
+    let size = int_size(dst);
+    Ok(Value::from_bytes(&bytes[..size]))
+}
+
+/// Casts an integer of type `src` to a float, rounding to the nearest value.
+pub(super) fn int_to_float(value: &Value, src: &RigidTy, dst: FloatTy) -> Result<Value> {
+    with_float_ty!(dst, cast_int_to_float(value, src))
+}
+
+fn cast_int_to_float<T: HostFloat>(value: &Value, src: &RigidTy) -> Result<Value> {
+    let float = match src {
+        RigidTy::Int(_) => T::from_i128(value.read_int()),
+        RigidTy::Uint(_) | RigidTy::Char | RigidTy::Bool => T::from_u128(value.read_uint()),
+        _ => bail!("Unsupported int to float cast source: {src:?}"),
+    };
+    Ok(float.to_value())
+}
+
+/// Casts between float types, rounding to the nearest value.
+pub(super) fn float_to_float(value: &Value, src: FloatTy, dst: FloatTy) -> Result<Value> {
+    // Every float type converts to `f128` exactly, so the value is only rounded once.
+    let wide = with_float_ty!(src, read_f128(value));
+    Ok(with_float_ty!(dst, write_f128(wide)))
+}
+
+fn read_f128<T: HostFloat>(value: &Value) -> f128 {
+    T::from_value(value).to_f128()
+}
+
+fn write_f128<T: HostFloat>(value: f128) -> Value {
+    T::from_f128(value).to_value()
+}
+
+fn int_bits(int_ty: IntTy) -> u32 {
+    match int_ty {
+        IntTy::I8 => 8,
+        IntTy::I16 => 16,
+        IntTy::I32 => 32,
+        IntTy::I64 => 64,
+        IntTy::I128 => 128,
+        IntTy::Isize => usize::BITS,
+    }
+}
+
+fn uint_bits(uint_ty: UintTy) -> u32 {
+    match uint_ty {
+        UintTy::U8 => 8,
+        UintTy::U16 => 16,
+        UintTy::U32 => 32,
+        UintTy::U64 => 64,
+        UintTy::U128 => 128,
+        UintTy::Usize => usize::BITS,
+    }
+}
+
+fn int_size(ty: &RigidTy) -> usize {
+    let bits = match ty {
+        RigidTy::Int(int_ty) => int_bits(*int_ty),
+        RigidTy::Uint(uint_ty) => uint_bits(*uint_ty),
+        _ => unreachable!("Expected integer type"),
+    };
+    bits as usize / 8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_float_binop() {
+        let left = 1.5f64.to_value();
+        let right = 0.25f64.to_value();
+        let sum = eval_float_binop(BinOp::Add, &left, &right, FloatTy::F64).unwrap();
+        assert_eq!(f64::from_value(&sum), 1.75);
+        let rem = eval_float_binop(BinOp::Rem, &left, &right, FloatTy::F64).unwrap();
+        assert_eq!(f64::from_value(&rem), 0.0);
+    }
+
+    #[test]
+    fn test_nan_comparisons() {
+        let nan = f32::NAN.to_value();
+        for op in [BinOp::Eq, BinOp::Lt, BinOp::Le, BinOp::Gt, BinOp::Ge] {
+            let result = eval_float_binop(op, &nan, &nan, FloatTy::F32).unwrap();
+            assert_eq!(result.as_bool(), Some(false), "{op:?}");
+        }
+        let result = eval_float_binop(BinOp::Ne, &nan, &nan, FloatTy::F32).unwrap();
+        assert_eq!(result.as_bool(), Some(true));
+    }
+
+    #[test]
+    fn test_float_to_int_saturates() {
+        let cast = |value: f64, ty: RigidTy| {
+            float_to_int(&value.to_value(), FloatTy::F64, &ty)
+                .unwrap()
+                .read_uint()
+        };
+        assert_eq!(cast(300.7, RigidTy::Uint(UintTy::U8)), 255);
+        assert_eq!(cast(-5.0, RigidTy::Uint(UintTy::U16)), 0);
+        assert_eq!(cast(f64::NAN, RigidTy::Int(IntTy::I32)), 0);
+        assert_eq!(cast(-1e10, RigidTy::Int(IntTy::I8)), 0x80);
+        assert_eq!(cast(-7.9, RigidTy::Int(IntTy::I32)), (-7i32) as u32 as u128);
+    }
+
+    #[test]
+    fn test_float_to_float() {
+        let narrow = float_to_float(&0.1f64.to_value(), FloatTy::F64, FloatTy::F32).unwrap();
+        assert_eq!(f32::from_value(&narrow), 0.1f32);
+        let wide = float_to_float(&0.1f32.to_value(), FloatTy::F32, FloatTy::F64).unwrap();
+        assert_eq!(f64::from_value(&wide), 0.1f32 as f64);
+    }
+
+    #[test]
+    fn test_int_to_float() {
+        let value = Value::from_type(u64::MAX);
+        let float = int_to_float(&value, &RigidTy::Uint(UintTy::U64), FloatTy::F32).unwrap();
+        assert_eq!(f32::from_value(&float), u64::MAX as f32);
+        let value = Value::from_type(-3i8);
+        let float = int_to_float(&value, &RigidTy::Int(IntTy::I8), FloatTy::F64).unwrap();
+        assert_eq!(f64::from_value(&float), -3.0);
+    }
+}
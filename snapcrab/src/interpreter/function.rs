@@ -1,14 +1,28 @@
-use std::thread;
-
 use crate::memory::ThreadMemory;
-use crate::value::Value;
+use crate::ty::MonoType;
+use crate::value::{Value, uint_from_bytes};
 use anyhow::{Context, Result, anyhow, bail};
-use rustc_public::mir::mono::Instance;
-use rustc_public::mir::{BasicBlockIdx, Body, Operand, Place, StatementKind, TerminatorKind};
-use rustc_public::ty::{ConstantKind, MirConst, RigidTy, TyKind};
+use rustc_public::CrateDef;
+use rustc_public::mir::mono::{Instance, InstanceKind};
+use rustc_public::mir::{
+    BasicBlockIdx, Body, Mutability, NonDivergingIntrinsic, Operand, Place, RuntimeChecks,
+    StatementKind, TerminatorKind, UnwindAction,
+};
+use rustc_public::ty::{Abi, ClosureKind, ConstantKind, MirConst, RigidTy, Ty, TyKind};
 use tracing::{debug, info};
 
+use super::allocator::{AllocatorFn, SystemAllocFn};
+use super::check::CheckConfig;
+use super::native::jit::JitEngine;
+use super::panic::{
+    Abort, Panic, PanicFn, UnwindFn, backtrace_enabled, caller_location_arg, native_panic,
+    requires_caller_location, start_panic,
+};
 use super::rvalue::write_discriminant;
+use super::scheduler::{ThreadFailure, yield_point};
+use super::sync::SyncFn;
+use super::thread::ThreadFn;
+use super::thread_local::TlsFn;
 
 /// Function interpreter that executes MIR (Mid-level Intermediate Representation) code.
 ///
@@ -23,52 +37,118 @@ pub struct FnInterpreter<'a> {
     instance: Instance,
     /// MIR body containing the function's basic blocks and metadata
     body: &'a Body,
-    /// The number a call is in the stack during the unwinding
-    unwinding: &'a mut Option<u16>,
+    /// The number of frames an interpreter error has been reported for
+    error_depth: &'a mut Option<u16>,
+    /// The panic being propagated through this frame's cleanup blocks
+    unwinding: Option<Panic>,
 }
 
 /// Run the interpreter for the given instance.
 ///
-/// Uses a three-tier dispatch:
-/// 1. If the function has a MIR body, interpret it
-/// 2. If it's an intrinsic without a body, shim it
+/// Every call is a yield point of the thread scheduler, including the calls
+/// to atomic intrinsics and to the blocking operations of locks.
+///
+/// Panic entry points and the unwinding runtime are always handled by the
+/// interpreter panic runtime, and threads, blocking operations and
+/// thread-local destructors are managed by the interpreter. Other functions
+/// use a three-tier dispatch:
+/// 1. If the function or compiler shim has a MIR body, interpret it.
+///    Intrinsics with an interpreter shim skip their fallback body.
+/// 2. If it's an intrinsic without a body or an allocator entry point, shim it
 /// 3. Otherwise, call the native compiled version via symbol resolution
 pub fn invoke_fn(
     instance: Instance,
     memory: &mut ThreadMemory,
     args: Vec<Value>,
-    unwinding: &mut Option<u16>,
+    error_depth: &mut Option<u16>,
 ) -> Result<Value> {
-    // Tier 1: interpret MIR body if available
-    if instance.has_body() {
-        return memory.with_stack_frame(instance, |body, memory| {
+    yield_point(memory)?;
+
+    // Panics are raised by the interpreter, even if the entry point has a body.
+    if let Some(panic_fn) = PanicFn::from_instance(instance) {
+        return Err(panic_fn.eval(&args, instance, memory, error_depth));
+    }
+    if let Some(unwind_fn) = UnwindFn::from_instance(instance) {
+        return unwind_fn.eval(&args, instance, memory, error_depth);
+    }
+    if let Some(tls_fn) = TlsFn::from_instance(instance) {
+        return tls_fn.eval(&args, memory);
+    }
+    if let Some(alloc_fn) = SystemAllocFn::from_instance(instance) {
+        return alloc_fn.eval(&args, instance, memory);
+    }
+    if let Some(thread_fn) = ThreadFn::from_instance(instance) {
+        return thread_fn.eval(&args, instance, memory, error_depth);
+    }
+    if let Some(sync_fn) = SyncFn::from_instance(instance) {
+        return sync_fn.eval(&args, instance, memory);
+    }
+
+    // Virtual calls go through the vtable of the receiver, even if the trait
+    // method has a default body.
+    if let InstanceKind::Virtual { idx } = instance.kind {
+        let mut args = args;
+        let Some(callee) = virtual_callee(memory, idx, &mut args)? else {
+            return Ok(Value::unit().clone());
+        };
+        debug!(
+            "Virtual call to `{}` resolved to `{}`",
+            instance.name(),
+            callee.name()
+        );
+        return invoke_fn(callee, memory, args, error_depth);
+    }
+
+    // Intrinsic shims are cheaper than their fallback bodies.
+    let intrinsic = instance.intrinsic_name();
+    if let Some(name) = &intrinsic
+        && let Some(result) =
+            super::intrinsics::eval_intrinsic(name.as_str(), &args, instance, memory)?
+    {
+        return Ok(result);
+    }
+
+    // Tier 1: interpret MIR body if available, including compiler shims.
+    // Shims without a body go through the other tiers, like items do.
+    let body = if instance.has_body() || instance.kind == InstanceKind::Shim {
+        instance.body()
+    } else {
+        None
+    };
+    if let Some(body) = body {
+        return memory.with_stack_frame(instance, body, |body, memory| {
             let interpreter = FnInterpreter {
                 memory,
                 current_block: 0,
                 instance,
                 body,
-                unwinding,
+                error_depth,
+                unwinding: None,
             };
             interpreter.execute(args)
         });
     }
 
-    // Tier 2: intrinsic shims
-    if let Some(intrinsic) = instance.intrinsic_name() {
-        return super::intrinsics::eval_intrinsic(
-            intrinsic.as_str(),
-            &args,
-            instance,
-            &memory.check_config,
-        );
+    // Tier 2: intrinsics must either have a shim or a fallback body
+    if let Some(name) = intrinsic {
+        bail!("Unimplemented intrinsic `{name}` in `{}`", instance.name());
+    }
+
+    // Allocator entry points are served by the interpreter heap.
+    if let Some(alloc_fn) = AllocatorFn::from_instance(instance) {
+        return alloc_fn.eval(&args, memory);
     }
 
-    // Detect implicit arguments (e.g., #[track_caller] passes &Location).
+    // `#[track_caller]` functions take an implicit `&Location` argument.
     let fn_abi = instance.fn_abi()?;
+    let mut args = args;
+    if requires_caller_location(instance) && fn_abi.args.len() == args.len() + 1 {
+        let location_ref = fn_abi.args[args.len()].ty;
+        args.push(caller_location_arg(memory, location_ref)?);
+    }
     if fn_abi.args.len() > args.len() {
         bail!(
-            "Failed to invoke `{}`: function expects {} arguments but got {} \
-             (implicit arguments like #[track_caller] are not yet supported)",
+            "Failed to invoke `{}`: function expects {} arguments but got {}",
             instance.name(),
             fn_abi.args.len(),
             args.len()
@@ -76,11 +156,23 @@ pub fn invoke_fn(
     }
 
     // Tier 3: native call via dlsym
+    run_native(memory, error_depth, &instance.name(), |config, jit| {
+        super::native::call_native(instance, &args, config, jit)
+    })
+}
+
+/// Run a call to native code, turning its panics into interpreted panics.
+fn run_native(
+    memory: &mut ThreadMemory,
+    error_depth: &mut Option<u16>,
+    name: &str,
+    native_call: impl FnOnce(&CheckConfig, &JitEngine) -> Result<Value>,
+) -> Result<Value> {
     let config = memory.check_config.clone();
-    let jit = &memory.jit;
-    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        super::native::call_native(instance, &args, &config, jit)
-    }));
+    let jit = memory.jit.clone();
+    let result = super::native::with_callbacks(memory, error_depth, || {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| native_call(&config, &jit)))
+    })?;
     match result {
         Ok(val) => val,
         Err(panic) => {
@@ -91,11 +183,42 @@ pub fn invoke_fn(
             } else {
                 "native function panicked".to_string()
             };
-            bail!("Native call to `{}` panicked: {msg}", instance.name())
+            debug!("Native call to `{name}` panicked: {msg}");
+            Err(native_panic(memory, msg))
         }
     }
 }
 
+/// Find the implementation of a virtual call in the vtable of its receiver.
+///
+/// The receiver is a wide pointer, possibly wrapped in a smart pointer such as
+/// `Box<Self>`. It is replaced by its data pointer, which is what the concrete
+/// implementation expects. Returns `None` for the drop entry of a type without
+/// drop glue, which the compiler leaves empty.
+pub(super) fn virtual_callee(
+    memory: &ThreadMemory,
+    idx: usize,
+    args: &mut [Value],
+) -> Result<Option<Instance>> {
+    let ptr_width = crate::memory::pointer_width();
+    let receiver = args
+        .first()
+        .context("Virtual call without a receiver")?
+        .as_bytes();
+    if receiver.len() < 2 * ptr_width {
+        bail!("Virtual call receiver is not a wide pointer");
+    }
+    let vtable = uint_from_bytes(&receiver[ptr_width..2 * ptr_width]) as usize;
+    let entry = memory
+        .read_bytes(vtable + idx * ptr_width, ptr_width)?
+        .read_uint() as usize;
+    args[0] = Value::from_bytes(&receiver[..ptr_width]);
+    if entry == 0 && idx == 0 {
+        return Ok(None);
+    }
+    memory.resolve_fn_ptr(entry).map(Some)
+}
+
 impl FnInterpreter<'_> {
     /// Executes the function by interpreting its MIR basic blocks.
     ///
@@ -111,15 +234,15 @@ impl FnInterpreter<'_> {
         info!("Starting interpretation of {}", self.instance.name());
 
         // Ensure argument count matches expected
-        debug_assert_eq!(
-            args.len(),
-            self.body.arg_locals().len(),
+        debug_assert!(
+            args.len() == self.body.arg_locals().len() || self.body.spread_arg().is_some(),
             "Argument count mismatch: expected {}, got {}",
             self.body.arg_locals().len(),
             args.len()
         );
 
         // Initialize arguments in locals (skip local 0 which is return value)
+        let args = self.spread_args(args)?;
         for (i, arg) in args.into_iter().enumerate() {
             self.memory.write_local(i + 1, arg)?;
         }
@@ -135,9 +258,10 @@ impl FnInterpreter<'_> {
                     .map_err(|e| self.statement_error(current_block_idx, stmt_idx, e))?;
             }
 
-            // Execute terminator
+            // Execute terminator, unwinding to its cleanup block if it panics
             match self
                 .execute_terminator(current_block_idx)
+                .or_else(|e| self.unwind(current_block_idx, e))
                 .map_err(|e| self.terminator_error(current_block_idx, e))?
             {
                 ControlFlow::Continue(next_block) => {
@@ -156,32 +280,32 @@ impl FnInterpreter<'_> {
         self.body.locals()
     }
 
-    /// Prints the error including the failing thread
+    /// Report an interpreter error with the location where it happened.
+    ///
+    /// Panics and aborts raised by the interpreted program were already
+    /// reported when they started, and so were the failures of scheduled
+    /// threads, so they are propagated unchanged.
     fn generate_error(
         &mut self,
         span: rustc_public::ty::Span,
         error: anyhow::Error,
     ) -> anyhow::Error {
-        let include_backtrace = match std::env::var("RUST_BACKTRACE").as_deref() {
-            Err(_) | Ok("0") => false,
-            Ok(_) => true,
-        };
+        if error.is::<Panic>() || error.is::<Abort>() || error.is::<ThreadFailure>() {
+            return error;
+        }
 
-        if !include_backtrace && self.unwinding.is_some() {
-            // In compact mode and already unwinding
+        let include_backtrace = backtrace_enabled();
+        if !include_backtrace && self.error_depth.is_some() {
+            // In compact mode and already reported
             return error;
         }
 
-        let msg = if self.unwinding.is_none() {
-            let tname = thread::current().name().map_or_else(
-                || format!("thread_{:?}", thread::current().id()),
-                str::to_string,
-            );
+        let msg = if self.error_depth.is_none() {
             let span_info = span.diagnostic();
-            let msg = format!("thread '{tname}' panicked at {span_info}\n{}", error);
+            let msg = format!("error: {error}\n  --> {span_info}");
             if std::env::var("RUST_BACKTRACE").is_err() {
                 format!(
-                    "{msg}\n note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"
+                    "{msg}\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"
                 )
             } else {
                 msg
@@ -190,8 +314,8 @@ impl FnInterpreter<'_> {
             error.to_string()
         };
 
-        let current = *self.unwinding.get_or_insert_default();
-        *self.unwinding = Some(current + 1);
+        let current = *self.error_depth.get_or_insert_default();
+        *self.error_depth = Some(current + 1);
 
         if include_backtrace {
             // Append stack trace
@@ -210,7 +334,7 @@ impl FnInterpreter<'_> {
         stmt_idx: usize,
         error: anyhow::Error,
     ) -> anyhow::Error {
-        assert!(self.unwinding.is_none()); // Unwinding should only be for terminator.
+        assert!(self.error_depth.is_none()); // Errors propagate only through terminators.
         let span = self.body.blocks[bb_idx].statements[stmt_idx].span;
         self.generate_error(span, error)
     }
@@ -249,6 +373,35 @@ impl FnInterpreter<'_> {
                 write_discriminant(enum_val.as_bytes_mut(), enum_ty, *variant_index)?;
                 self.memory.write_addr(addr, enum_val.as_bytes(), enum_ty)?;
             }
+            StatementKind::Intrinsic(NonDivergingIntrinsic::Assume(operand)) => {
+                let value = self.evaluate_operand(operand)?;
+                if !value
+                    .as_bool()
+                    .context("Assume operand must be a boolean")?
+                {
+                    bail!("Undefined behavior: assumption violated");
+                }
+            }
+            StatementKind::Intrinsic(NonDivergingIntrinsic::CopyNonOverlapping(copy)) => {
+                let src = self.evaluate_operand(&copy.src)?;
+                let dst = self.evaluate_operand(&copy.dst)?;
+                let count = self.evaluate_operand(&copy.count)?;
+                let elem_ty = copy
+                    .src
+                    .ty(self.locals())?
+                    .kind()
+                    .builtin_deref(true)
+                    .context("Expected a pointer to copy from")?
+                    .ty;
+                super::intrinsics::copy(
+                    self.memory,
+                    src.read_uint() as usize,
+                    dst.read_uint() as usize,
+                    count.read_uint() as usize,
+                    elem_ty,
+                    true,
+                )?;
+            }
             StatementKind::StorageLive(_) | StatementKind::StorageDead(_) => {
                 // Ignore storage annotations for now
             }
@@ -309,11 +462,12 @@ impl FnInterpreter<'_> {
                 target,
                 ..
             } => {
+                self.memory.set_call_site(terminator.span);
                 self.execute_call(&func, &args, &destination)?;
 
                 match target {
                     Some(target_bb) => Ok(ControlFlow::Continue(target_bb)),
-                    None => bail!("Diverging calls not yet supported"),
+                    None => bail!("Diverging function call returned"),
                 }
             }
             TerminatorKind::Assert {
@@ -321,7 +475,7 @@ impl FnInterpreter<'_> {
                 expected,
                 target,
                 msg,
-                ..
+                unwind,
             } => {
                 let cond_value = self.evaluate_operand(&cond)?;
                 let cond_bool = cond_value
@@ -331,12 +485,30 @@ impl FnInterpreter<'_> {
                 if cond_bool == expected {
                     Ok(ControlFlow::Continue(target))
                 } else {
-                    let msg_str = msg
-                        .description()
-                        .unwrap_or("Failed to get assert description");
-                    bail!("Assertion failed: {}", msg_str);
+                    // Checks that cannot unwind, like the invalid enum
+                    // construction check, abort after reporting the panic.
+                    let can_unwind = unwind != UnwindAction::Unreachable;
+                    let message = self.assert_message(&msg)?;
+                    self.memory.set_call_site(terminator.span);
+                    Err(start_panic(self.memory, message, can_unwind))
                 }
             }
+            TerminatorKind::Drop { place, target, .. } => {
+                self.memory.set_call_site(terminator.span);
+                self.execute_drop(&place)?;
+                Ok(ControlFlow::Continue(target))
+            }
+            TerminatorKind::Resume => match self.unwinding.take() {
+                Some(panic) => Err(anyhow::Error::new(panic)),
+                None => bail!("Resumed unwinding outside of a cleanup block"),
+            },
+            TerminatorKind::Abort => {
+                // The compiler inserts this when unwinding would leave a
+                // function that cannot unwind.
+                self.memory.set_call_site(terminator.span);
+                let message = "panic in a function that cannot unwind".to_string();
+                Err(start_panic(self.memory, message, false))
+            }
             TerminatorKind::Unreachable => {
                 bail!("Entered unreachable code");
             }
@@ -355,26 +527,49 @@ impl FnInterpreter<'_> {
     ) -> Result<()> {
         // Evaluate arguments
         let arg_values: Result<Vec<Value>> =
-            args.iter().map(|arg| self.evaluate_operand(arg)).collect();
-        let arg_values = arg_values?;
+            args.iter().map(|arg| self.evaluate_arg(arg)).collect();
+        let mut arg_values = arg_values?;
+
+        // The "rust-call" ABI passes the elements of the trailing tuple
+        // argument as separate arguments.
+        let func_ty = func.ty(self.locals())?;
+        let is_rust_call = func_ty
+            .kind()
+            .fn_sig()
+            .is_some_and(|sig| sig.value.abi == Abi::RustCall);
+        if is_rust_call && let Some(tuple) = arg_values.pop() {
+            let tuple_ty = args.last().unwrap().ty(self.locals())?;
+            arg_values.extend(tuple.tuple_fields(tuple_ty)?);
+        }
 
         // Resolve function instance
-        let func_instance = match func {
-            Operand::Constant(const_op) => {
-                // Extract instance from constant type
-                let func_ty = const_op.ty();
-                match func_ty.kind() {
-                    TyKind::RigidTy(RigidTy::FnDef(def_id, args)) => {
-                        Instance::resolve(def_id, &args)?
+        let func_instance = match func_ty.kind() {
+            TyKind::RigidTy(RigidTy::FnDef(def_id, args)) => Instance::resolve(def_id, &args)?,
+            TyKind::RigidTy(RigidTy::FnPtr(sig)) => {
+                let fn_ptr = self.evaluate_operand(func)?.read_uint() as usize;
+                match self.fn_ptr_callee(fn_ptr, &mut arg_values)? {
+                    Some(instance) => instance,
+                    None => {
+                        let name = format!("0x{fn_ptr:x}");
+                        let result =
+                            run_native(self.memory, self.error_depth, &name, |config, jit| {
+                                super::native::call_native_fn_ptr(
+                                    fn_ptr,
+                                    sig,
+                                    &arg_values,
+                                    config,
+                                    jit,
+                                )
+                            })?;
+                        return self.assign_to_place(destination, result);
                     }
-                    _ => bail!("Unsupported function type: {:?}", func_ty),
                 }
             }
-            _ => bail!("Only constant function operands supported"),
+            _ => bail!("Unsupported function type: {func_ty}"),
         };
 
         // Create new interpreter and call function
-        let result = invoke_fn(func_instance, self.memory, arg_values, self.unwinding)?;
+        let result = invoke_fn(func_instance, self.memory, arg_values, self.error_depth)?;
 
         // Store result in destination
         self.assign_to_place(destination, result)?;
@@ -382,6 +577,118 @@ impl FnInterpreter<'_> {
         Ok(())
     }
 
+    /// Evaluate a call argument.
+    ///
+    /// Unsized values, like the `dyn FnOnce` moved out of a box to call it,
+    /// are passed by reference, as compiled code does.
+    fn evaluate_arg(&mut self, arg: &Operand) -> Result<Value> {
+        if let Operand::Move(place) = arg {
+            let ty = place.ty(self.locals())?;
+            if !ty.layout()?.shape().is_sized() {
+                return self.place_to_ptr(place, Ty::new_ptr(ty, Mutability::Mut));
+            }
+        }
+        self.evaluate_operand(arg)
+    }
+
+    /// Collect the untupled "rust-call" arguments back into a tuple.
+    ///
+    /// Bodies with a spread argument, such as the `Fn*` shims, expect the
+    /// trailing arguments to be stored in a single tuple local.
+    fn spread_args(&self, mut args: Vec<Value>) -> Result<Vec<Value>> {
+        let Some(spread) = self.body.spread_arg() else {
+            return Ok(args);
+        };
+        let tuple_ty = self.body.locals()[spread].ty;
+        let rest = args.split_off(spread - 1);
+        args.push(Value::from_tuple_with_layout(&rest, tuple_ty)?);
+        Ok(args)
+    }
+
+    /// Resolve the function called through a function pointer.
+    ///
+    /// Returns `None` if the pointer was created by native code, which must
+    /// be called natively.
+    ///
+    /// Pointers created from closures point to their `FnOnce::call_once`
+    /// shim, which expects the closure by value. Instead, the closure body is
+    /// called directly with the closure environment as an extra first
+    /// argument. Only closures without captures can be turned into function
+    /// pointers, so the environment is empty and a dangling pointer can refer
+    /// to it.
+    fn fn_ptr_callee(&self, fn_ptr: usize, args: &mut Vec<Value>) -> Result<Option<Instance>> {
+        let Some(instance) = self.memory.resolve_any_fn_ptr(fn_ptr)? else {
+            return Ok(None);
+        };
+        let Some((closure, closure_ty)) = closure_fn_ptr_body(instance)? else {
+            return Ok(Some(instance));
+        };
+        let env_ty = closure.fn_abi()?.args[0].ty;
+        let env = if env_ty == closure_ty {
+            Value::unit().clone()
+        } else {
+            Value::dangling_ptr(closure_ty.alignment()?)
+        };
+        args.insert(0, env);
+        Ok(Some(closure))
+    }
+
+    /// Drop the value stored in `place` by running its drop glue.
+    ///
+    /// This invokes the `drop_in_place::<T>` shim with a mutable pointer to the
+    /// place. Types without drop glue resolve to an empty shim and are skipped.
+    fn execute_drop(&mut self, place: &Place) -> Result<()> {
+        let place_ty = place.ty(self.locals())?;
+        if let TyKind::RigidTy(RigidTy::Dynamic(..)) = place_ty.kind() {
+            // The drop glue of a trait object comes from its vtable.
+            let ptr_ty = Ty::new_ptr(place_ty, Mutability::Mut);
+            let mut args = vec![self.place_to_ptr(place, ptr_ty)?];
+            if let Some(drop_fn) = virtual_callee(self.memory, 0, &mut args)? {
+                debug!("Dropping `{place_ty}` with `{}`", drop_fn.name());
+                invoke_fn(drop_fn, self.memory, args, self.error_depth)?;
+            }
+            return Ok(());
+        }
+        let drop_fn = Instance::resolve_drop_in_place(place_ty);
+        if drop_fn.is_empty_shim() {
+            return Ok(());
+        }
+        debug!("Dropping `{place_ty}` with `{}`", drop_fn.name());
+        let ptr_ty = Ty::new_ptr(place_ty, Mutability::Mut);
+        let ptr = self.place_to_ptr(place, ptr_ty)?;
+        invoke_fn(drop_fn, self.memory, vec![ptr], self.error_depth)?;
+        Ok(())
+    }
+
+    /// Follow the unwind action of a terminator that raised `error`.
+    ///
+    /// Only panics unwind. If the terminator has a cleanup block, the panic
+    /// is stored until a `Resume` terminator propagates it to the caller.
+    fn unwind(&mut self, bb_idx: BasicBlockIdx, error: anyhow::Error) -> Result<ControlFlow> {
+        let panic = error.downcast::<Panic>()?;
+        let terminator = &self.body.blocks[bb_idx].terminator;
+        match terminator.kind.unwind() {
+            Some(UnwindAction::Cleanup(cleanup)) => {
+                debug!("Unwinding to cleanup block {cleanup}");
+                self.unwinding = Some(panic);
+                Ok(ControlFlow::Continue(*cleanup))
+            }
+            Some(UnwindAction::Terminate) => {
+                let message = if self.unwinding.is_some() {
+                    "panic in a destructor during cleanup"
+                } else {
+                    "panic in a function that cannot unwind"
+                };
+                self.memory.set_call_site(terminator.span);
+                Err(start_panic(self.memory, message.to_string(), false))
+            }
+            Some(UnwindAction::Unreachable) => {
+                bail!("Undefined behavior: unwinding out of a call that cannot unwind")
+            }
+            Some(UnwindAction::Continue) | None => Err(anyhow::Error::new(panic)),
+        }
+    }
+
     /// Evaluates an operand to produce a value.
     ///
     /// # Arguments
@@ -394,8 +701,15 @@ impl FnInterpreter<'_> {
         match operand {
             Operand::Copy(place) | Operand::Move(place) => self.read_from_place(place),
             Operand::Constant(const_op) => self.evaluate_constant(&const_op.const_),
-            // TODO: Get the value from rustc_public when a function is available
-            Operand::RuntimeChecks(_) => Ok(Value::from_bool(true)),
+            Operand::RuntimeChecks(checks) => {
+                let config = &self.memory.check_config;
+                let enabled = match checks {
+                    RuntimeChecks::UbChecks => config.ub_checks,
+                    RuntimeChecks::OverflowChecks => config.overflow_checks,
+                    RuntimeChecks::ContractChecks => config.contract_checks,
+                };
+                Ok(Value::from_bool(enabled))
+            }
         }
     }
 
@@ -409,17 +723,7 @@ impl FnInterpreter<'_> {
     /// * `Err(anyhow::Error)` - If constant evaluation fails or type is unsupported
     fn evaluate_constant(&self, const_: &MirConst) -> Result<Value> {
         match const_.kind() {
-            ConstantKind::Allocated(alloc) => {
-                let mut bytes = alloc.raw_bytes()?;
-                // Resolve provenance entries (pointers to other allocations).
-                let ptr_size = crate::memory::pointer_width();
-                for (offset, prov) in &alloc.provenance.ptrs {
-                    let addr = self.memory.resolve_alloc(prov.0)?;
-                    let addr_bytes = addr.to_le_bytes();
-                    bytes[*offset..*offset + ptr_size].copy_from_slice(&addr_bytes[..ptr_size]);
-                }
-                Ok(Value::from_bytes(&bytes))
-            }
+            ConstantKind::Allocated(alloc) => self.memory.allocation_value(alloc, const_.ty()),
             ConstantKind::ZeroSized => Ok(Value::unit().clone()),
             ConstantKind::Ty(ty_const) => {
                 bail!("Unexpected type constant: {:?}", ty_const);
@@ -434,6 +738,28 @@ impl FnInterpreter<'_> {
     }
 }
 
+/// The closure body behind a function pointer created from a closure, along
+/// with the closure type.
+fn closure_fn_ptr_body(instance: Instance) -> Result<Option<(Instance, Ty)>> {
+    if let TyKind::RigidTy(RigidTy::Closure(..)) = instance.ty().kind() {
+        return Ok(Some((instance, instance.ty())));
+    }
+    if !matches!(
+        instance.def.name().as_str(),
+        "std::ops::FnOnce::call_once" | "core::ops::FnOnce::call_once"
+    ) {
+        return Ok(None);
+    }
+    let self_ty = instance.args().0.first().and_then(|arg| arg.ty().copied());
+    match self_ty.map(|ty| (ty, ty.kind())) {
+        Some((ty, TyKind::RigidTy(RigidTy::Closure(def, args)))) => Ok(Some((
+            Instance::resolve_closure(def, &args, ClosureKind::Fn)?,
+            ty,
+        ))),
+        _ => Ok(None),
+    }
+}
+
 /// Control flow result from executing a terminator instruction.
 #[derive(Debug)]
 pub enum ControlFlow {
@@ -2,42 +2,212 @@
 //!
 //! Intrinsics that don't have MIR fallback bodies are handled here.
 //! This is the irreducible set that neither interpretation nor native calls can provide.
+//! Shims take precedence over fallback bodies, so intrinsics on hot paths,
+//! such as the integer ones, are shimmed as well.
 
-use crate::interpreter::check::{CheckConfig, validate_value};
+mod atomic;
+mod float;
+mod int;
+mod mem;
+
+use crate::interpreter::check::{is_uninhabited, is_zero_valid, validate_value};
+use crate::interpreter::panic::{Abort, start_panic};
+use crate::interpreter::rvalue::read_discriminant;
+use crate::interpreter::scheduler::preemption_point;
+use crate::memory::ThreadMemory;
+use crate::ty::MonoType;
 use crate::value::Value;
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
+use rustc_public::abi::FieldsShape;
+use rustc_public::mir::BinOp;
 use rustc_public::mir::mono::Instance;
-use rustc_public::ty::{RigidTy, TyKind};
+use rustc_public::ty::{RigidTy, Ty, TyKind};
 use tracing::debug;
 
+pub(super) use int::eval_overflowing_binop;
+pub(super) use mem::copy;
+
 /// Evaluate a compiler intrinsic.
+///
+/// Returns `None` if the intrinsic has no shim.
 pub fn eval_intrinsic(
     name: &str,
     args: &[Value],
     instance: Instance,
-    config: &CheckConfig,
-) -> Result<Value> {
+    memory: &mut ThreadMemory,
+) -> Result<Option<Value>> {
     debug!("Intrinsic: {name}");
-    match name {
+    let result = match name {
         "assume" => {
             let val = args[0].as_bool().unwrap();
             if !val {
                 bail!("Assumption violated in `{}`", instance.name());
             }
-            Ok(Value::unit().clone())
+            Value::unit().clone()
         }
-        "likely" | "unlikely" => Ok(args[0].clone()),
+        "likely" | "unlikely" => args[0].clone(),
         "transmute" | "transmute_unchecked" => {
             let result = args[0].clone();
             // Validate that the transmuted value is valid for the target type
             let ret_ty = transmute_return_ty(instance)?;
-            validate_value(&result, ret_ty, config)?;
-            Ok(result)
+            validate_value(&result, ret_ty, &memory.check_config)?;
+            result
+        }
+        "forget" => Value::unit().clone(),
+        "needs_drop" => {
+            // The answer only depends on the type, so let the compiler evaluate it.
+            let alloc = instance.try_const_eval(Ty::bool_ty())?;
+            Value::from_bytes(&alloc.raw_bytes()?)
+        }
+        "variant_count" => Value::from_type(variant_count(generic_ty_arg(instance, 0)?)),
+        "discriminant_value" => {
+            let ty = generic_ty_arg(instance, 0)?;
+            if ty.kind().is_enum() {
+                let value = memory.read_addr(args[0].read_uint() as usize, ty)?;
+                read_discriminant(&value, ty)?
+            } else {
+                // Other types have a single variant with discriminant zero.
+                Value::with_size(instance.fn_abi()?.ret.ty.size()?)
+            }
+        }
+        "black_box" => args[0].clone(),
+        // The fallback bodies return the configuration `core` was built with.
+        "ub_checks" => Value::from_bool(memory.check_config.ub_checks),
+        "overflow_checks" => Value::from_bool(memory.check_config.overflow_checks),
+        "contract_checks" => Value::from_bool(memory.check_config.contract_checks),
+        "abort" => return Err(anyhow::Error::new(Abort::default())),
+        "assert_inhabited" | "assert_zero_valid" | "assert_mem_uninitialized_valid" => {
+            // Compiled code panics without unwinding if the assertion fails.
+            let ty = generic_ty_arg(instance, 0)?;
+            if is_uninhabited(ty)? {
+                let message = format!("attempted to instantiate uninhabited type `{ty}`");
+                return Err(start_panic(memory, message, false));
+            }
+            if name == "assert_zero_valid" && !is_zero_valid(ty)? {
+                let message = format!("attempted to zero-initialize type `{ty}`, which is invalid");
+                return Err(start_panic(memory, message, false));
+            }
+            Value::unit().clone()
+        }
+        "size_of_val" | "align_of_val" => {
+            let ty = generic_ty_arg(instance, 0)?;
+            let metadata = args[0].ptr_metadata()?;
+            let metadata = (!metadata.is_unit()).then(|| metadata.read_uint() as usize);
+            let (size, align) = size_and_align_of(memory, ty, metadata)?;
+            let result = if name == "size_of_val" { size } else { align };
+            Value::from_type(result)
+        }
+        "vtable_size" | "vtable_align" => {
+            let vtable = args[0].read_uint() as usize;
+            let ptr_width = crate::memory::pointer_width();
+            let offset = if name == "vtable_size" { 1 } else { 2 };
+            memory.read_bytes(vtable + offset * ptr_width, ptr_width)?
+        }
+        "add_with_overflow" | "sub_with_overflow" | "mul_with_overflow" => {
+            let op = match name {
+                "add_with_overflow" => BinOp::Add,
+                "sub_with_overflow" => BinOp::Sub,
+                _ => BinOp::Mul,
+            };
+            let ty = generic_ty_arg(instance, 0)?;
+            let (result, overflow) = eval_overflowing_binop(op, &args[0], &args[1], ty)?;
+            let ret_ty = instance.fn_abi()?.ret.ty;
+            Value::from_tuple_with_layout(&[result, Value::from_bool(overflow)], ret_ty)?
+        }
+        _ => {
+            let ty = generic_ty_arg(instance, 0).ok();
+            if name.starts_with("atomic_") {
+                // A thread may spin on an atomic while it waits for another.
+                preemption_point(memory)?;
+                let ret_ty = instance.fn_abi()?.ret.ty;
+                return atomic::eval_atomic_intrinsic(name, args, ty, ret_ty, memory);
+            }
+            if let Some(result) = mem::eval_mem_intrinsic(name, args, ty, memory)? {
+                return Ok(Some(result));
+            }
+            let second_ty = generic_ty_arg(instance, 1).ok();
+            if let Some(result) = float::eval_float_intrinsic(name, args, ty, second_ty)? {
+                return Ok(Some(result));
+            }
+            return match ty {
+                Some(ty) if ty.kind().is_integral() => {
+                    int::eval_int_intrinsic(name, args, ty, second_ty)
+                }
+                _ => Ok(None),
+            };
+        }
+    };
+    Ok(Some(result))
+}
+
+/// The number of variants of `ty`, which is zero for types that are not ADTs.
+fn variant_count(ty: Ty) -> usize {
+    match ty.kind() {
+        TyKind::RigidTy(RigidTy::Adt(def, _)) => def.num_variants(),
+        TyKind::RigidTy(RigidTy::Pat(base, _)) => variant_count(base),
+        _ => 0,
+    }
+}
+
+/// Compute the size and alignment of a value of type `ty`.
+///
+/// The metadata of the pointer to the value is required for unsized types.
+/// It is the length of slices and `str`, or the vtable of trait objects.
+/// Structs with an unsized tail add the dynamic size of the tail to the
+/// offset of the last field, rounded up to the dynamic alignment of the tail.
+pub(super) fn size_and_align_of(
+    memory: &ThreadMemory,
+    ty: Ty,
+    metadata: Option<usize>,
+) -> Result<(usize, usize)> {
+    let Some(metadata) = metadata else {
+        return Ok((ty.size()?, ty.alignment()?));
+    };
+    match ty.kind() {
+        TyKind::RigidTy(RigidTy::Dynamic(..)) => {
+            let ptr_width = crate::memory::pointer_width();
+            let size = memory.read_bytes(metadata + ptr_width, ptr_width)?;
+            let align = memory.read_bytes(metadata + 2 * ptr_width, ptr_width)?;
+            Ok((size.read_uint() as usize, align.read_uint() as usize))
+        }
+        TyKind::RigidTy(RigidTy::Slice(elem_ty)) => {
+            Ok((elem_ty.size()? * metadata, elem_ty.alignment()?))
+        }
+        TyKind::RigidTy(RigidTy::Str) => Ok((metadata, 1)),
+        TyKind::RigidTy(RigidTy::Adt(def, args)) => {
+            let variants = def.variants();
+            let tail = variants[0]
+                .fields()
+                .pop()
+                .with_context(|| format!("Expected an unsized field in `{ty}`"))?;
+            let layout = ty.layout()?.shape();
+            let FieldsShape::Arbitrary { offsets } = &layout.fields else {
+                bail!("Unexpected layout for unsized type `{ty}`");
+            };
+            let (tail_size, tail_align) =
+                size_and_align_of(memory, tail.ty_with_args(&args), Some(metadata))?;
+            // The static offset only accounts for the static alignment of the
+            // tail, while a trait object may be aligned more.
+            let offset = offsets
+                .last()
+                .map_or(0, |offset| offset.bytes())
+                .next_multiple_of(tail_align);
+            let align = tail_align.max(layout.abi_align as usize);
+            Ok(((offset + tail_size).next_multiple_of(align), align))
         }
-        "forget" => Ok(Value::unit().clone()),
-        "needs_drop" => Ok(Value::from_bool(false)),
-        "black_box" => Ok(args[0].clone()),
-        _ => bail!("Unimplemented intrinsic `{name}` in `{}`", instance.name()),
+        _ => bail!("Cannot compute the size of unsized type `{ty}`"),
+    }
+}
+
+/// Extract the type of the generic argument at `idx` from an intrinsic instance.
+fn generic_ty_arg(instance: Instance, idx: usize) -> Result<Ty> {
+    match instance.ty().kind() {
+        TyKind::RigidTy(RigidTy::FnDef(_, args)) => args
+            .0
+            .get(idx)
+            .and_then(|arg| arg.ty().copied())
+            .with_context(|| format!("Missing type argument {idx} in `{}`", instance.name())),
+        _ => bail!("Unexpected intrinsic type for `{}`", instance.name()),
     }
 }
 
@@ -0,0 +1,250 @@
+//! Atomic intrinsics and fences.
+//!
+//! Interpreter addresses are real process memory, so atomic operations are
+//! performed with host atomics directly on the address. This keeps them atomic
+//! with respect to native code that accesses the same location.
+//!
+//! The memory ordering requested by the intrinsic is ignored, and every
+//! operation uses `SeqCst`, which is at least as strong as any other ordering.
+
+use crate::memory::ThreadMemory;
+use crate::ty::MonoType;
+use crate::value::Value;
+use anyhow::{Context, Result, bail};
+use rustc_public::ty::Ty;
+use std::sync::atomic::{self, AtomicU8, AtomicU16, AtomicU32, AtomicU64, Ordering};
+
+/// Evaluate the atomic intrinsic `name` on values of type `ty`.
+///
+/// `ret_ty` is the return type of the intrinsic, which is needed to build the
+/// result of compare-exchange operations. Returns `None` if `name` is not an
+/// atomic intrinsic.
+pub(super) fn eval_atomic_intrinsic(
+    name: &str,
+    args: &[Value],
+    ty: Option<Ty>,
+    ret_ty: Ty,
+    memory: &ThreadMemory,
+) -> Result<Option<Value>> {
+    if let "atomic_fence" | "atomic_singlethreadfence" = name {
+        if name == "atomic_fence" {
+            atomic::fence(Ordering::SeqCst);
+        } else {
+            atomic::compiler_fence(Ordering::SeqCst);
+        }
+        return Ok(Some(Value::unit().clone()));
+    }
+    let Some(op) = AtomicOp::from_name(name) else {
+        return Ok(None);
+    };
+    let ty = ty.context("Missing atomic value type")?;
+    let size = ty.size()?;
+    let address = args[0]
+        .as_type::<usize>()
+        .context("Expected a pointer to an atomic value")?;
+    memory.check_atomic_access(address, size, op != AtomicOp::Load)?;
+    // SAFETY: the access was checked to be in bounds, aligned and writable if
+    // it modifies memory.
+    let atomic = unsafe { HostAtomic::new(address, size) }?;
+    let to_value = |bits: u64| Value::from_bytes(&bits.to_le_bytes()[..size]);
+    let arg = |idx: usize| args[idx].read_uint() as u64;
+
+    let result = match op {
+        AtomicOp::Load => to_value(atomic.load()),
+        AtomicOp::Store => {
+            atomic.store(arg(1));
+            Value::unit().clone()
+        }
+        AtomicOp::Exchange => to_value(atomic.swap(arg(1))),
+        AtomicOp::CompareExchange => {
+            let (old, success) = match atomic.compare_exchange(arg(1), arg(2)) {
+                Ok(old) => (old, true),
+                Err(old) => (old, false),
+            };
+            Value::from_tuple_with_layout(&[to_value(old), Value::from_bool(success)], ret_ty)?
+        }
+        AtomicOp::Fetch(fetch_op) => {
+            let operand = arg(1);
+            let mut current = atomic.load();
+            loop {
+                let new = fetch_op.apply(current, operand, size);
+                match atomic.compare_exchange(current, new) {
+                    Ok(old) => break to_value(old),
+                    Err(old) => current = old,
+                }
+            }
+        }
+    };
+    Ok(Some(result))
+}
+
+/// The atomic operations that access memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AtomicOp {
+    Load,
+    Store,
+    Exchange,
+    CompareExchange,
+    Fetch(FetchOp),
+}
+
+/// The read-modify-write operations that return the previous value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FetchOp {
+    Add,
+    Sub,
+    And,
+    Nand,
+    Or,
+    Xor,
+    Max,
+    Min,
+    UMax,
+    UMin,
+}
+
+impl AtomicOp {
+    fn from_name(name: &str) -> Option<Self> {
+        let op = match name {
+            "atomic_load" => AtomicOp::Load,
+            "atomic_store" => AtomicOp::Store,
+            "atomic_xchg" => AtomicOp::Exchange,
+            // A weak compare-exchange is allowed to fail spuriously, but it never does here.
+            "atomic_cxchg" | "atomic_cxchgweak" => AtomicOp::CompareExchange,
+            "atomic_xadd" => AtomicOp::Fetch(FetchOp::Add),
+            "atomic_xsub" => AtomicOp::Fetch(FetchOp::Sub),
+            "atomic_and" => AtomicOp::Fetch(FetchOp::And),
+            "atomic_nand" => AtomicOp::Fetch(FetchOp::Nand),
+            "atomic_or" => AtomicOp::Fetch(FetchOp::Or),
+            "atomic_xor" => AtomicOp::Fetch(FetchOp::Xor),
+            "atomic_max" => AtomicOp::Fetch(FetchOp::Max),
+            "atomic_min" => AtomicOp::Fetch(FetchOp::Min),
+            "atomic_umax" => AtomicOp::Fetch(FetchOp::UMax),
+            "atomic_umin" => AtomicOp::Fetch(FetchOp::UMin),
+            _ => return None,
+        };
+        Some(op)
+    }
+}
+
+impl FetchOp {
+    /// Compute the new value of an atomic of `size` bytes.
+    ///
+    /// `Max` and `Min` compare signed values, while the other operations are
+    /// the same for both signed and unsigned types.
+    fn apply(self, current: u64, operand: u64, size: usize) -> u64 {
+        let bits = size as u32 * 8;
+        let mask = u64::MAX >> (64 - bits);
+        let sign_extend = |value: u64| ((value << (64 - bits)) as i64) >> (64 - bits);
+        let result = match self {
+            FetchOp::Add => current.wrapping_add(operand),
+            FetchOp::Sub => current.wrapping_sub(operand),
+            FetchOp::And => current & operand,
+            FetchOp::Nand => !(current & operand),
+            FetchOp::Or => current | operand,
+            FetchOp::Xor => current ^ operand,
+            FetchOp::Max => sign_extend(current).max(sign_extend(operand)) as u64,
+            FetchOp::Min => sign_extend(current).min(sign_extend(operand)) as u64,
+            FetchOp::UMax => current.max(operand),
+            FetchOp::UMin => current.min(operand),
+        };
+        result & mask
+    }
+}
+
+/// A host atomic of one of the supported sizes.
+enum HostAtomic<'a> {
+    U8(&'a AtomicU8),
+    U16(&'a AtomicU16),
+    U32(&'a AtomicU32),
+    U64(&'a AtomicU64),
+}
+
+/// Evaluate `$body` with `$a` bound to the host atomic and `$t` to its value type.
+macro_rules! with_host_atomic {
+    ($atomic:expr, |$a:ident, $t:ident| $body:expr) => {
+        match $atomic {
+            HostAtomic::U8($a) => {
+                #[allow(dead_code)]
+                type $t = u8;
+                $body
+            }
+            HostAtomic::U16($a) => {
+                #[allow(dead_code)]
+                type $t = u16;
+                $body
+            }
+            HostAtomic::U32($a) => {
+                #[allow(dead_code)]
+                type $t = u32;
+                $body
+            }
+            HostAtomic::U64($a) => {
+                #[allow(dead_code)]
+                type $t = u64;
+                $body
+            }
+        }
+    };
+}
+
+// The casts are no-ops for `AtomicU64`.
+#[allow(clippy::unnecessary_cast)]
+impl HostAtomic<'_> {
+    /// # Safety
+    ///
+    /// `address` must be valid for reads of `size` bytes, and aligned to
+    /// `size`. It must also be valid for writes if the atomic is modified.
+    unsafe fn new(address: usize, size: usize) -> Result<Self> {
+        // SAFETY: guaranteed by the caller.
+        let atomic = unsafe {
+            match size {
+                1 => HostAtomic::U8(AtomicU8::from_ptr(address as *mut u8)),
+                2 => HostAtomic::U16(AtomicU16::from_ptr(address as *mut u16)),
+                4 => HostAtomic::U32(AtomicU32::from_ptr(address as *mut u32)),
+                8 => HostAtomic::U64(AtomicU64::from_ptr(address as *mut u64)),
+                _ => bail!("Unsupported atomic access of {size} bytes"),
+            }
+        };
+        Ok(atomic)
+    }
+
+    fn load(&self) -> u64 {
+        with_host_atomic!(self, |a, T| a.load(Ordering::SeqCst) as u64)
+    }
+
+    fn store(&self, value: u64) {
+        with_host_atomic!(self, |a, T| a.store(value as T, Ordering::SeqCst))
+    }
+
+    fn swap(&self, value: u64) -> u64 {
+        with_host_atomic!(self, |a, T| a.swap(value as T, Ordering::SeqCst) as u64)
+    }
+
+    fn compare_exchange(&self, old: u64, new: u64) -> Result<u64, u64> {
+        with_host_atomic!(self, |a, T| a
+            .compare_exchange(old as T, new as T, Ordering::SeqCst, Ordering::SeqCst)
+            .map(|value| value as u64)
+            .map_err(|value| value as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_op_sizes() {
+        assert_eq!(FetchOp::Add.apply(0xFF, 1, 1), 0);
+        assert_eq!(FetchOp::Sub.apply(0, 1, 2), 0xFFFF);
+        assert_eq!(FetchOp::Nand.apply(0xF0, 0xFF, 1), 0x0F);
+    }
+
+    #[test]
+    fn test_fetch_op_signed_min_max() {
+        // -1 and 1 as `i8`.
+        assert_eq!(FetchOp::Max.apply(0xFF, 1, 1), 1);
+        assert_eq!(FetchOp::Min.apply(0xFF, 1, 1), 0xFF);
+        assert_eq!(FetchOp::UMax.apply(0xFF, 1, 1), 0xFF);
+    }
+}
@@ -0,0 +1,310 @@
+//! Floating-point math intrinsics.
+//!
+//! The intrinsics are evaluated with the same host methods that compiled code
+//! lowers them to, so results are bit-exact with the host. The non-deterministic
+//! intrinsics, such as `fmuladd`, always pick the same behavior.
+
+use crate::interpreter::float::{HostFloat, eval_float_binop, float_to_int, with_float_ty};
+use crate::ty::MonoType;
+use crate::value::Value;
+use anyhow::{Context, Result, bail};
+use rustc_public::mir::BinOp;
+use rustc_public::ty::{FloatTy, RigidTy, Ty, TyKind};
+
+/// Math operations of the host float types.
+trait FloatMath: HostFloat {
+    fn sqrt(self) -> Self;
+    fn powi(self, exp: i32) -> Self;
+    fn powf(self, exp: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn exp(self) -> Self;
+    fn exp2(self) -> Self;
+    fn ln(self) -> Self;
+    fn log2(self) -> Self;
+    fn log10(self) -> Self;
+    fn mul_add(self, a: Self, b: Self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn trunc(self) -> Self;
+    fn round(self) -> Self;
+    fn round_ties_even(self) -> Self;
+    fn abs(self) -> Self;
+    fn copysign(self, sign: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn minimum(self, other: Self) -> Self;
+    fn maximum(self, other: Self) -> Self;
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! float_math {
+    ($float:ty) => {
+        impl FloatMath for $float {
+            fn sqrt(self) -> Self {
+                <$float>::sqrt(self)
+            }
+
+            fn powi(self, exp: i32) -> Self {
+                <$float>::powi(self, exp)
+            }
+
+            fn powf(self, exp: Self) -> Self {
+                <$float>::powf(self, exp)
+            }
+
+            fn sin(self) -> Self {
+                <$float>::sin(self)
+            }
+
+            fn cos(self) -> Self {
+                <$float>::cos(self)
+            }
+
+            fn exp(self) -> Self {
+                <$float>::exp(self)
+            }
+
+            fn exp2(self) -> Self {
+                <$float>::exp2(self)
+            }
+
+            fn ln(self) -> Self {
+                <$float>::ln(self)
+            }
+
+            fn log2(self) -> Self {
+                <$float>::log2(self)
+            }
+
+            fn log10(self) -> Self {
+                <$float>::log10(self)
+            }
+
+            fn mul_add(self, a: Self, b: Self) -> Self {
+                <$float>::mul_add(self, a, b)
+            }
+
+            fn floor(self) -> Self {
+                <$float>::floor(self)
+            }
+
+            fn ceil(self) -> Self {
+                <$float>::ceil(self)
+            }
+
+            fn trunc(self) -> Self {
+                <$float>::trunc(self)
+            }
+
+            fn round(self) -> Self {
+                <$float>::round(self)
+            }
+
+            fn round_ties_even(self) -> Self {
+                <$float>::round_ties_even(self)
+            }
+
+            fn abs(self) -> Self {
+                <$float>::abs(self)
+            }
+
+            fn copysign(self, sign: Self) -> Self {
+                <$float>::copysign(self, sign)
+            }
+
+            fn min(self, other: Self) -> Self {
+                <$float>::min(self, other)
+            }
+
+            fn max(self, other: Self) -> Self {
+                <$float>::max(self, other)
+            }
+
+            fn minimum(self, other: Self) -> Self {
+                <$float>::minimum(self, other)
+            }
+
+            fn maximum(self, other: Self) -> Self {
+                <$float>::maximum(self, other)
+            }
+
+            fn is_finite(self) -> bool {
+                <$float>::is_finite(self)
+            }
+        }
+    };
+}
+
+float_math!(f16);
+float_math!(f32);
+float_math!(f64);
+float_math!(f128);
+
+/// Evaluate the float intrinsic `name`.
+///
+/// The float type is either a suffix of the name, e.g. `sqrtf64`, or the first
+/// generic argument `ty`. `int_ty` is the target type of `float_to_int_unchecked`.
+/// Returns `None` if `name` is not a float intrinsic.
+pub(super) fn eval_float_intrinsic(
+    name: &str,
+    args: &[Value],
+    ty: Option<Ty>,
+    int_ty: Option<Ty>,
+) -> Result<Option<Value>> {
+    if let Some((base, float_ty)) = split_float_suffix(name) {
+        return with_float_ty!(float_ty, float_math(base, args));
+    }
+    let Some(float_ty) = ty.and_then(as_float_ty) else {
+        return Ok(None);
+    };
+    let result = match name {
+        "fabs" => with_float_ty!(float_ty, fabs(&args[0])),
+        "fadd_fast" | "fsub_fast" | "fmul_fast" | "fdiv_fast" | "frem_fast" => {
+            let op = arith_op(name);
+            let result = eval_float_binop(op, &args[0], &args[1], float_ty)?;
+            let finite = [&args[0], &args[1], &result]
+                .into_iter()
+                .all(|value| with_float_ty!(float_ty, is_finite(value)));
+            if !finite {
+                bail!("Undefined behavior: `{name}` with a non-finite operand or result");
+            }
+            result
+        }
+        "fadd_algebraic" | "fsub_algebraic" | "fmul_algebraic" | "fdiv_algebraic"
+        | "frem_algebraic" => eval_float_binop(arith_op(name), &args[0], &args[1], float_ty)?,
+        "float_to_int_unchecked" => {
+            let int_ty = int_ty.context("Missing integer type")?;
+            let TyKind::RigidTy(rigid) = int_ty.kind() else {
+                bail!("Expected an integer type, but found `{int_ty}`");
+            };
+            let bits = int_ty.size()? as u32 * 8;
+            let signed = matches!(rigid, RigidTy::Int(_));
+            if !with_float_ty!(float_ty, fits_int(&args[0], bits, signed)) {
+                bail!(
+                    "Undefined behavior: `float_to_int_unchecked` of a value that does not \
+                     fit in `{int_ty}`"
+                );
+            }
+            float_to_int(&args[0], float_ty, &rigid)?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+fn float_math<T: FloatMath>(base: &str, args: &[Value]) -> Result<Option<Value>> {
+    let arg = |idx: usize| T::from_value(&args[idx]);
+    let result = match base {
+        "sqrt" => arg(0).sqrt(),
+        "powi" => {
+            let exp = args[1]
+                .as_type::<i32>()
+                .context("Expected an `i32` exponent")?;
+            arg(0).powi(exp)
+        }
+        "pow" => arg(0).powf(arg(1)),
+        "sin" => arg(0).sin(),
+        "cos" => arg(0).cos(),
+        "exp" => arg(0).exp(),
+        "exp2" => arg(0).exp2(),
+        "log" => arg(0).ln(),
+        "log2" => arg(0).log2(),
+        "log10" => arg(0).log10(),
+        "fma" => arg(0).mul_add(arg(1), arg(2)),
+        // `fmuladd` may or may not be fused. Like codegen without the `fma`
+        // target feature, we round the product first.
+        "fmuladd" => arg(0) * arg(1) + arg(2),
+        "floor" => arg(0).floor(),
+        "ceil" => arg(0).ceil(),
+        "trunc" => arg(0).trunc(),
+        "round" => arg(0).round(),
+        "round_ties_even_" => arg(0).round_ties_even(),
+        "copysign" => arg(0).copysign(arg(1)),
+        "minimum_number_nsz_" => arg(0).min(arg(1)),
+        "maximum_number_nsz_" => arg(0).max(arg(1)),
+        "minimum" => arg(0).minimum(arg(1)),
+        "maximum" => arg(0).maximum(arg(1)),
+        _ => return Ok(None),
+    };
+    Ok(Some(result.to_value()))
+}
+
+fn fabs<T: FloatMath>(value: &Value) -> Value {
+    T::from_value(value).abs().to_value()
+}
+
+fn is_finite<T: FloatMath>(value: &Value) -> bool {
+    T::from_value(value).is_finite()
+}
+
+/// Whether `value` truncated towards zero fits in an integer of `bits` bits.
+fn fits_int<T: FloatMath>(value: &Value, bits: u32, signed: bool) -> bool {
+    // Powers of two up to 2^128 are exact in `f128`.
+    let value = T::from_value(value).to_f128().trunc();
+    let (min, max) = if signed {
+        (-(2f128.powi(bits as i32 - 1)), 2f128.powi(bits as i32 - 1))
+    } else {
+        (0.0, 2f128.powi(bits as i32))
+    };
+    value >= min && value < max
+}
+
+/// Split an intrinsic name such as `sqrtf64` into its base name and float type.
+fn split_float_suffix(name: &str) -> Option<(&str, FloatTy)> {
+    [
+        ("f16", FloatTy::F16),
+        ("f32", FloatTy::F32),
+        ("f64", FloatTy::F64),
+        ("f128", FloatTy::F128),
+    ]
+    .into_iter()
+    .find_map(|(suffix, float_ty)| Some((name.strip_suffix(suffix)?, float_ty)))
+}
+
+fn as_float_ty(ty: Ty) -> Option<FloatTy> {
+    match ty.kind() {
+        TyKind::RigidTy(RigidTy::Float(float_ty)) => Some(float_ty),
+        _ => None,
+    }
+}
+
+fn arith_op(name: &str) -> BinOp {
+    match &name[..4] {
+        "fadd" => BinOp::Add,
+        "fsub" => BinOp::Sub,
+        "fmul" => BinOp::Mul,
+        "fdiv" => BinOp::Div,
+        _ => BinOp::Rem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_float_suffix() {
+        assert!(matches!(
+            split_float_suffix("sqrtf128"),
+            Some(("sqrt", FloatTy::F128))
+        ));
+        assert!(matches!(
+            split_float_suffix("round_ties_even_f16"),
+            Some(("round_ties_even_", FloatTy::F16))
+        ));
+        assert!(split_float_suffix("ctpop").is_none());
+    }
+
+    #[test]
+    fn test_fits_int() {
+        let fits = |value: f64, bits, signed| fits_int::<f64>(&value.to_value(), bits, signed);
+        assert!(fits(255.9, 8, false));
+        assert!(!fits(256.0, 8, false));
+        assert!(fits(-0.9, 8, false));
+        assert!(fits(-128.0, 8, true));
+        assert!(!fits(-129.0, 8, true));
+        assert!(!fits(f64::NAN, 32, true));
+        assert!(!fits(f64::INFINITY, 64, false));
+    }
+}
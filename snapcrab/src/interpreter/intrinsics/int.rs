@@ -0,0 +1,289 @@
+//! Integer intrinsics.
+//!
+//! These intrinsics are evaluated with the host integer type that matches the
+//! generic argument of the intrinsic. Many of them have fallback bodies in
+//! `core`, but they are used by every integer method on hot paths, so shimming
+//! them is much cheaper than interpreting the fallback.
+
+use crate::value::Value;
+use anyhow::{Context, Result, bail};
+use num_traits::ops::overflowing::{OverflowingAdd, OverflowingMul, OverflowingSub};
+use num_traits::{
+    CheckedDiv, CheckedRem, PrimInt, SaturatingAdd, SaturatingSub, WrappingAdd, WrappingMul,
+    WrappingSub,
+};
+use rustc_public::mir::BinOp;
+use rustc_public::ty::{IntTy, RigidTy, Ty, TyKind, UintTy};
+use std::fmt::Display;
+use zerocopy::{FromBytes, Immutable, IntoBytes};
+
+/// A host integer type that mirrors one of the Rust integer types.
+trait HostInt:
+    PrimInt
+    + CheckedDiv
+    + CheckedRem
+    + OverflowingAdd
+    + OverflowingSub
+    + OverflowingMul
+    + SaturatingAdd
+    + SaturatingSub
+    + WrappingAdd
+    + WrappingSub
+    + WrappingMul
+    + FromBytes
+    + IntoBytes
+    + Immutable
+    + Display
+{
+}
+
+impl<T> HostInt for T where
+    T: PrimInt
+        + CheckedDiv
+        + CheckedRem
+        + OverflowingAdd
+        + OverflowingSub
+        + OverflowingMul
+        + SaturatingAdd
+        + SaturatingSub
+        + WrappingAdd
+        + WrappingSub
+        + WrappingMul
+        + FromBytes
+        + IntoBytes
+        + Immutable
+        + Display
+{
+}
+
+/// Dispatch `$func::<T>($args)` to the host type `T` that matches the integer type `$ty`.
+macro_rules! with_int_ty {
+    ($ty:expr, $func:ident($($args:expr),*)) => {
+        match $ty.kind() {
+            TyKind::RigidTy(RigidTy::Int(int_ty)) => match int_ty {
+                IntTy::I8 => $func::<i8>($($args),*),
+                IntTy::I16 => $func::<i16>($($args),*),
+                IntTy::I32 => $func::<i32>($($args),*),
+                IntTy::I64 => $func::<i64>($($args),*),
+                IntTy::I128 => $func::<i128>($($args),*),
+                IntTy::Isize => $func::<isize>($($args),*),
+            },
+            TyKind::RigidTy(RigidTy::Uint(uint_ty)) => match uint_ty {
+                UintTy::U8 => $func::<u8>($($args),*),
+                UintTy::U16 => $func::<u16>($($args),*),
+                UintTy::U32 => $func::<u32>($($args),*),
+                UintTy::U64 => $func::<u64>($($args),*),
+                UintTy::U128 => $func::<u128>($($args),*),
+                UintTy::Usize => $func::<usize>($($args),*),
+            },
+            _ => bail!("Expected an integer type, but found `{}`", $ty),
+        }
+    };
+}
+
+/// Evaluate the integer intrinsic `name` whose first generic argument is `ty`.
+///
+/// `shift_ty` is the type of the shift amount of `unchecked_shl` and
+/// `unchecked_shr`. Returns `None` if `name` is not an integer intrinsic.
+pub(super) fn eval_int_intrinsic(
+    name: &str,
+    args: &[Value],
+    ty: Ty,
+    shift_ty: Option<Ty>,
+) -> Result<Option<Value>> {
+    with_int_ty!(ty, int_intrinsic(name, args, shift_ty))
+}
+
+/// Evaluate an overflowing arithmetic operation on integers of type `ty`.
+///
+/// Returns the wrapped result, and whether the operation overflowed.
+pub(in crate::interpreter) fn eval_overflowing_binop(
+    op: BinOp,
+    left: &Value,
+    right: &Value,
+    ty: Ty,
+) -> Result<(Value, bool)> {
+    with_int_ty!(ty, overflowing_binop(op, left, right))
+}
+
+fn overflowing_binop<T: HostInt>(op: BinOp, l: &Value, r: &Value) -> Result<(Value, bool)> {
+    let left = read::<T>(l)?;
+    let right = read::<T>(r)?;
+    let (result, overflow) = match op {
+        BinOp::Add => left.overflowing_add(&right),
+        BinOp::Sub => left.overflowing_sub(&right),
+        BinOp::Mul => left.overflowing_mul(&right),
+        _ => bail!("Unsupported overflowing operation: {op:?}"),
+    };
+    Ok((Value::from_type(result), overflow))
+}
+
+fn int_intrinsic<T: HostInt>(
+    name: &str,
+    args: &[Value],
+    shift_ty: Option<Ty>,
+) -> Result<Option<Value>> {
+    let result = match name {
+        "ctpop" => Value::from_type(read::<T>(&args[0])?.count_ones()),
+        "ctlz" => Value::from_type(read::<T>(&args[0])?.leading_zeros()),
+        "cttz" => Value::from_type(read::<T>(&args[0])?.trailing_zeros()),
+        "ctlz_nonzero" | "cttz_nonzero" => {
+            let x = read::<T>(&args[0])?;
+            if x.is_zero() {
+                bail!("Undefined behavior: `{name}` called with zero");
+            }
+            let count = if name == "ctlz_nonzero" {
+                x.leading_zeros()
+            } else {
+                x.trailing_zeros()
+            };
+            Value::from_type(count)
+        }
+        "bswap" => Value::from_type(read::<T>(&args[0])?.swap_bytes()),
+        "bitreverse" => Value::from_type(read::<T>(&args[0])?.reverse_bits()),
+        "rotate_left" | "rotate_right" => {
+            let x = read::<T>(&args[0])?;
+            // The rotation is modulo the bit width, which the host method also does.
+            let shift = read::<u32>(&args[1])?;
+            if name == "rotate_left" {
+                Value::from_type(x.rotate_left(shift))
+            } else {
+                Value::from_type(x.rotate_right(shift))
+            }
+        }
+        "unchecked_add" | "unchecked_sub" | "unchecked_mul" => {
+            let (x, y) = (read::<T>(&args[0])?, read::<T>(&args[1])?);
+            let result = match name {
+                "unchecked_add" => x.checked_add(&y),
+                "unchecked_sub" => x.checked_sub(&y),
+                _ => x.checked_mul(&y),
+            };
+            let Some(result) = result else {
+                bail!("Undefined behavior: overflow in `{name}` ({x}, {y})");
+            };
+            Value::from_type(result)
+        }
+        "unchecked_div" | "unchecked_rem" | "exact_div" => {
+            let (x, y) = (read::<T>(&args[0])?, read::<T>(&args[1])?);
+            if y.is_zero() {
+                bail!("Undefined behavior: division by zero in `{name}`");
+            }
+            let result = if name == "unchecked_rem" {
+                CheckedRem::checked_rem(&x, &y)
+            } else {
+                CheckedDiv::checked_div(&x, &y)
+            };
+            let Some(result) = result else {
+                bail!("Undefined behavior: overflow in `{name}` ({x}, {y})");
+            };
+            if name == "exact_div" && !(x % y).is_zero() {
+                bail!("Undefined behavior: `exact_div` of {x} by {y} has a remainder");
+            }
+            Value::from_type(result)
+        }
+        "unchecked_shl" | "unchecked_shr" => {
+            let x = read::<T>(&args[0])?;
+            let shift_ty = shift_ty.context("Missing shift amount type")?;
+            let shift = match shift_ty.kind() {
+                TyKind::RigidTy(RigidTy::Int(_)) => args[1].read_int(),
+                _ => args[1].read_uint() as i128,
+            };
+            let bits = size_of::<T>() as u32 * 8;
+            if shift < 0 || shift >= bits as i128 {
+                bail!(
+                    "Undefined behavior: unchecked shift with amount {shift} \
+                     (valid range: 0..{bits})"
+                );
+            }
+            if name == "unchecked_shl" {
+                Value::from_type(x << shift as usize)
+            } else {
+                Value::from_type(x >> shift as usize)
+            }
+        }
+        "wrapping_add" | "wrapping_sub" | "wrapping_mul" => {
+            let (x, y) = (read::<T>(&args[0])?, read::<T>(&args[1])?);
+            let result = match name {
+                "wrapping_add" => x.wrapping_add(&y),
+                "wrapping_sub" => x.wrapping_sub(&y),
+                _ => x.wrapping_mul(&y),
+            };
+            Value::from_type(result)
+        }
+        "saturating_add" => {
+            let (x, y) = (read::<T>(&args[0])?, read::<T>(&args[1])?);
+            Value::from_type(SaturatingAdd::saturating_add(&x, &y))
+        }
+        "saturating_sub" => {
+            let (x, y) = (read::<T>(&args[0])?, read::<T>(&args[1])?);
+            Value::from_type(SaturatingSub::saturating_sub(&x, &y))
+        }
+        "three_way_compare" => {
+            let (x, y) = (read::<T>(&args[0])?, read::<T>(&args[1])?);
+            // `Ordering` is represented as an `i8` of -1, 0 or 1.
+            Value::from_type(x.cmp(&y) as i8)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+fn read<T: FromBytes>(value: &Value) -> Result<T> {
+    value
+        .as_type::<T>()
+        .with_context(|| format!("Expected a {}-byte integer", size_of::<T>()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval<T: HostInt>(name: &str, args: &[Value]) -> Result<Value> {
+        int_intrinsic::<T>(name, args, None).map(|result| result.unwrap())
+    }
+
+    #[test]
+    fn test_bit_counts() {
+        let x = Value::from_type(0b0110_0000u8);
+        assert_eq!(
+            eval::<u8>("ctpop", std::slice::from_ref(&x))
+                .unwrap()
+                .read_uint(),
+            2
+        );
+        assert_eq!(
+            eval::<u8>("ctlz", std::slice::from_ref(&x))
+                .unwrap()
+                .read_uint(),
+            1
+        );
+        assert_eq!(eval::<u8>("cttz", &[x]).unwrap().read_uint(), 5);
+        let zero = Value::from_type(0i32);
+        assert_eq!(
+            eval::<i32>("ctlz", std::slice::from_ref(&zero))
+                .unwrap()
+                .read_uint(),
+            32
+        );
+        assert!(eval::<i32>("cttz_nonzero", &[zero]).is_err());
+    }
+
+    #[test]
+    fn test_unchecked_ub() {
+        let args = [Value::from_type(i8::MIN), Value::from_type(-1i8)];
+        assert!(eval::<i8>("unchecked_div", &args).is_err());
+        assert!(eval::<i8>("unchecked_mul", &args).is_err());
+        let args = [Value::from_type(7u16), Value::from_type(2u16)];
+        assert!(eval::<u16>("exact_div", &args).is_err());
+        assert_eq!(eval::<u16>("unchecked_rem", &args).unwrap().read_uint(), 1);
+    }
+
+    #[test]
+    fn test_overflowing_binop() {
+        let (left, right) = (Value::from_type(250u8), Value::from_type(10u8));
+        let (result, overflow) = overflowing_binop::<u8>(BinOp::Add, &left, &right).unwrap();
+        assert_eq!((result.read_uint(), overflow), (4, true));
+        let (result, overflow) = overflowing_binop::<u8>(BinOp::Sub, &left, &right).unwrap();
+        assert_eq!((result.read_uint(), overflow), (240, false));
+    }
+}
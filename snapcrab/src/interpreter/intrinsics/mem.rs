@@ -0,0 +1,193 @@
+//! Memory intrinsics.
+//!
+//! All accesses go through [`ThreadMemory`], so they are subject to the same
+//! bounds, use-after-free and alignment checks as regular loads and stores.
+
+use crate::memory::ThreadMemory;
+use crate::ty::MonoType;
+use crate::value::Value;
+use anyhow::{Context, Result, bail};
+use rustc_public::ty::Ty;
+use std::cmp::Ordering;
+
+/// Evaluate the memory intrinsic `name` whose first generic argument is `ty`, if any.
+///
+/// Returns `None` if `name` is not a memory intrinsic.
+pub(super) fn eval_mem_intrinsic(
+    name: &str,
+    args: &[Value],
+    ty: Option<Ty>,
+    memory: &mut ThreadMemory,
+) -> Result<Option<Value>> {
+    let result = match name {
+        "copy" | "copy_nonoverlapping" => {
+            let ty = ty.context("Missing element type")?;
+            let (src, dst, count) = (
+                read_ptr(&args[0])?,
+                read_ptr(&args[1])?,
+                read_ptr(&args[2])?,
+            );
+            copy(memory, src, dst, count, ty, name == "copy_nonoverlapping")?;
+            Value::unit().clone()
+        }
+        "volatile_copy_memory" | "volatile_copy_nonoverlapping_memory" => {
+            // The destination comes first for the volatile versions.
+            let ty = ty.context("Missing element type")?;
+            let (dst, src, count) = (
+                read_ptr(&args[0])?,
+                read_ptr(&args[1])?,
+                read_ptr(&args[2])?,
+            );
+            let nonoverlapping = name == "volatile_copy_nonoverlapping_memory";
+            copy(memory, src, dst, count, ty, nonoverlapping)?;
+            Value::unit().clone()
+        }
+        "write_bytes" | "volatile_set_memory" => {
+            let ty = ty.context("Missing element type")?;
+            let dst = read_ptr(&args[0])?;
+            let byte = args[1].as_type::<u8>().context("Expected a byte")?;
+            let count = read_ptr(&args[2])?;
+            let size = byte_len(name, ty, count)?;
+            check_ptr(memory, name, dst, ty.alignment()?)?;
+            if size > 0 {
+                memory.write_bytes(dst, &vec![byte; size])?;
+            }
+            Value::unit().clone()
+        }
+        "compare_bytes" => {
+            let (left, right, len) = (
+                read_ptr(&args[0])?,
+                read_ptr(&args[1])?,
+                read_ptr(&args[2])?,
+            );
+            let ordering = if len == 0 {
+                Ordering::Equal
+            } else {
+                let left = memory.read_bytes(left, len)?;
+                let right = memory.read_bytes(right, len)?;
+                left.as_bytes().cmp(right.as_bytes())
+            };
+            Value::from_type(ordering as i32)
+        }
+        "raw_eq" => {
+            let ty = ty.context("Missing value type")?;
+            let left = memory.read_addr(read_ptr(&args[0])?, ty)?;
+            let right = memory.read_addr(read_ptr(&args[1])?, ty)?;
+            Value::from_bool(left.as_bytes() == right.as_bytes())
+        }
+        "volatile_load" => {
+            let ty = ty.context("Missing value type")?;
+            memory.read_addr(read_ptr(&args[0])?, ty)?
+        }
+        "unaligned_volatile_load" => {
+            let ty = ty.context("Missing value type")?;
+            memory.read_bytes(read_ptr(&args[0])?, ty.size()?)?
+        }
+        "volatile_store" => {
+            let ty = ty.context("Missing value type")?;
+            memory.write_addr(read_ptr(&args[0])?, args[1].as_bytes(), ty)?;
+            Value::unit().clone()
+        }
+        "unaligned_volatile_store" => {
+            if !args[1].as_bytes().is_empty() {
+                memory.write_bytes(read_ptr(&args[0])?, args[1].as_bytes())?;
+            }
+            Value::unit().clone()
+        }
+        "arith_offset" => {
+            // Wrapping pointer arithmetic, e.g. `ptr.wrapping_add(count)`.
+            let ty = ty.context("Missing pointee type")?;
+            let (ptr, count) = (read_ptr(&args[0])?, read_ptr(&args[1])?);
+            Value::from_type(ptr.wrapping_add(count.wrapping_mul(ty.size()?)))
+        }
+        "ptr_offset_from" | "ptr_offset_from_unsigned" => {
+            let ty = ty.context("Missing pointee type")?;
+            let (ptr, base) = (read_ptr(&args[0])?, read_ptr(&args[1])?);
+            let size = ty.size()? as isize;
+            let distance = (ptr as isize).wrapping_sub(base as isize);
+            if size == 0 || distance % size != 0 {
+                bail!(
+                    "Undefined behavior: `{name}` distance of {distance} bytes is not a \
+                     multiple of the element size {size}"
+                );
+            }
+            let (low, high) = (ptr.min(base), ptr.max(base));
+            if low != high && !memory.in_allocation(low, high - low) {
+                bail!(
+                    "Undefined behavior: `{name}` with pointers 0x{ptr:x} and 0x{base:x} \
+                     that are not in the same allocation"
+                );
+            }
+            let offset = distance / size;
+            if name == "ptr_offset_from" {
+                Value::from_type(offset)
+            } else if ptr < base {
+                bail!(
+                    "Undefined behavior: `{name}` with pointer 0x{ptr:x} before its base 0x{base:x}"
+                );
+            } else {
+                Value::from_type(offset as usize)
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+/// Copy `count` values of type `ty` from `src` to `dst`.
+///
+/// Overlapping ranges are undefined behavior if `nonoverlapping` is set.
+pub(in crate::interpreter) fn copy(
+    memory: &mut ThreadMemory,
+    src: usize,
+    dst: usize,
+    count: usize,
+    ty: Ty,
+    nonoverlapping: bool,
+) -> Result<()> {
+    let name = if nonoverlapping {
+        "copy_nonoverlapping"
+    } else {
+        "copy"
+    };
+    let size = byte_len(name, ty, count)?;
+    let align = ty.alignment()?;
+    check_ptr(memory, name, src, align)?;
+    check_ptr(memory, name, dst, align)?;
+    if size == 0 {
+        return Ok(());
+    }
+    if nonoverlapping && src < dst.wrapping_add(size) && dst < src.wrapping_add(size) {
+        bail!(
+            "Undefined behavior: `copy_nonoverlapping` with overlapping ranges \
+             0x{src:x}..0x{:x} and 0x{dst:x}..0x{:x}",
+            src.wrapping_add(size),
+            dst.wrapping_add(size)
+        );
+    }
+    let data = memory.read_bytes(src, size)?;
+    memory.write_bytes(dst, data.as_bytes())
+}
+
+/// The number of bytes covered by `count` values of type `ty`.
+fn byte_len(name: &str, ty: Ty, count: usize) -> Result<usize> {
+    match ty.size()?.checked_mul(count) {
+        Some(size) if size <= isize::MAX as usize => Ok(size),
+        _ => bail!("Undefined behavior: `{name}` of {count} elements of `{ty}` overflows `isize`"),
+    }
+}
+
+/// Pointers passed to memory intrinsics must be non-null and aligned, even if
+/// no byte is accessed.
+fn check_ptr(memory: &ThreadMemory, name: &str, address: usize, align: usize) -> Result<()> {
+    if address == 0 {
+        bail!("Undefined behavior: null pointer passed to `{name}`");
+    }
+    memory.check_alignment(address, align)
+}
+
+fn read_ptr(value: &Value) -> Result<usize> {
+    value
+        .as_type::<usize>()
+        .context("Expected a pointer-sized value")
+}
@@ -16,18 +16,167 @@
 //! to the target function, and writes the return value back to a buffer
 //! the interpreter can read. Cranelift handles register allocation and
 //! calling convention details.
+//!
+//! Function pointers created by the interpreter are JIT'd callbacks, so that
+//! native code can call them too, e.g. the formatting functions in
+//! `fmt::Arguments`. A callback runs the function in the interpreted thread
+//! that made the native call. Errors raised by the function, including
+//! panics, cannot unwind through native frames. They are reported once the
+//! native call returns, and until then, callbacks return zero.
 
 pub mod jit;
 
 use crate::interpreter::check::{CheckConfig, validate_value};
-use crate::ty::{has_any_ptr_to_padded, has_mutable_ptr_to_padded, has_padding};
-use crate::value::Value;
+use crate::interpreter::function::invoke_fn;
+use crate::interpreter::panic::requires_caller_location;
+use crate::memory::{ThreadMemory, pointer_width};
+use crate::ty::{MonoType, has_any_ptr_to_padded, has_mutable_ptr_to_padded, has_padding};
+use crate::value::{Value, uint_from_bytes};
 use anyhow::{Result, bail};
 use rustc_public::abi::{FnAbi, PassMode};
+use rustc_public::mir::Mutability;
 use rustc_public::mir::mono::Instance;
+use rustc_public::ty::{PolyFnSig, RigidTy, TyKind};
+use std::cell::{Cell, RefCell};
 use std::ffi::CString;
+use std::ptr;
 use tracing::{debug, trace};
 
+thread_local! {
+    /// The interpreted thread that is running native code, if any.
+    static CALLER: Cell<Option<Caller>> = const { Cell::new(None) };
+    /// The first error raised by a callback during the current native call.
+    static CALLBACK_ERROR: RefCell<Option<anyhow::Error>> = const { RefCell::new(None) };
+}
+
+/// The state a callback needs to run an interpreted function.
+#[derive(Clone, Copy)]
+struct Caller {
+    memory: *mut ThreadMemory,
+    error_depth: *mut Option<u16>,
+}
+
+/// Run `native_call`, letting the callbacks it calls run in `memory`.
+///
+/// Returns the first error raised by a callback, if any.
+pub fn with_callbacks<R>(
+    memory: &mut ThreadMemory,
+    error_depth: &mut Option<u16>,
+    native_call: impl FnOnce() -> R,
+) -> Result<R> {
+    let caller = Caller {
+        memory: ptr::from_mut(memory),
+        error_depth: ptr::from_mut(error_depth),
+    };
+    let previous = CALLER.replace(Some(caller));
+    let result = native_call();
+    CALLER.set(previous);
+    match CALLBACK_ERROR.take() {
+        Some(error) => Err(error),
+        None => Ok(result),
+    }
+}
+
+/// Whether an interpreted thread is running native code, whose frames are
+/// still on the host stack.
+pub fn in_native_call() -> bool {
+    CALLER.get().is_some()
+}
+
+/// Compile a callback that runs `instance` when native code calls it.
+///
+/// `id` is passed to [`handle_callback`], which finds `instance` with it.
+pub fn compile_callback(jit: &jit::JitEngine, instance: Instance, id: usize) -> Result<usize> {
+    let fn_abi = instance.fn_abi()?;
+    let addr = jit.compile_callback(&fn_abi, id, handle_callback, &instance.name())?;
+    Ok(addr as usize)
+}
+
+/// Run the function of the callback `id` in the interpreted thread that
+/// called native code.
+extern "C" fn handle_callback(id: usize, args_buf: *const u8, ret_buf: *mut u8) {
+    let Some(caller) = CALLER.get() else {
+        eprintln!(
+            "fatal runtime error: native code called an interpreted function outside of a native call"
+        );
+        std::process::abort();
+    };
+    // SAFETY: `with_callbacks` holds the exclusive borrows of both for the
+    // duration of the native call, and doesn't use them meanwhile.
+    let (memory, error_depth) = unsafe { (&mut *caller.memory, &mut *caller.error_depth) };
+    let instance = memory
+        .callback_instance(id)
+        .expect("callback should have been registered");
+    let result = instance
+        .fn_abi()
+        .map_err(anyhow::Error::from)
+        .and_then(|fn_abi| {
+            let ret_size = fn_abi.ret.ty.layout()?.shape().size.bytes();
+            // SAFETY: Zero is written first, in case the function fails.
+            unsafe { ptr::write_bytes(ret_buf, 0, ret_size) };
+            if CALLBACK_ERROR.with_borrow(Option::is_some) {
+                return Ok(());
+            }
+            // SAFETY: The callback of `instance` was compiled for `fn_abi`, and
+            // native code passes valid pointers to indirect arguments.
+            let mut args = unsafe { memory.jit.read_callback_args(&fn_abi, args_buf)? };
+            if requires_caller_location(instance) {
+                // The interpreter doesn't pass the implicit location argument.
+                args.pop();
+            }
+            debug!("Native code called `{}`", instance.name());
+            let loans = lend_pointees(memory, &fn_abi, &args);
+            let value = invoke_fn(instance, memory, args, error_depth);
+            for (address, size) in loans {
+                memory.release_native(address, size);
+            }
+            let value = value?;
+            let len = value.len().min(ret_size);
+            // SAFETY: The return buffer holds at least `ret_size` bytes.
+            unsafe { ptr::copy_nonoverlapping(value.as_bytes().as_ptr(), ret_buf, len) };
+            Ok(())
+        });
+    if let Err(error) = result {
+        CALLBACK_ERROR.with_borrow_mut(|pending| {
+            pending.get_or_insert(error);
+        });
+    }
+    // Coroutines of other threads may have made native calls meanwhile.
+    CALLER.set(Some(caller));
+}
+
+/// Lend the native memory that the reference arguments of a callback point
+/// to, such as a `&str` on the stack of native code. Returns the loans.
+///
+/// Only the pointees of the arguments themselves are lent, not the memory
+/// they point to in turn.
+fn lend_pointees(memory: &ThreadMemory, fn_abi: &FnAbi, args: &[Value]) -> Vec<(usize, usize)> {
+    let ptr_size = pointer_width();
+    let mut loans = Vec::new();
+    for (arg_abi, arg) in fn_abi.args.iter().zip(args) {
+        let TyKind::RigidTy(RigidTy::Ref(_, pointee, mutability)) = arg_abi.ty.kind() else {
+            continue;
+        };
+        let bytes = arg.as_bytes();
+        let address = uint_from_bytes(&bytes[..ptr_size]) as usize;
+        let size = match pointee.kind() {
+            TyKind::RigidTy(RigidTy::Str) => uint_from_bytes(&bytes[ptr_size..]) as usize,
+            TyKind::RigidTy(RigidTy::Slice(elem)) => {
+                let len = uint_from_bytes(&bytes[ptr_size..]) as usize;
+                elem.size().map_or(0, |size| size * len)
+            }
+            _ if bytes.len() == ptr_size => pointee.size().unwrap_or(0),
+            _ => 0,
+        };
+        let writable = mutability == Mutability::Mut;
+        // SAFETY: The reference is valid for the duration of the callback.
+        if unsafe { memory.lend_native(address, size, writable) } {
+            loans.push((address, size));
+        }
+    }
+    loans
+}
+
 /// Call a native function by resolving its mangled symbol name.
 ///
 /// Validates argument values and checks call safety before invoking the
@@ -45,13 +194,6 @@ pub fn call_native(
     let fn_abi = instance.fn_abi()?;
     trace_fn_abi(&name, &mangled, &fn_abi, args.len());
 
-    // Validate arguments before passing to native code.
-    for (arg_abi, arg_val) in fn_abi.args.iter().zip(args.iter()) {
-        validate_value(arg_val, arg_abi.ty, config)?;
-    }
-
-    check_call_safety(&fn_abi, &name)?;
-
     // Resolve symbol from the current process (cached).
     let symbol_name = mangled.as_str();
     let fn_ptr = jit
@@ -59,14 +201,65 @@ pub fn call_native(
         .map_err(|e| anyhow::anyhow!("Failed to invoke `{name}`: {e}"))?;
     trace!("Resolved symbol `{symbol_name}` at {fn_ptr:?}");
 
+    call_with_abi(fn_ptr, &fn_abi, &name, args, config, jit)
+}
+
+/// Call native code through a function pointer of type `sig`.
+///
+/// This is used for function pointers that native code created, which
+/// don't map back to an [`Instance`].
+pub fn call_native_fn_ptr(
+    fn_ptr: usize,
+    sig: PolyFnSig,
+    args: &[Value],
+    config: &CheckConfig,
+    jit: &jit::JitEngine,
+) -> Result<Value> {
+    let name = format!("native function 0x{fn_ptr:x}");
+    debug!("Native call: {name}");
+    let fn_abi = sig.fn_ptr_abi()?;
+    call_with_abi(
+        ptr::with_exposed_provenance(fn_ptr),
+        &fn_abi,
+        &name,
+        args,
+        config,
+        jit,
+    )
+}
+
+/// Validate the arguments of a native call, and call `fn_ptr` with them.
+fn call_with_abi(
+    fn_ptr: *const (),
+    fn_abi: &FnAbi,
+    name: &str,
+    args: &[Value],
+    config: &CheckConfig,
+    jit: &jit::JitEngine,
+) -> Result<Value> {
+    // Validate arguments before passing to native code.
+    for (arg_abi, arg_val) in fn_abi.args.iter().zip(args.iter()) {
+        validate_value(arg_val, arg_abi.ty, config)?;
+    }
+
+    check_call_safety(fn_abi, name)?;
+
     // SAFETY: check_call_safety rejected calls violating property 1 (mutable
     // pointers to padded types). Property 2 is upheld by construction (same
     // compiler produces both MIR and native code).
-    let result = unsafe { jit.call_native(fn_ptr, &fn_abi, args, &name)? };
+    let result = unsafe { jit.call_native(fn_ptr, fn_abi, args, name)? };
     debug!("Native call returned: {name}");
     Ok(result)
 }
 
+/// Whether `address` points into the code or data of a loaded library or of
+/// the executable.
+pub fn is_native_code(address: usize) -> bool {
+    let mut info = std::mem::MaybeUninit::<libc::Dl_info>::uninit();
+    // SAFETY: dladdr only reads the address, and fills `info` on success.
+    unsafe { libc::dladdr(ptr::with_exposed_provenance(address), info.as_mut_ptr()) != 0 }
+}
+
 /// Bail if a native call may leave interpreter-visible memory uninitialized.
 ///
 /// This happens when the function takes a mutable pointer/reference to a type
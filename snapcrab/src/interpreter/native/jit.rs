@@ -11,28 +11,41 @@
 //! correct ABI, and stores the result to `ret_buf`. The caller provides a
 //! zeroed `ret_buf` so padding bytes are initialized.
 //!
-//! NOTE: This module handles native calls only. Function pointer support
-//! (callbacks from native into the interpreter) will be added later.
+//! Callbacks from native code into the interpreter go the other way around.
+//! A callback is a native function with the ABI of an interpreted function,
+//! whose body stores its arguments into an `args_buf` with the same layout
+//! and calls a [`CallbackHandler`] to run the interpreted function.
 
 use crate::value::Value;
 use anyhow::{Result, bail};
 use cranelift::prelude::*;
 use cranelift_codegen::Context;
+use cranelift_codegen::gimli::RunTimeEndian;
+use cranelift_codegen::gimli::write::{
+    Address, CommonInformationEntry, EhFrame, EndianVec, FrameDescriptionEntry, FrameTable,
+};
 use cranelift_codegen::ir::{self, Function};
 use cranelift_codegen::isa::CallConv;
+use cranelift_codegen::isa::unwind::UnwindInfo;
 use cranelift_jit::{JITBuilder, JITModule};
-use cranelift_module::{Linkage, Module};
+use cranelift_module::{FuncId, Linkage, Module};
 use rustc_public::abi::{
-    FieldsShape, FloatLength, FnAbi, IntegerLength, LayoutShape, PassMode, Primitive, Scalar,
-    ValueAbi,
+    ArgAbi, FieldsShape, FloatLength, FnAbi, IntegerLength, LayoutShape, PassMode, Primitive,
+    Scalar, ValueAbi,
 };
+use std::alloc::{self, Layout};
 use std::collections::HashMap;
 use std::mem::MaybeUninit;
+use std::ptr::{self, NonNull};
 use std::sync::{Arc, Mutex};
 use tracing::{debug, trace};
 
-/// Trampoline function type.
-type Trampoline = unsafe extern "C" fn(*const (), *const u8, *mut MaybeUninit<u8>);
+/// Trampoline function type. Native panics unwind through it.
+type Trampoline = unsafe extern "C-unwind" fn(*const (), *const u8, *mut MaybeUninit<u8>);
+
+/// Function called by callbacks with their id, their `args_buf` and the
+/// buffer the return value must be written to.
+pub type CallbackHandler = extern "C" fn(usize, *const u8, *mut u8);
 
 /// Shared JIT engine for compiling native call trampolines.
 ///
@@ -131,8 +144,9 @@ impl JitEngine {
         let mut inner = self.0.lock().unwrap();
         let ret_size = fn_abi.ret.ty.layout()?.shape().size.bytes();
 
-        // Build the argument buffer.
-        let (args_buf, arg_layout) = inner.build_args_buffer(fn_abi, args)?;
+        // Build the argument buffer. It points to `indirect_args`, which must
+        // outlive the call.
+        let (args_buf, arg_layout, indirect_args) = inner.build_args_buffer(fn_abi, args)?;
         trace!(
             "  args_buf: {} bytes, {} entries",
             args_buf.len(),
@@ -157,26 +171,123 @@ impl JitEngine {
         // Release the lock before calling the trampoline (it might re-enter).
         drop(inner);
 
-        let mut ret_buf = vec![MaybeUninit::<u8>::zeroed(); ret_size];
+        let ret_align = fn_abi.ret.layout.shape().abi_align as usize;
+        let ret_buf = AlignedBuf::new(&vec![0; ret_size], ret_align)?;
         // SAFETY: This is only sound if the native function does not leave
         // memory accessible to the interpreter in an uninitialized state.
         // For example, if the function receives a pointer and writes a struct
         // with padding bytes into the pointee, those padding bytes become
         // uninitialized from the interpreter's perspective. We currently only
         // sanitize the return buffer (pre-zeroed above).
-        unsafe { trampoline(fn_ptr, args_buf.as_ptr(), ret_buf.as_mut_ptr()) };
+        unsafe { trampoline(fn_ptr, args_buf.as_ptr(), ret_buf.as_mut_ptr().cast()) };
+        drop(indirect_args);
 
         if ret_size == 0 {
             Ok(Value::unit().clone())
         } else {
-            // SAFETY: The trampoline wrote the return value for each scalar types.
+            // The trampoline wrote the return value for each scalar types.
             // The buffer was pre-zeroed so padding bytes are defined.
-            let bytes: Vec<u8> = ret_buf.iter().map(|b| unsafe { b.assume_init() }).collect();
-            Ok(Value::from_bytes(&bytes))
+            Ok(Value::from_bytes(ret_buf.as_bytes()))
+        }
+    }
+
+    /// Compile a callback with the ABI `fn_abi`, and return its address.
+    ///
+    /// The callback calls `handler` with `id`, a buffer holding its arguments,
+    /// which [`JitEngine::read_callback_args`] decodes, and a buffer for the
+    /// return value. For indirect returns, that is the caller's return slot.
+    pub fn compile_callback(
+        &self,
+        fn_abi: &FnAbi,
+        id: usize,
+        handler: CallbackHandler,
+        fn_name: &str,
+    ) -> Result<*const ()> {
+        let mut inner = self.0.lock().unwrap();
+        let ret_size = fn_abi.ret.ty.layout()?.shape().size.bytes();
+        let ret_info = inner.build_ret_info(fn_abi, ret_size)?;
+        let mut ab = ArgsBuffer::new();
+        for arg_abi in &fn_abi.args {
+            match inner.arg_parts(arg_abi)? {
+                ArgParts::Ignore => {}
+                ArgParts::Scalars(parts) => parts.iter().for_each(|(ty, _)| ab.push(*ty, &[])),
+                ArgParts::Indirect => ab.push(inner.pointer_ty, &[]),
+            }
+        }
+        inner.compile_callback_fn(
+            &ab.layout,
+            ab.buf.len(),
+            ret_info.as_ref(),
+            ret_size,
+            id,
+            handler,
+            fn_name,
+        )
+    }
+
+    /// Decode the arguments that a callback with the ABI `fn_abi` stored in
+    /// `args_buf`.
+    ///
+    /// # Safety
+    ///
+    /// `args_buf` must be the buffer of a callback compiled for `fn_abi`, and
+    /// the pointers to indirect arguments must be valid for reads.
+    pub unsafe fn read_callback_args(
+        &self,
+        fn_abi: &FnAbi,
+        args_buf: *const u8,
+    ) -> Result<Vec<Value>> {
+        let inner = self.0.lock().unwrap();
+        let mut ab = ArgsBuffer::new();
+        let mut args = Vec::with_capacity(fn_abi.args.len());
+        for arg_abi in &fn_abi.args {
+            let size = arg_abi.layout.shape().size.bytes();
+            let mut bytes = vec![0u8; size];
+            match inner.arg_parts(arg_abi)? {
+                ArgParts::Ignore => {}
+                ArgParts::Scalars(parts) => {
+                    for (ty, offset) in parts {
+                        ab.push(ty, &[]);
+                        let entry = ab.layout.last().unwrap();
+                        let len = (ty.bytes() as usize).min(size - offset);
+                        // SAFETY: The callback stored a value of type `ty` at
+                        // this offset of its buffer.
+                        let src = unsafe { args_buf.add(entry.offset) };
+                        unsafe {
+                            std::ptr::copy_nonoverlapping(src, bytes[offset..].as_mut_ptr(), len)
+                        };
+                    }
+                }
+                ArgParts::Indirect => {
+                    ab.push(inner.pointer_ty, &[]);
+                    let entry = ab.layout.last().unwrap();
+                    // SAFETY: The callback stored the pointer to the argument
+                    // at this offset, and the caller ensures it is readable.
+                    unsafe {
+                        let src = args_buf
+                            .add(entry.offset)
+                            .cast::<*const u8>()
+                            .read_unaligned();
+                        std::ptr::copy_nonoverlapping(src, bytes.as_mut_ptr(), size);
+                    }
+                }
+            }
+            args.push(Value::from_bytes(&bytes));
         }
+        Ok(args)
     }
 }
 
+/// How an argument is split into the entries of an argument buffer.
+enum ArgParts {
+    /// Zero-sized arguments are not passed.
+    Ignore,
+    /// Scalars of the given types, at the given offsets of the value.
+    Scalars(Vec<(ir::Type, usize)>),
+    /// A pointer to the value.
+    Indirect,
+}
+
 impl JitEngineInner {
     /// Build return info from the function ABI.
     fn build_ret_info(&self, fn_abi: &FnAbi, ret_size: usize) -> Result<Option<RetInfo>> {
@@ -215,59 +326,103 @@ impl JitEngineInner {
         }
     }
 
+    /// Split an argument into the scalars passed for it.
+    fn arg_parts(&self, arg_abi: &ArgAbi) -> Result<ArgParts> {
+        let parts = match &arg_abi.mode {
+            PassMode::Ignore => ArgParts::Ignore,
+            PassMode::Direct(_) => match &arg_abi.layout.shape().abi {
+                ValueAbi::Scalar(scalar) => {
+                    ArgParts::Scalars(vec![(self.scalar_to_ir_type(scalar), 0)])
+                }
+                ValueAbi::Vector { element, count } => {
+                    let ty = self.scalar_to_ir_type(element);
+                    let num_bytes = ty.bytes() as usize;
+                    ArgParts::Scalars((0..*count as usize).map(|i| (ty, i * num_bytes)).collect())
+                }
+                val_abi => {
+                    bail!("internal error: Unexpected ValueAbi: {val_abi:?}")
+                }
+            },
+            PassMode::Pair(_, _) => {
+                let ValueAbi::ScalarPair(first, second) = &arg_abi.layout.shape().abi else {
+                    bail!("internal error: PassMode::Pair must have ScalarPair ABI");
+                };
+                let second_offset = pair_second_offset(&arg_abi.layout.shape())?;
+                ArgParts::Scalars(vec![
+                    (self.scalar_to_ir_type(first), 0),
+                    (self.scalar_to_ir_type(second), second_offset),
+                ])
+            }
+            PassMode::Indirect { .. } => ArgParts::Indirect,
+            PassMode::Cast { .. } => {
+                bail!("PassMode::Cast is not supported");
+            }
+        };
+        Ok(parts)
+    }
+
     /// Build a flat, zero-initialized, aligned byte buffer containing all arguments.
+    ///
+    /// Arguments passed by pointer are copied to buffers aligned like their
+    /// types, which are returned along with the buffer. Native code may rely
+    /// on that alignment, e.g., to load SIMD vectors, while values are stored
+    /// without alignment.
     fn build_args_buffer(
         &self,
         fn_abi: &FnAbi,
         args: &[Value],
-    ) -> Result<(Vec<u8>, Vec<ArgEntry>)> {
+    ) -> Result<(Vec<u8>, Vec<ArgEntry>, Vec<AlignedBuf>)> {
         let mut ab = ArgsBuffer::new();
+        let mut indirect_args = Vec::new();
 
         for (arg_abi, arg_val) in fn_abi.args.iter().zip(args.iter()) {
-            match &arg_abi.mode {
-                PassMode::Ignore => {}
-                PassMode::Direct(_) => match &arg_abi.layout.shape().abi {
-                    ValueAbi::Scalar(scalar) => {
-                        let ty = self.scalar_to_ir_type(scalar);
-                        ab.push(ty, arg_val.as_bytes());
-                    }
-                    ValueAbi::Vector { element, count } => {
-                        let ty = self.scalar_to_ir_type(element);
-                        let num_bytes = ty.bytes() as usize;
-                        for i in 0..(*count as usize) {
-                            let offset = i * num_bytes;
-                            let bytes = arg_val.as_bytes();
-                            ab.push(ty, &bytes[offset..offset + num_bytes]);
-                        }
-                    }
-                    val_abi => {
-                        bail!("internal error: Unexpected ValueAbi: {val_abi:?}")
-                    }
-                },
-                PassMode::Pair(_, _) => {
-                    if let ValueAbi::ScalarPair(first, second) = &arg_abi.layout.shape().abi {
-                        let ty1 = self.scalar_to_ir_type(first);
-                        let ty2 = self.scalar_to_ir_type(second);
-                        let second_offset = pair_second_offset(&arg_abi.layout.shape())?;
-                        let bytes = arg_val.as_bytes();
-                        ab.push(ty1, &bytes[..ty1.bytes() as usize]);
-                        ab.push(
-                            ty2,
-                            &bytes[second_offset..second_offset + ty2.bytes() as usize],
-                        );
+            let bytes = arg_val.as_bytes();
+            match self.arg_parts(arg_abi)? {
+                ArgParts::Ignore => {}
+                ArgParts::Scalars(parts) => {
+                    for (ty, offset) in parts {
+                        let end = (offset + ty.bytes() as usize).min(bytes.len());
+                        ab.push(ty, &bytes[offset..end]);
                     }
                 }
-                PassMode::Indirect { .. } => {
-                    let ptr = arg_val.as_bytes().as_ptr() as u64;
+                ArgParts::Indirect => {
+                    let align = arg_abi.layout.shape().abi_align as usize;
+                    let copy = AlignedBuf::new(bytes, align)?;
+                    let ptr = copy.as_mut_ptr() as u64;
                     ab.push(self.pointer_ty, &ptr.to_le_bytes());
-                }
-                PassMode::Cast { .. } => {
-                    bail!("PassMode::Cast is not supported");
+                    indirect_args.push(copy);
                 }
             }
         }
 
-        Ok((ab.buf, ab.layout))
+        Ok((ab.buf, ab.layout, indirect_args))
+    }
+
+    /// Build the signature of a function taking `arg_layout` and returning
+    /// `ret_info`.
+    fn target_signature(&self, arg_layout: &[ArgEntry], ret_info: Option<&RetInfo>) -> Signature {
+        let mut target_sig = Signature::new(self.call_conv);
+        target_sig
+            .params
+            .extend(arg_layout.iter().map(|entry| AbiParam::new(entry.ty)));
+        if let Some(info) = ret_info {
+            match &info.mode {
+                RetMode::Direct(ty) => {
+                    target_sig.returns.push(AbiParam::new(*ty));
+                }
+                RetMode::Pair(ty1, ty2, _) => {
+                    target_sig.returns.push(AbiParam::new(*ty1));
+                    target_sig.returns.push(AbiParam::new(*ty2));
+                }
+                RetMode::Indirect => {
+                    target_sig.params.insert(
+                        0,
+                        AbiParam::special(self.pointer_ty, ir::ArgumentPurpose::StructReturn),
+                    );
+                }
+            }
+        }
+        target_sig
     }
 
     /// Compile a trampoline for the given argument layout and return info.
@@ -286,35 +441,13 @@ impl JitEngineInner {
         trampoline_sig.params.push(AbiParam::new(self.pointer_ty));
 
         // Target function signature.
-        let mut target_sig = Signature::new(self.call_conv);
-        target_sig
-            .params
-            .extend(arg_layout.iter().map(|entry| AbiParam::new(entry.ty)));
-
-        // Configure return type on target signature.
+        let target_sig = self.target_signature(arg_layout, ret_info);
         let ret_indirect = matches!(
             ret_info,
             Some(RetInfo {
                 mode: RetMode::Indirect
             })
         );
-        if let Some(info) = ret_info {
-            match &info.mode {
-                RetMode::Direct(ty) => {
-                    target_sig.returns.push(AbiParam::new(*ty));
-                }
-                RetMode::Pair(ty1, ty2, _) => {
-                    target_sig.returns.push(AbiParam::new(*ty1));
-                    target_sig.returns.push(AbiParam::new(*ty2));
-                }
-                RetMode::Indirect => {
-                    target_sig.params.insert(
-                        0,
-                        AbiParam::special(self.pointer_ty, ir::ArgumentPurpose::StructReturn),
-                    );
-                }
-            }
-        }
 
         // Declare the trampoline.
         let name = format!("__snapcrab_{fn_name}_{}", self.counter);
@@ -388,16 +521,146 @@ impl JitEngineInner {
             builder.finalize();
         }
 
-        // Compile.
+        let code_ptr = self.define_and_finalize(func_id, func)?;
+        trace!("Trampoline compiled at {code_ptr:?}");
+        // SAFETY: code_ptr points to JIT'd code matching the Trampoline signature.
+        Ok(unsafe { std::mem::transmute::<*const u8, Trampoline>(code_ptr) })
+    }
+
+    /// Compile a callback taking `arg_layout` and returning `ret_info`.
+    #[allow(clippy::too_many_arguments)]
+    fn compile_callback_fn(
+        &mut self,
+        arg_layout: &[ArgEntry],
+        args_size: usize,
+        ret_info: Option<&RetInfo>,
+        ret_size: usize,
+        id: usize,
+        handler: CallbackHandler,
+        fn_name: &str,
+    ) -> Result<*const ()> {
+        debug!("Compiling callback for `{fn_name}`");
+
+        let callback_sig = self.target_signature(arg_layout, ret_info);
+        let name = format!("__snapcrab_callback_{fn_name}_{}", self.counter);
+        self.counter += 1;
+        let func_id = self
+            .module
+            .declare_function(&name, Linkage::Local, &callback_sig)?;
+
+        // Handler signature: fn(id: usize, args_buf: ptr, ret_buf: ptr)
+        let mut handler_sig = Signature::new(self.call_conv);
+        handler_sig.params.push(AbiParam::new(self.pointer_ty));
+        handler_sig.params.push(AbiParam::new(self.pointer_ty));
+        handler_sig.params.push(AbiParam::new(self.pointer_ty));
+
+        let mut func = Function::with_name_signature(
+            ir::UserFuncName::user(0, func_id.as_u32()),
+            callback_sig,
+        );
+
+        {
+            let mut builder = FunctionBuilder::new(&mut func, &mut self.func_ctx);
+            let block = builder.create_block();
+            builder.append_block_params_for_function_params(block);
+            builder.switch_to_block(block);
+            let mut params = builder.block_params(block).to_vec();
+
+            // The return value is written to the caller's slot for indirect
+            // returns, and to a stack slot otherwise.
+            let ret_buf = match ret_info {
+                Some(RetInfo {
+                    mode: RetMode::Indirect,
+                }) => params.remove(0),
+                _ => {
+                    // Scalars are loaded with their full size, even if the
+                    // value is smaller.
+                    let size = ret_size.max(2 * self.pointer_ty.bytes() as usize);
+                    let slot = builder.create_sized_stack_slot(StackSlotData::new(
+                        StackSlotKind::ExplicitSlot,
+                        size as u32,
+                        4,
+                    ));
+                    builder.ins().stack_addr(self.pointer_ty, slot, 0)
+                }
+            };
+
+            // Store each argument in the buffer.
+            let args_slot = builder.create_sized_stack_slot(StackSlotData::new(
+                StackSlotKind::ExplicitSlot,
+                args_size.max(1) as u32,
+                4,
+            ));
+            for (entry, param) in arg_layout.iter().zip(params) {
+                builder
+                    .ins()
+                    .stack_store(param, args_slot, entry.offset as i32);
+            }
+            let args_buf = builder.ins().stack_addr(self.pointer_ty, args_slot, 0);
+
+            // Call the handler.
+            let sig_ref = builder.import_signature(handler_sig);
+            let handler_ptr = builder
+                .ins()
+                .iconst(self.pointer_ty, handler as usize as i64);
+            let id = builder.ins().iconst(self.pointer_ty, id as i64);
+            builder
+                .ins()
+                .call_indirect(sig_ref, handler_ptr, &[id, args_buf, ret_buf]);
+
+            // Load the return value.
+            let results = match ret_info.map(|info| &info.mode) {
+                Some(RetMode::Direct(ty)) => {
+                    vec![builder.ins().load(*ty, MemFlagsData::trusted(), ret_buf, 0)]
+                }
+                Some(RetMode::Pair(ty1, ty2, second_offset)) => vec![
+                    builder
+                        .ins()
+                        .load(*ty1, MemFlagsData::trusted(), ret_buf, 0),
+                    builder.ins().load(
+                        *ty2,
+                        MemFlagsData::trusted(),
+                        ret_buf,
+                        *second_offset as i32,
+                    ),
+                ],
+                Some(RetMode::Indirect) | None => vec![],
+            };
+            builder.ins().return_(&results);
+            builder.seal_all_blocks();
+            builder.finalize();
+        }
+
+        let code_ptr = self.define_and_finalize(func_id, func)?;
+        trace!("Callback compiled at {code_ptr:?}");
+        Ok(code_ptr.cast())
+    }
+
+    /// Compile `func`, make it executable and return its address.
+    ///
+    /// The unwind info of the function is registered with the system
+    /// unwinder, so that panics of native code can unwind through it.
+    fn define_and_finalize(&mut self, func_id: FuncId, func: Function) -> Result<*const u8> {
         self.ctx.func = func;
         self.module.define_function(func_id, &mut self.ctx)?;
+        let unwind_info = self
+            .ctx
+            .compiled_code()
+            .expect("function should have been compiled")
+            .create_unwind_info(self.module.isa())?;
         self.module.clear_context(&mut self.ctx);
         self.module.finalize_definitions()?;
 
         let code_ptr = self.module.get_finalized_function(func_id);
-        trace!("Trampoline compiled at {code_ptr:?}");
-        // SAFETY: code_ptr points to JIT'd code matching the Trampoline signature.
-        Ok(unsafe { std::mem::transmute::<*const u8, Trampoline>(code_ptr) })
+        if let Some(UnwindInfo::SystemV(info)) = unwind_info {
+            let cie = self
+                .module
+                .isa()
+                .create_systemv_cie()
+                .expect("SystemV unwind info should have a CIE");
+            register_frame(cie, info.to_fde(Address::Constant(code_ptr as u64)))?;
+        }
+        Ok(code_ptr)
     }
 
     /// Map a rustc Scalar to a cranelift IR type.
@@ -476,6 +739,84 @@ impl ArgsBuffer {
     }
 }
 
+/// A heap buffer aligned to a given alignment, which `Vec<u8>` doesn't
+/// guarantee.
+struct AlignedBuf {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+impl AlignedBuf {
+    /// Copy `bytes` to a new buffer aligned to `align`.
+    fn new(bytes: &[u8], align: usize) -> Result<Self> {
+        // Allocations must not be empty.
+        let layout = Layout::from_size_align(bytes.len().max(1), align)?;
+        // SAFETY: The layout is not empty.
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            alloc::handle_alloc_error(layout);
+        };
+        // SAFETY: The buffer holds at least `bytes.len()` bytes.
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.as_ptr(), bytes.len()) };
+        Ok(AlignedBuf { ptr, layout })
+    }
+
+    fn as_mut_ptr(&self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: The buffer is initialized and holds `layout.size()` bytes.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        // SAFETY: The buffer was allocated with this layout.
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+/// Register the unwind info of a JIT'd function with the system unwinder.
+///
+/// The `.eh_frame` data is leaked, since compiled code is never freed.
+fn register_frame(cie: CommonInformationEntry, fde: FrameDescriptionEntry) -> Result<()> {
+    unsafe extern "C" {
+        fn __register_frame(begin: *const u8);
+    }
+
+    let mut table = FrameTable::default();
+    let cie_id = table.add_cie(cie);
+    table.add_fde(cie_id, fde);
+    let mut eh_frame = EhFrame(EndianVec::new(RunTimeEndian::default()));
+    table.write_eh_frame(&mut eh_frame)?;
+    let mut bytes = eh_frame.0.into_vec();
+    // A zero length entry terminates the section.
+    bytes.extend_from_slice(&[0; 4]);
+    let bytes: &'static [u8] = bytes.leak();
+
+    if cfg!(target_os = "macos") {
+        // libunwind registers a single FDE at a time.
+        let word =
+            |offset: usize| u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let mut offset = 0;
+        while word(offset) != 0 {
+            // CIEs have an id of zero, FDEs the offset back to their CIE.
+            if word(offset + 4) != 0 {
+                // SAFETY: The entry is valid and outlives the compiled code.
+                unsafe { __register_frame(bytes[offset..].as_ptr()) };
+            }
+            offset += 4 + word(offset) as usize;
+        }
+    } else {
+        // libgcc registers every entry of the section.
+        // SAFETY: The section is valid and outlives the compiled code.
+        unsafe { __register_frame(bytes.as_ptr()) };
+    }
+    Ok(())
+}
+
 /// Get the offset of the second field in a ScalarPair layout.
 ///
 /// TODO: replace this by getting pair offset in newer rustc_public version.
@@ -0,0 +1,616 @@
+//! Interpreted panic runtime.
+//!
+//! The panic entry points in `core::panicking`, and a few of their callers in
+//! `core` and `std`, are only available as native code. Calling them natively
+//! would run the host panic machinery and unwind through the interpreter.
+//! Instead, these calls are intercepted here: the message and the location are
+//! computed from the interpreter state, reported the way the default panic hook
+//! does, and the panic is propagated as a [`Panic`] error.
+//!
+//! Failures of the interpreter itself are ordinary `anyhow` errors, so they
+//! can always be told apart from panics raised by the interpreted program.
+//!
+//! While a [`Panic`] propagates, each frame runs the cleanup blocks of the
+//! terminator that unwound. The `catch_unwind` intrinsic stops the
+//! propagation and hands the payload over to the program, boxed as a
+//! `Box<dyn Any + Send>`.
+
+use super::function::{FnInterpreter, invoke_fn};
+use crate::memory::{ThreadMemory, pointer_width};
+use crate::ty::{MonoType, dyn_principal, struct_field};
+use crate::value::{Value, uint_from_bytes};
+use anyhow::{Context, Result, bail};
+use rustc_public::CrateDef;
+use rustc_public::mir::mono::Instance;
+use rustc_public::mir::{AssertMessage, Mutability};
+use rustc_public::ty::{GenericArgs, Region, RegionKind, RigidTy, Span, Ty, TyKind};
+use rustc_public_bridge::IndexedVal;
+use std::fmt;
+use tracing::debug;
+
+/// A panic raised by the interpreted program.
+#[derive(Debug)]
+pub struct Panic {
+    thread: String,
+    location: String,
+    message: String,
+    payload: PanicPayload,
+}
+
+/// The value carried by a panic while it unwinds.
+#[derive(Clone, Debug)]
+pub enum PanicPayload {
+    /// The panic message, which becomes a `&'static str` payload once caught.
+    Message(String),
+    /// A value passed to `begin_panic`, not boxed yet, with the index of its
+    /// type. `anyhow` errors must be `Send`, which `Ty` is not.
+    Value(Value, usize),
+    /// A `Box<dyn Any + Send>` given to `resume_unwind`.
+    Boxed(Value),
+}
+
+impl fmt::Display for Panic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "thread '{}' panicked at {}:\n{}",
+            self.thread, self.location, self.message
+        )
+    }
+}
+
+impl std::error::Error for Panic {}
+
+/// The interpreted program aborted, either explicitly or because of a
+/// panic that cannot unwind.
+#[derive(Debug, Default)]
+pub struct Abort {
+    /// Why the runtime aborted, if the program didn't abort on its own.
+    reason: Option<String>,
+}
+
+impl Abort {
+    /// An abort raised by the runtime, such as the one that `rtabort!` does.
+    pub fn runtime(reason: impl Into<String>) -> Self {
+        Abort {
+            reason: Some(reason.into()),
+        }
+    }
+
+    /// The abort of a panic that cannot unwind, which reports the panic.
+    fn non_unwinding(panic: &Panic) -> Self {
+        Abort::runtime(format!(
+            "{panic}\nthread caused non-unwinding panic. aborting."
+        ))
+    }
+}
+
+impl fmt::Display for Abort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            Some(reason) => write!(f, "abnormal termination: {reason}"),
+            None => write!(f, "abnormal termination: the program aborted execution"),
+        }
+    }
+}
+
+impl std::error::Error for Abort {}
+
+/// The panic entry points intercepted by the interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicFn {
+    /// `core::panicking::panic_fmt(fmt::Arguments) -> !`
+    PanicFmt,
+    /// `core::panicking::panic_nounwind_fmt(fmt::Arguments, bool) -> !`
+    PanicNounwindFmt,
+    /// `core::panicking::panic(&'static str) -> !`
+    Panic,
+    /// `core::panicking::panic_nounwind(&'static str) -> !` and its
+    /// `_nobacktrace` variant
+    PanicNounwind,
+    /// `core::panicking::panic_explicit() -> !`
+    PanicExplicit,
+    /// `core::panicking::panic_bounds_check(index, len) -> !`
+    BoundsCheck,
+    /// `core::panicking::panic_cannot_unwind() -> !`
+    CannotUnwind,
+    /// `core::panicking::panic_in_cleanup() -> !`
+    InCleanup,
+    /// `core::option::unwrap_failed() -> !`
+    OptionUnwrapFailed,
+    /// `core::option::expect_failed(&str) -> !`
+    OptionExpectFailed,
+    /// `std::panicking::begin_panic::<M>(M) -> !`, used by 2015 edition `panic!`
+    BeginPanic,
+    /// `std::panic::resume_unwind(Box<dyn Any + Send>) -> !`
+    ResumeUnwind,
+    /// `std::process::abort() -> !`
+    Abort,
+    /// The guard that aborts when a thread-local destructor unwinds. Its
+    /// `rtabort!` message is carried by the abort instead of being printed.
+    DtorUnwindGuard,
+}
+
+impl PanicFn {
+    /// Return the panic entry point implemented by `instance`, if any.
+    pub fn from_instance(instance: Instance) -> Option<Self> {
+        match instance.def.name().as_str() {
+            "core::panicking::panic_fmt" | "std::rt::panic_fmt" => Some(PanicFn::PanicFmt),
+            "core::panicking::panic_nounwind_fmt" => Some(PanicFn::PanicNounwindFmt),
+            "core::panicking::panic" => Some(PanicFn::Panic),
+            "core::panicking::panic_nounwind" | "core::panicking::panic_nounwind_nobacktrace" => {
+                Some(PanicFn::PanicNounwind)
+            }
+            "core::panicking::panic_explicit" => Some(PanicFn::PanicExplicit),
+            "core::panicking::panic_bounds_check" => Some(PanicFn::BoundsCheck),
+            "core::panicking::panic_cannot_unwind" => Some(PanicFn::CannotUnwind),
+            "core::panicking::panic_in_cleanup" => Some(PanicFn::InCleanup),
+            "std::option::unwrap_failed" | "core::option::unwrap_failed" => {
+                Some(PanicFn::OptionUnwrapFailed)
+            }
+            "std::option::expect_failed" | "core::option::expect_failed" => {
+                Some(PanicFn::OptionExpectFailed)
+            }
+            "std::rt::begin_panic" | "std::panicking::begin_panic" => Some(PanicFn::BeginPanic),
+            "std::panic::resume_unwind" | "std::panicking::resume_unwind" => {
+                Some(PanicFn::ResumeUnwind)
+            }
+            "std::process::abort" => Some(PanicFn::Abort),
+            "<std::sys::thread_local::abort_on_dtor_unwind::DtorUnwindGuard as std::ops::Drop>::drop" => {
+                Some(PanicFn::DtorUnwindGuard)
+            }
+            _ => None,
+        }
+    }
+
+    /// Start the panic described by this entry point.
+    ///
+    /// Returns the error to propagate, or an interpreter error if the panic
+    /// message could not be computed.
+    pub fn eval(
+        self,
+        args: &[Value],
+        instance: Instance,
+        memory: &mut ThreadMemory,
+        error_depth: &mut Option<u16>,
+    ) -> anyhow::Error {
+        debug!("Panic entry point: {self:?}");
+        let message = match self {
+            PanicFn::PanicFmt | PanicFn::PanicNounwindFmt => {
+                format_arguments(memory, &args[0], error_depth)
+            }
+            PanicFn::Panic | PanicFn::PanicNounwind | PanicFn::OptionExpectFailed => {
+                read_str(memory, &args[0])
+            }
+            PanicFn::PanicExplicit => Ok("explicit panic".to_string()),
+            PanicFn::BoundsCheck => {
+                let index = args[0].read_uint();
+                let len = args[1].read_uint();
+                Ok(format!(
+                    "index out of bounds: the len is {len} but the index is {index}"
+                ))
+            }
+            PanicFn::CannotUnwind => Ok("panic in a function that cannot unwind".to_string()),
+            PanicFn::InCleanup => Ok("panic in a destructor during cleanup".to_string()),
+            PanicFn::OptionUnwrapFailed => {
+                Ok("called `Option::unwrap()` on a `None` value".to_string())
+            }
+            PanicFn::BeginPanic => {
+                return match begin_panic_payload(memory, &args[0], instance) {
+                    Ok((message, payload)) => raise_panic(memory, message, payload, true),
+                    Err(err) => err,
+                };
+            }
+            PanicFn::ResumeUnwind => {
+                // The panic hook is not invoked when a panic is resumed.
+                let payload = PanicPayload::Boxed(args[0].clone());
+                return anyhow::Error::new(new_panic(memory, "Box<dyn Any>".into(), payload));
+            }
+            PanicFn::Abort => return anyhow::Error::new(Abort::default()),
+            PanicFn::DtorUnwindGuard => {
+                return anyhow::Error::new(Abort::runtime("thread local panicked on drop"));
+            }
+        };
+        match message {
+            Ok(message) => {
+                let can_unwind = !matches!(
+                    self,
+                    PanicFn::PanicNounwindFmt
+                        | PanicFn::PanicNounwind
+                        | PanicFn::CannotUnwind
+                        | PanicFn::InCleanup
+                );
+                start_panic(memory, message, can_unwind)
+            }
+            Err(err) => err,
+        }
+    }
+}
+
+/// The unwinding runtime used by `std::panic::catch_unwind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnwindFn {
+    /// The `catch_unwind(try_fn, data, catch_fn) -> i32` intrinsic.
+    CatchUnwind,
+    /// `std::panicking::catch_unwind::cleanup(*mut u8) -> Box<dyn Any + Send>`,
+    /// which turns the caught exception into the panic payload.
+    Cleanup,
+}
+
+impl UnwindFn {
+    /// Return the unwinding runtime function implemented by `instance`, if any.
+    pub fn from_instance(instance: Instance) -> Option<Self> {
+        if instance.intrinsic_name().as_deref() == Some("catch_unwind") {
+            return Some(UnwindFn::CatchUnwind);
+        }
+        match instance.def.name().as_str() {
+            "std::panicking::catch_unwind::cleanup" => Some(UnwindFn::Cleanup),
+            _ => None,
+        }
+    }
+
+    /// Execute the function.
+    pub fn eval(
+        self,
+        args: &[Value],
+        instance: Instance,
+        memory: &mut ThreadMemory,
+        error_depth: &mut Option<u16>,
+    ) -> Result<Value> {
+        debug!("Unwind runtime: {self:?}");
+        match self {
+            UnwindFn::CatchUnwind => {
+                let try_fn = memory.resolve_fn_ptr(args[0].read_uint() as usize)?;
+                let catch_fn = memory.resolve_fn_ptr(args[2].read_uint() as usize)?;
+                let data = args[1].clone();
+                match invoke_fn(try_fn, memory, vec![data.clone()], error_depth) {
+                    Ok(_) => Ok(Value::from_type(0i32)),
+                    Err(error) => {
+                        let panic = error.downcast::<Panic>()?;
+                        debug!("Caught panic: {}", panic.message);
+                        memory.caught_panic = Some(panic.payload);
+                        // The exception object is opaque to the program. It is
+                        // only forwarded to `cleanup`, which is shimmed below.
+                        let exception = Value::from_type(0usize);
+                        invoke_fn(catch_fn, memory, vec![data, exception], error_depth)?;
+                        Ok(Value::from_type(1i32))
+                    }
+                }
+            }
+            UnwindFn::Cleanup => {
+                let payload = memory
+                    .caught_panic
+                    .take()
+                    .context("No caught panic to clean up")?;
+                let box_ty = instance.fn_abi()?.ret.ty;
+                box_payload(memory, payload, box_ty)
+            }
+        }
+    }
+}
+
+impl FnInterpreter<'_> {
+    /// Build the panic message for a failed `Assert` terminator.
+    pub(super) fn assert_message(&self, msg: &AssertMessage) -> Result<String> {
+        let message = match msg {
+            AssertMessage::BoundsCheck { len, index } => {
+                let len = self.evaluate_operand(len)?.read_uint();
+                let index = self.evaluate_operand(index)?.read_uint();
+                format!("index out of bounds: the len is {len} but the index is {index}")
+            }
+            AssertMessage::MisalignedPointerDereference { required, found } => {
+                let required = self.evaluate_operand(required)?.read_uint();
+                let found = self.evaluate_operand(found)?.read_uint();
+                format!(
+                    "misaligned pointer dereference: address must be a multiple of \
+                     {required:#x} but is {found:#x}"
+                )
+            }
+            AssertMessage::NullPointerDereference => "null pointer dereference occurred".into(),
+            AssertMessage::InvalidEnumConstruction(source) => {
+                let source = self.evaluate_operand(source)?.read_uint();
+                format!("trying to construct an enum from an invalid value {source:#x}")
+            }
+            _ => msg.description()?.to_string(),
+        };
+        Ok(message)
+    }
+}
+
+/// Report a panic and return the error used to propagate it.
+///
+/// The report mimics the default panic hook. The location is the innermost
+/// call site that is not inside a `#[track_caller]` function.
+///
+/// Panics that cannot unwind abort the program right after the report.
+pub fn start_panic(memory: &ThreadMemory, message: String, can_unwind: bool) -> anyhow::Error {
+    let payload = PanicPayload::Message(message.clone());
+    raise_panic(memory, message, payload, can_unwind)
+}
+
+/// Turn a panic that escaped from native code into an interpreted panic.
+///
+/// The native panic hook already reported it, so it is not reported again.
+pub fn native_panic(memory: &ThreadMemory, message: String) -> anyhow::Error {
+    let payload = PanicPayload::Message(message.clone());
+    anyhow::Error::new(new_panic(memory, message, payload))
+}
+
+/// Report a panic carrying `payload` and return the error used to propagate it.
+fn raise_panic(
+    memory: &ThreadMemory,
+    message: String,
+    payload: PanicPayload,
+    can_unwind: bool,
+) -> anyhow::Error {
+    let panic = new_panic(memory, message, payload);
+
+    // The abort of a panic that cannot unwind reports the panic instead.
+    if can_unwind {
+        eprintln!("\n{panic}");
+    }
+    if backtrace_enabled() {
+        eprint!("stack backtrace:\n{}", memory.backtrace());
+    } else {
+        eprintln!("note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace");
+    }
+
+    if can_unwind {
+        anyhow::Error::new(panic)
+    } else {
+        anyhow::Error::new(Abort::non_unwinding(&panic))
+    }
+}
+
+/// Create a panic raised at the current location.
+fn new_panic(memory: &ThreadMemory, message: String, payload: PanicPayload) -> Panic {
+    let location = caller_location(memory).map_or_else(|| "<unknown>".into(), location_str);
+    Panic {
+        thread: memory.thread_name.clone(),
+        location,
+        message,
+        payload,
+    }
+}
+
+/// Box a panic payload as the `Box<dyn Any + Send>` type `box_ty`.
+fn box_payload(memory: &mut ThreadMemory, payload: PanicPayload, box_ty: Ty) -> Result<Value> {
+    let (value, ty) = match payload {
+        PanicPayload::Boxed(boxed) => return Ok(boxed),
+        PanicPayload::Value(value, ty) => (value, Ty::to_val(ty)),
+        PanicPayload::Message(message) => {
+            let addr = if message.is_empty() {
+                1
+            } else {
+                memory.intern_bytes(message.as_bytes())
+            };
+            let str_ty = Ty::from_rigid_kind(RigidTy::Str);
+            let static_region = Region {
+                kind: RegionKind::ReStatic,
+            };
+            let ty = Ty::new_ref(static_region, str_ty, Mutability::Not);
+            (Value::new_wide_ptr(addr, message.len()), ty)
+        }
+    };
+
+    let size = ty.size()?;
+    let addr = if size == 0 {
+        ty.alignment()?
+    } else {
+        let addr = memory.allocate(size, ty.alignment()?)?;
+        memory.write_addr(addr, value.as_bytes(), ty)?;
+        addr
+    };
+    let TyKind::RigidTy(RigidTy::Adt(_, args)) = box_ty.kind() else {
+        bail!("Expected a `Box` type, found `{box_ty}`");
+    };
+    let dyn_ty = args.0.first().and_then(|arg| arg.ty().copied());
+    let dyn_ty =
+        dyn_ty.with_context(|| format!("Expected a `Box<dyn Trait>`, found `{box_ty}`"))?;
+    let vtable = memory.vtable(ty, dyn_principal(dyn_ty)?)?;
+    Ok(Value::new_wide_ptr(addr, vtable))
+}
+
+/// Whether `RUST_BACKTRACE` asks for backtraces to be displayed.
+pub fn backtrace_enabled() -> bool {
+    !matches!(std::env::var("RUST_BACKTRACE").as_deref(), Err(_) | Ok("0"))
+}
+
+/// Find the span that `core::panic::Location::caller()` would report.
+///
+/// That is the call site of the innermost frame whose function does not have
+/// `#[track_caller]`.
+fn caller_location(memory: &ThreadMemory) -> Option<Span> {
+    memory
+        .call_stack()
+        .find(|(instance, _)| !requires_caller_location(*instance))
+        .and_then(|(_, span)| span)
+}
+
+/// Build the implicit `&Location` argument of a native `#[track_caller]` call.
+///
+/// The location is interned with a trailing NUL, like the ones the compiler
+/// emits, so `Location::file_as_c_str` works too.
+pub(super) fn caller_location_arg(memory: &ThreadMemory, location_ref: Ty) -> Result<Value> {
+    let location_ty = location_ref
+        .kind()
+        .builtin_deref(true)
+        .with_context(|| format!("Expected a reference, found `{location_ref}`"))?
+        .ty;
+    let (file, line, col) = caller_location(memory).map_or_else(
+        || ("<unknown>".to_string(), 0, 0),
+        |span| {
+            let lines = span.get_lines();
+            (span.get_filename(), lines.start_line, lines.start_col)
+        },
+    );
+    let file_addr = memory.intern_bytes(format!("{file}\0").as_bytes());
+
+    let mut location = Value::with_size(location_ty.size()?);
+    let fields = [
+        Value::new_wide_ptr(file_addr, file.len()),
+        Value::from_type(line as u32),
+        Value::from_type(col as u32),
+    ];
+    for (idx, field) in fields.iter().enumerate() {
+        let (offset, _) = struct_field(location_ty, idx)?;
+        location.as_bytes_mut()[offset..offset + field.len()].copy_from_slice(field.as_bytes());
+    }
+    let addr = memory.intern_bytes(location.as_bytes());
+    Ok(Value::from_type(addr))
+}
+
+/// Whether the instance receives an implicit `&Location` argument.
+///
+/// `#[track_caller]` is not exposed by `rustc_public`, so we look for the
+/// implicit trailing argument in the function ABI instead.
+pub(super) fn requires_caller_location(instance: Instance) -> bool {
+    let TyKind::RigidTy(RigidTy::FnDef(_, _)) = instance.ty().kind() else {
+        return false;
+    };
+    let Some(sig) = instance.ty().kind().fn_sig() else {
+        return false;
+    };
+    let Ok(fn_abi) = instance.fn_abi() else {
+        return false;
+    };
+    fn_abi.args.len() == sig.skip_binder().inputs().len() + 1
+        && fn_abi
+            .args
+            .last()
+            .is_some_and(|arg| is_location_ref(arg.ty))
+}
+
+/// Whether `ty` is `&core::panic::Location`.
+fn is_location_ref(ty: Ty) -> bool {
+    let TyKind::RigidTy(RigidTy::Ref(_, pointee, _)) = ty.kind() else {
+        return false;
+    };
+    let TyKind::RigidTy(RigidTy::Adt(def, _)) = pointee.kind() else {
+        return false;
+    };
+    matches!(
+        def.name().as_str(),
+        "core::panic::Location" | "std::panic::Location"
+    )
+}
+
+/// Render a span as `file:line:col`, like `core::panic::Location` does.
+fn location_str(span: Span) -> String {
+    let lines = span.get_lines();
+    format!(
+        "{}:{}:{}",
+        span.get_filename(),
+        lines.start_line,
+        lines.start_col
+    )
+}
+
+/// The type of the first argument of `instance`.
+fn first_arg_ty(instance: Instance) -> Result<Ty> {
+    let fn_abi = instance.fn_abi()?;
+    match fn_abi.args.first() {
+        Some(arg) => Ok(arg.ty),
+        None => bail!("Expected an argument for `{}`", instance.name()),
+    }
+}
+
+/// Read a `&str` from interpreter memory.
+fn read_str(memory: &ThreadMemory, value: &Value) -> Result<String> {
+    let ptr_size = pointer_width();
+    let bytes = value.as_bytes();
+    let addr = uint_from_bytes(&bytes[..ptr_size]) as usize;
+    let len = uint_from_bytes(&bytes[ptr_size..2 * ptr_size]) as usize;
+    read_utf8(memory, addr, len)
+}
+
+/// Read `len` bytes of UTF-8 text starting at `addr`.
+fn read_utf8(memory: &ThreadMemory, addr: usize, len: usize) -> Result<String> {
+    if len == 0 {
+        return Ok(String::new());
+    }
+    let data = memory.read_bytes(addr, len)?;
+    Ok(String::from_utf8_lossy(data.as_bytes()).into_owned())
+}
+
+/// Render a `fmt::Arguments` value, like `fmt::format` does.
+///
+/// The message is written into a `String` of the interpreted program by
+/// `core::fmt::write`. It only exists as native code, which calls the
+/// formatting functions of the arguments and the `fmt::Write` methods of the
+/// string through callbacks.
+fn format_arguments(
+    memory: &mut ThreadMemory,
+    args: &Value,
+    error_depth: &mut Option<u16>,
+) -> Result<String> {
+    let write_fn = find_fn("core", &["core::fmt::write", "std::fmt::write"])?;
+    let new_fn = find_fn(
+        "alloc",
+        &["alloc::string::String::new", "std::string::String::new"],
+    )?;
+    let as_str_fn = find_fn(
+        "alloc",
+        &[
+            "alloc::string::String::as_str",
+            "std::string::String::as_str",
+        ],
+    )?;
+    let string_ty = new_fn.fn_abi()?.ret.ty;
+    let TyKind::RigidTy(RigidTy::Ref(_, write_ty, _)) = first_arg_ty(write_fn)?.kind() else {
+        bail!("Unexpected signature for `{}`", write_fn.name());
+    };
+    let vtable = memory.vtable(string_ty, dyn_principal(write_ty)?)?;
+
+    let (size, align) = (string_ty.size()?, string_ty.alignment()?);
+    let string = invoke_fn(new_fn, memory, vec![], error_depth)?;
+    let addr = memory.allocate(size, align)?;
+    memory.write_addr(addr, string.as_bytes(), string_ty)?;
+    let output = Value::new_wide_ptr(addr, vtable);
+    let message = invoke_fn(write_fn, memory, vec![output, args.clone()], error_depth)
+        .and_then(|_| invoke_fn(as_str_fn, memory, vec![Value::from_type(addr)], error_depth))
+        .and_then(|message| read_str(memory, &message));
+
+    // Free the string, even if formatting failed.
+    let drop_fn = Instance::resolve_drop_in_place(string_ty);
+    invoke_fn(drop_fn, memory, vec![Value::from_type(addr)], error_depth)?;
+    memory.deallocate(addr, size, align)?;
+    message
+}
+
+/// Find the non-generic function named by one of `names` in the crate `krate`.
+///
+/// Items of `core` and `alloc` may be named by their `std` path.
+fn find_fn(krate: &str, names: &[&str]) -> Result<Instance> {
+    let def = rustc_public::find_crates(krate)
+        .iter()
+        .flat_map(|krate| krate.fn_defs())
+        .find(|def| names.contains(&def.name().as_str()))
+        .with_context(|| format!("Cannot find `{}`", names[0]))?;
+    Ok(Instance::resolve(def, &GenericArgs(vec![]))?)
+}
+
+/// Compute the message and the payload of `begin_panic::<M>(payload)`.
+///
+/// Like the default panic hook, only string literal payloads are printed.
+fn begin_panic_payload(
+    memory: &ThreadMemory,
+    payload: &Value,
+    instance: Instance,
+) -> Result<(String, PanicPayload)> {
+    let payload_ty = instance
+        .args()
+        .0
+        .first()
+        .and_then(|arg| arg.ty().copied())
+        .with_context(|| format!("Missing payload type in `{}`", instance.name()))?;
+    let is_str_ref = matches!(payload_ty.kind(), TyKind::RigidTy(RigidTy::Ref(_, pointee, _))
+            if pointee.kind() == TyKind::RigidTy(RigidTy::Str));
+    let message = if is_str_ref {
+        read_str(memory, payload)?
+    } else {
+        "Box<dyn Any>".to_string()
+    };
+    let payload = PanicPayload::Value(payload.clone(), payload_ty.to_index());
+    Ok((message, payload))
+}
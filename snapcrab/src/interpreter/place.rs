@@ -6,18 +6,23 @@
 
 use crate::ty::MonoType;
 use crate::value::Value;
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use rustc_public::abi::VariantsShape;
 use rustc_public::mir::{Place, ProjectionElem};
 use rustc_public::ty::{RigidTy, Ty, TyKind, VariantIdx};
 use rustc_public_bridge::IndexedVal;
 
+use super::check::validate_load;
 use super::function;
+use super::intrinsics::size_and_align_of;
 
 /// State tracked while resolving place projections.
 struct PlaceState {
     addr: usize,
     ty: Ty,
+    /// Alignment that the address must have. This is the alignment of `ty`,
+    /// unless the place is a field of a packed struct.
+    align: usize,
     /// Active variant after a Downcast projection; cleared after Field use.
     downcast: Option<VariantIdx>,
     /// Pointer metadata from derefing a wide pointer. Preserved through Field
@@ -28,9 +33,9 @@ struct PlaceState {
 impl<'a> function::FnInterpreter<'a> {
     /// Assigns a value to a place (local variable or memory location).
     pub(super) fn assign_to_place(&mut self, place: &Place, value: Value) -> Result<()> {
-        let addr = self.resolve_place_addr(place)?;
-        let place_ty = place.ty(self.locals())?;
-        self.memory.write_addr(addr, value.as_bytes(), place_ty)?;
+        let state = self.resolve_place(place)?;
+        self.memory
+            .write_addr_with_align(state.addr, value.as_bytes(), state.ty, state.align)?;
         Ok(())
     }
 
@@ -48,6 +53,7 @@ impl<'a> function::FnInterpreter<'a> {
             PlaceState {
                 addr: initial_addr,
                 ty: initial_ty,
+                align: initial_ty.alignment()?,
                 downcast: None,
                 metadata: None,
             },
@@ -58,6 +64,32 @@ impl<'a> function::FnInterpreter<'a> {
     /// Resolve a place and construct a pointer value (thin or wide).
     pub(super) fn place_to_ptr(&self, place: &Place, result_ty: Ty) -> Result<Value> {
         let state = self.resolve_place(place)?;
+        Self::state_to_ptr(state, result_ty)
+    }
+
+    /// Resolve a place and construct a reference to it.
+    ///
+    /// Unlike raw pointers, references must point to a live allocation that
+    /// covers the entire pointee, and they must be aligned.
+    pub(super) fn place_to_ref(&self, place: &Place, result_ty: Ty) -> Result<Value> {
+        let state = self.resolve_place(place)?;
+        let metadata = state
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.read_uint() as usize);
+        let (size, align) = size_and_align_of(self.memory, state.ty, metadata)?;
+        self.memory
+            .check_reference(state.addr, size, align)
+            .map_err(|error| {
+                anyhow!(
+                    "Undefined behavior: invalid reference to `{}`: {error}",
+                    state.ty
+                )
+            })?;
+        Self::state_to_ptr(state, result_ty)
+    }
+
+    fn state_to_ptr(state: PlaceState, result_ty: Ty) -> Result<Value> {
         if result_ty.is_wide_ptr() {
             let metadata = state.metadata.context(
                 "Expected metadata for wide pointer, but place resolved without metadata",
@@ -79,6 +111,7 @@ impl<'a> function::FnInterpreter<'a> {
         let PlaceState {
             addr: current_addr,
             ty: current_ty,
+            align: current_align,
             downcast,
             metadata,
         } = state;
@@ -90,7 +123,9 @@ impl<'a> function::FnInterpreter<'a> {
                     _ => bail!("Cannot dereference non-pointer type: {:?}", current_ty),
                 };
 
-                let ptr_value = self.memory.read_addr(current_addr, current_ty)?;
+                let ptr_value =
+                    self.memory
+                        .read_addr_with_align(current_addr, current_ty, current_align)?;
 
                 // Extract metadata if this is a wide pointer deref.
                 let new_metadata = if current_ty.is_wide_ptr() {
@@ -101,9 +136,11 @@ impl<'a> function::FnInterpreter<'a> {
 
                 let address = ptr_value.to_data_addr()?.as_type::<usize>().unwrap();
 
+                // The pointee must be aligned, even if the pointer was read from a packed field.
                 Ok(PlaceState {
                     addr: address,
                     ty: pointee_ty,
+                    align: pointee_ty.alignment()?,
                     downcast: None,
                     metadata: new_metadata,
                 })
@@ -160,9 +197,21 @@ impl<'a> function::FnInterpreter<'a> {
                 } else {
                     None
                 };
+                // An unsized tail starts at the next multiple of its dynamic
+                // alignment, e.g., the alignment of a trait object's type.
+                let field_offset = match &field_metadata {
+                    Some(metadata) => {
+                        let metadata = Some(metadata.read_uint() as usize);
+                        let (_, tail_align) = size_and_align_of(self.memory, *field_ty, metadata)?;
+                        field_offset.next_multiple_of(tail_align)
+                    }
+                    None => field_offset,
+                };
+                // Fields of packed structs are only as aligned as the struct.
                 Ok(PlaceState {
                     addr: current_addr + field_offset,
                     ty: *field_ty,
+                    align: current_align.min(field_ty.alignment()?),
                     downcast: None,
                     metadata: field_metadata,
                 })
@@ -170,6 +219,7 @@ impl<'a> function::FnInterpreter<'a> {
             ProjectionElem::Downcast(variant_idx) => Ok(PlaceState {
                 addr: current_addr,
                 ty: current_ty,
+                align: current_align,
                 downcast: Some(*variant_idx),
                 metadata,
             }),
@@ -193,10 +243,12 @@ impl<'a> function::FnInterpreter<'a> {
                     }
                     _ => bail!("Cannot index non-array type: {current_ty:?}"),
                 };
+                self.check_index(index, current_ty, &metadata)?;
 
                 Ok(PlaceState {
                     addr: current_addr + index * stride,
                     ty: element_ty,
+                    align: current_align.min(element_ty.alignment()?),
                     downcast: None,
                     metadata: None,
                 })
@@ -211,14 +263,16 @@ impl<'a> function::FnInterpreter<'a> {
                 let index = if *from_end {
                     // Index from end: need the actual length.
                     let len = self.array_or_slice_len(current_ty, &metadata)?;
-                    len - (*offset as usize)
+                    len.wrapping_sub(*offset as usize)
                 } else {
                     *offset as usize
                 };
+                self.check_index(index, current_ty, &metadata)?;
 
                 Ok(PlaceState {
                     addr: current_addr + index * stride,
                     ty: element_ty,
+                    align: current_align.min(element_ty.alignment()?),
                     downcast: None,
                     metadata: None,
                 })
@@ -227,19 +281,26 @@ impl<'a> function::FnInterpreter<'a> {
                 let (element_ty, stride) = self.element_ty_and_stride(current_ty)?;
                 let new_addr = current_addr + (*from as usize) * stride;
 
-                let new_len = if *from_end {
-                    // slice[from..len - to]
-                    let len = self.array_or_slice_len(current_ty, &metadata)?;
-                    len - (*from as usize) - (*to as usize)
-                } else {
-                    // array[from..to]
-                    (*to as usize) - (*from as usize)
-                };
+                let len = self.array_or_slice_len(current_ty, &metadata)?;
+                let (from, to) = (*from as usize, *to as usize);
+                // The subslice is `[from..len - to]` if `from_end`, and `[from..to]` otherwise.
+                let end = if *from_end { len.wrapping_sub(to) } else { to };
+                self.memory.check_config.bounds.check(|| {
+                    if from > end || end > len {
+                        bail!(
+                            "Undefined behavior: subslice {from}..{end} out of bounds for \
+                             length {len}"
+                        );
+                    }
+                    Ok(())
+                })?;
+                let new_len = end.wrapping_sub(from);
 
                 let slice_ty = Ty::from_rigid_kind(RigidTy::Slice(element_ty));
                 Ok(PlaceState {
                     addr: new_addr,
                     ty: slice_ty,
+                    align: current_align,
                     downcast: None,
                     metadata: Some(Value::from_type(new_len)),
                 })
@@ -247,12 +308,24 @@ impl<'a> function::FnInterpreter<'a> {
             ProjectionElem::OpaqueCast(ty) => Ok(PlaceState {
                 addr: current_addr,
                 ty: *ty,
+                align: current_align,
                 downcast: None,
                 metadata,
             }),
         }
     }
 
+    /// Check that `index` is in bounds of the array or slice of type `ty`.
+    fn check_index(&self, index: usize, ty: Ty, metadata: &Option<Value>) -> Result<()> {
+        self.memory.check_config.bounds.check(|| {
+            let len = self.array_or_slice_len(ty, metadata)?;
+            if index >= len {
+                bail!("Undefined behavior: index {index} out of bounds for length {len}");
+            }
+            Ok(())
+        })
+    }
+
     /// Get element type and stride for an array or slice type.
     fn element_ty_and_stride(&self, ty: Ty) -> Result<(Ty, usize)> {
         match ty.kind() {
@@ -289,7 +362,11 @@ impl<'a> function::FnInterpreter<'a> {
             return Ok(Value::unit().clone());
         }
 
-        let addr = self.resolve_place_addr(place)?;
-        self.memory.read_addr(addr, place_ty)
+        let state = self.resolve_place(place)?;
+        let value = self
+            .memory
+            .read_addr_with_align(state.addr, place_ty, state.align)?;
+        validate_load(&value, place_ty, &self.memory.check_config)?;
+        Ok(value)
     }
 }
@@ -1,14 +1,23 @@
-use crate::ty::MonoType;
+use crate::memory::ThreadMemory;
+use crate::ty::{MonoType, dyn_principal};
 use crate::value::{Value, uint_from_bytes};
 use anyhow::{Context, Result, bail};
-use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedNeg, CheckedSub, Zero};
+use num_traits::{
+    CheckedAdd, CheckedDiv, CheckedMul, CheckedNeg, CheckedRem, CheckedSub, WrappingAdd,
+    WrappingMul, WrappingSub, Zero,
+};
 use rustc_public::abi::{TagEncoding, VariantsShape};
+use rustc_public::mir::mono::{Instance, StaticDef};
 use rustc_public::mir::{AggregateKind, BinOp, CastKind, Operand, PointerCoercion, Rvalue, UnOp};
-use rustc_public::ty::{AdtDef, IntTy, RigidTy, Ty, TyKind, TypeAndMut, UintTy, VariantIdx};
+use rustc_public::ty::{
+    AdtDef, AdtKind, ClosureKind, IntTy, RigidTy, Ty, TyKind, UintTy, VariantIdx,
+};
 use rustc_public_bridge::IndexedVal;
 use zerocopy::{FromBytes, Immutable, IntoBytes};
 
+use super::float::{eval_float_binop, eval_float_unop, float_to_float, float_to_int, int_to_float};
 use super::function::FnInterpreter;
+use super::intrinsics::eval_overflowing_binop;
 
 /// Trait for evaluating binary operations on values.
 pub trait BinaryEval {
@@ -59,7 +68,11 @@ impl BinaryEval for BinOp {
                 UintTy::Usize => eval_int_binop::<usize>(*self, left, right),
             },
             RigidTy::Bool => eval_bool_binop(*self, left, right),
+            RigidTy::Float(float_ty) => eval_float_binop(*self, left, right, float_ty),
             RigidTy::Char => eval_int_binop::<u32>(*self, left, right),
+            RigidTy::RawPtr(pointee, _) if *self == BinOp::Offset => {
+                eval_ptr_offset(left, right, pointee)
+            }
             RigidTy::RawPtr(_, _) | RigidTy::Ref(_, _, _) => {
                 let ty = Ty::from_rigid_kind(operand_type.clone());
                 if ty.is_thin_ptr() {
@@ -79,6 +92,11 @@ impl BinaryEval for BinOp {
 impl UnaryEval for UnOp {
     fn eval(&self, operand: &Value, result_type: RigidTy) -> Result<Value> {
         match self {
+            UnOp::Not if matches!(result_type, RigidTy::Int(_) | RigidTy::Uint(_)) => {
+                // Bitwise complement doesn't depend on the signedness.
+                let bytes: Vec<u8> = operand.as_bytes().iter().map(|byte| !byte).collect();
+                Ok(Value::from_bytes(&bytes))
+            }
             UnOp::Not | UnOp::Neg => match result_type {
                 RigidTy::Int(int_ty) => match int_ty {
                     IntTy::I8 => eval_int_unop::<i8>(*self, operand),
@@ -89,6 +107,7 @@ impl UnaryEval for UnOp {
                     IntTy::Isize => eval_int_unop::<isize>(*self, operand),
                 },
                 RigidTy::Bool => eval_bool_unop(*self, operand),
+                RigidTy::Float(float_ty) => eval_float_unop(*self, operand, float_ty),
                 RigidTy::Uint(_) => bail!("Unary operations on unsigned integers not supported"),
                 _ => bail!(
                     "Unsupported operation `{self:?}` on `{}` type",
@@ -112,7 +131,11 @@ where
         + CheckedAdd
         + CheckedDiv
         + CheckedMul
+        + CheckedRem
         + CheckedSub
+        + WrappingAdd
+        + WrappingMul
+        + WrappingSub
         + PartialEq
         + PartialOrd
         + Zero
@@ -122,26 +145,37 @@ where
 {
     let left = l.as_type::<T>().unwrap();
     let right = r.as_type::<T>().unwrap();
+    // Overflow checks are lowered to `*WithOverflow` operations and
+    // assertions, so `Add`, `Sub` and `Mul` wrap. The compiler also guards
+    // `Div` and `Rem` with assertions, so their operands are only invalid
+    // when lowered from the `unchecked_div` and `unchecked_rem` intrinsics.
+    let overflow = || anyhow::anyhow!("Undefined behavior: overflow in `{op:?}`");
     match op {
-        BinOp::Add => left
+        BinOp::Add => Ok(Value::from_type(left.wrapping_add(&right))),
+        BinOp::Sub => Ok(Value::from_type(left.wrapping_sub(&right))),
+        BinOp::Mul => Ok(Value::from_type(left.wrapping_mul(&right))),
+        BinOp::AddUnchecked => left
             .checked_add(&right)
             .map(Value::from_type)
-            .with_context(|| format!("Attempt to {op:?} with overflow")),
-        BinOp::Sub => left
+            .ok_or_else(overflow),
+        BinOp::SubUnchecked => left
             .checked_sub(&right)
             .map(Value::from_type)
-            .with_context(|| format!("Attempt to {op:?} with overflow")),
-        BinOp::Mul => left
+            .ok_or_else(overflow),
+        BinOp::MulUnchecked => left
             .checked_mul(&right)
             .map(Value::from_type)
-            .with_context(|| format!("Attempt to {op:?} with overflow")),
-        BinOp::Div => {
+            .ok_or_else(overflow),
+        BinOp::Div | BinOp::Rem => {
             if right == <T as Zero>::zero() {
-                bail!("Division by zero");
+                bail!("Undefined behavior: division by zero in `{op:?}`");
             }
-            left.checked_div(&right)
-                .map(Value::from_type)
-                .with_context(|| format!("Attempt to {op:?} with overflow"))
+            let result = if op == BinOp::Div {
+                left.checked_div(&right)
+            } else {
+                left.checked_rem(&right)
+            };
+            result.map(Value::from_type).ok_or_else(overflow)
         }
         BinOp::BitAnd => Ok(Value::from_type(left & right)),
         BinOp::BitOr => Ok(Value::from_type(left | right)),
@@ -152,10 +186,27 @@ where
         BinOp::Le => Ok(Value::from_bool(left <= right)),
         BinOp::Gt => Ok(Value::from_bool(left > right)),
         BinOp::Ge => Ok(Value::from_bool(left >= right)),
+        BinOp::Cmp => {
+            // `Ordering` is represented as an `i8` of -1, 0 or 1.
+            let ordering = left.partial_cmp(&right).unwrap();
+            Ok(Value::from_type(ordering as i8))
+        }
         _ => bail!("Unsupported integer binary operation: {:?}", op),
     }
 }
 
+/// Evaluates `ptr.offset(count)`, i.e., `ptr + count * size_of::<Pointee>()`.
+///
+/// The count may be either `isize` or `usize`; both wrap identically.
+fn eval_ptr_offset(ptr: &Value, count: &Value, pointee: Ty) -> Result<Value> {
+    let addr = ptr.as_type::<usize>().context("Expected thin pointer")?;
+    let count = count
+        .as_type::<usize>()
+        .context("Expected pointer-sized offset")?;
+    let offset = count.wrapping_mul(pointee.size()?);
+    Ok(Value::from_type(addr.wrapping_add(offset)))
+}
+
 /// Evaluates a comparison on wide pointers (both halves must match).
 fn eval_wide_ptr_binop(op: BinOp, l: &Value, r: &Value, operand_type: &RigidTy) -> Result<Value> {
     let pointee = match operand_type {
@@ -242,6 +293,7 @@ fn eval_bool_binop(op: BinOp, l: &Value, r: &Value) -> Result<Value> {
     let result = match op {
         BinOp::BitAnd => left & right,
         BinOp::BitOr => left | right,
+        BinOp::BitXor => left ^ right,
         BinOp::Eq => left == right,
         BinOp::Ne => left != right,
         _ => bail!("Unsupported boolean binary operation: {:?}", op),
@@ -303,7 +355,11 @@ impl<'a> FnInterpreter<'a> {
                 op.eval(&val, result_type)
             }
             Rvalue::Use(operand, _) => self.evaluate_operand(operand),
-            Rvalue::Ref(_, _, place) | Rvalue::AddressOf(_, place) => {
+            Rvalue::Ref(_, _, place) => {
+                let ty = rvalue.ty(self.locals())?;
+                self.place_to_ref(place, ty)
+            }
+            Rvalue::AddressOf(_, place) => {
                 let ty = rvalue.ty(self.locals())?;
                 self.place_to_ptr(place, ty)
             }
@@ -333,17 +389,11 @@ impl<'a> FnInterpreter<'a> {
             Rvalue::CheckedBinaryOp(op, left, right) => {
                 let left_val = self.evaluate_operand(left)?;
                 let right_val = self.evaluate_operand(right)?;
-                let operand_type = left.ty(self.locals())?.kind().rigid().unwrap().clone();
+                let operand_ty = left.ty(self.locals())?;
                 let result_ty = rvalue.ty(self.locals())?;
-                match op.eval(&left_val, &right_val, operand_type) {
-                    Ok(val) => {
-                        Value::from_tuple_with_layout(&[val, Value::from_bool(false)], result_ty)
-                    }
-                    Err(_) => {
-                        let zero = Value::with_size(left_val.len());
-                        Value::from_tuple_with_layout(&[zero, Value::from_bool(true)], result_ty)
-                    }
-                }
+                let (val, overflow) =
+                    eval_overflowing_binop(*op, &left_val, &right_val, operand_ty)?;
+                Value::from_tuple_with_layout(&[val, Value::from_bool(overflow)], result_ty)
             }
             Rvalue::Discriminant(place) => {
                 let enum_ty = place.ty(self.locals())?;
@@ -356,6 +406,11 @@ impl<'a> FnInterpreter<'a> {
                 let count_val = count.eval_target_usize()? as usize;
                 Ok(Value::from_repeated(&value, count_val))
             }
+            Rvalue::ThreadLocalRef(item) => {
+                let def = StaticDef::try_from(*item)?;
+                let address = self.memory.thread_local_addr(def)?;
+                Ok(Value::from_type(address))
+            }
             _ => {
                 bail!("Unsupported rvalue: {:?}", rvalue);
             }
@@ -399,6 +454,20 @@ impl<'a> FnInterpreter<'a> {
                 }
                 Ok(Value::from_array(&values))
             }
+            AggregateKind::RawPtr(..) => {
+                // Operands are the data pointer and the (possibly unit) metadata.
+                let data = self.evaluate_operand(&operands[0])?;
+                let metadata = self.evaluate_operand(&operands[1])?;
+                if metadata.is_unit() {
+                    Ok(data)
+                } else {
+                    let data_addr = data.as_type::<usize>().context("Expected thin pointer")?;
+                    Ok(Value::new_wide_ptr(
+                        data_addr,
+                        metadata.read_uint() as usize,
+                    ))
+                }
+            }
             _ => bail!("Unsupported aggregate kind: {:?}", kind),
         }
     }
@@ -433,7 +502,25 @@ impl<'a> FnInterpreter<'a> {
                     Ok(value.as_bytes()[..target_size].into())
                 }
             }
-            CastKind::PtrToPtr => {
+            CastKind::FloatToInt => match (source_ty.kind(), target_ty.kind()) {
+                (TyKind::RigidTy(RigidTy::Float(src)), TyKind::RigidTy(dst)) => {
+                    float_to_int(&value, src, &dst)
+                }
+                _ => bail!("Expected cast from float to int, found `{source_ty}` to `{target_ty}`"),
+            },
+            CastKind::IntToFloat => match (source_ty.kind(), target_ty.kind()) {
+                (TyKind::RigidTy(src), TyKind::RigidTy(RigidTy::Float(dst))) => {
+                    int_to_float(&value, &src, dst)
+                }
+                _ => bail!("Expected cast from int to float, found `{source_ty}` to `{target_ty}`"),
+            },
+            CastKind::FloatToFloat => match (source_ty.kind(), target_ty.kind()) {
+                (TyKind::RigidTy(RigidTy::Float(src)), TyKind::RigidTy(RigidTy::Float(dst))) => {
+                    float_to_float(&value, src, dst)
+                }
+                _ => bail!("Expected cast between floats, found `{source_ty}` to `{target_ty}`"),
+            },
+            CastKind::PtrToPtr | CastKind::FnPtrToPtr => {
                 if target_ty.is_wide_ptr() {
                     bail!("Expected cast to thin pointer, but found: `{target_ty}")
                 } else if source_ty.is_wide_ptr() {
@@ -443,8 +530,25 @@ impl<'a> FnInterpreter<'a> {
                 }
             }
             CastKind::PointerCoercion(PointerCoercion::Unsize) => {
-                perform_unsized_coercion(value, source_ty, target_ty)
+                perform_unsized_coercion(self.memory, value, source_ty, target_ty)
+            }
+            CastKind::PointerCoercion(PointerCoercion::ReifyFnPointer(_)) => {
+                let TyKind::RigidTy(RigidTy::FnDef(def, args)) = source_ty.kind() else {
+                    bail!("Expected a function item, but found `{source_ty}`")
+                };
+                let instance = Instance::resolve_for_fn_ptr(def, &args)?;
+                Ok(Value::from_type(self.memory.fn_ptr(instance)))
             }
+            CastKind::PointerCoercion(PointerCoercion::ClosureFnPointer(_)) => {
+                let TyKind::RigidTy(RigidTy::Closure(def, args)) = source_ty.kind() else {
+                    bail!("Expected a closure, but found `{source_ty}`")
+                };
+                // Like the compiler, point to the `FnOnce` implementation.
+                // See `FnInterpreter::fn_ptr_callee` for how it is called.
+                let instance = Instance::resolve_closure(def, &args, ClosureKind::FnOnce)?;
+                Ok(Value::from_type(self.memory.fn_ptr(instance)))
+            }
+            CastKind::PointerCoercion(PointerCoercion::UnsafeFnPointer) => Ok(value),
             CastKind::Transmute => {
                 super::check::validate_value(&value, target_ty, &self.memory.check_config)?;
                 Ok(value)
@@ -464,64 +568,108 @@ impl<'a> FnInterpreter<'a> {
 ///   - Structs containing thin pointers to structs containing wide pointers
 ///   - Conversion between wide pointers.
 ///       - E.g.: `&(dyn Any + Send)` to `&dyn Any`.
-fn perform_unsized_coercion(value: Value, src_ptr_ty: Ty, dst_ptr_ty: Ty) -> Result<Value> {
-    let src_pointee_kind = src_ptr_ty
-        .kind()
-        .builtin_deref(true)
-        .map(|TypeAndMut { ty, .. }| ty.kind())
-        .context("Expected pointer coercion, found source `{src_ptr_ty}`")?;
-    let dst_pointee_kind = dst_ptr_ty
-        .kind()
-        .builtin_deref(true)
-        .map(|TypeAndMut { ty, .. }| ty.kind())
-        .context("Expected pointer coercion, found target `{src_ptr_ty}`")?;
-
-    if src_pointee_kind == dst_pointee_kind {
+fn perform_unsized_coercion(
+    memory: &ThreadMemory,
+    value: Value,
+    src_ptr_ty: Ty,
+    dst_ptr_ty: Ty,
+) -> Result<Value> {
+    let (src_pointee, dst_pointee) = coerced_pointees(src_ptr_ty, dst_ptr_ty)?;
+    if src_pointee == dst_pointee {
         // In case of redundant cast
-        Ok(value)
-    } else if dst_pointee_kind.is_slice() {
-        // [T; N] -> &[T]
-        let data_ptr = value.as_type::<usize>().context("Expected pointer value")?;
-        let TyKind::RigidTy(RigidTy::Array(_, len_const)) = src_pointee_kind else {
-            bail!("Expected array for coercion to slice, but found {dst_ptr_ty}")
-        };
-        let len = len_const.eval_target_usize()? as usize;
-
-        Ok(Value::new_wide_ptr(data_ptr, len))
-    } else if dst_pointee_kind.is_struct() {
-        // Container coercion: &Struct<[T; N]> -> &Struct<[T]>
-        // The data pointer stays the same; we extract the array length from the
-        // source type's unsized tail and use it as slice metadata.
-        let data_ptr = value.as_type::<usize>().context("Expected pointer value")?;
-        let metadata = extract_unsized_metadata(src_pointee_kind)?;
-        Ok(Value::new_wide_ptr(data_ptr, metadata))
-    } else {
-        // TODO: support trait object coercion (e.g., &T -> &dyn Trait).
-        // See test_wrapper_dyn_debug.
+        return Ok(value);
+    }
+    // Smart pointers like `Rc<T>` store their (possibly wide) pointer first.
+    let ptr_width = crate::memory::pointer_width();
+    let bytes = value.as_bytes();
+    if bytes.len() < ptr_width {
+        bail!("Expected pointer value for coercion from `{src_ptr_ty}`");
+    }
+    let data_ptr = uint_from_bytes(&bytes[..ptr_width]) as usize;
+    let old_metadata = (bytes.len() >= 2 * ptr_width)
+        .then(|| uint_from_bytes(&bytes[ptr_width..2 * ptr_width]) as usize);
+    let metadata = unsized_metadata(memory, src_pointee, dst_pointee, old_metadata)?;
+    Ok(Value::new_wide_ptr(data_ptr, metadata))
+}
+
+/// Find the pointee types that an unsized coercion changes.
+///
+/// For smart pointers such as `Rc<T>` -> `Rc<dyn Trait>`, this follows the
+/// field whose type changes until it reaches a builtin pointer.
+fn coerced_pointees(src_ptr_ty: Ty, dst_ptr_ty: Ty) -> Result<(Ty, Ty)> {
+    let src_kind = src_ptr_ty.kind();
+    let dst_kind = dst_ptr_ty.kind();
+    if let (Some(src), Some(dst)) = (src_kind.builtin_deref(true), dst_kind.builtin_deref(true)) {
+        return Ok((src.ty, dst.ty));
+    }
+    if let (TyKind::RigidTy(RigidTy::Pat(src, _)), TyKind::RigidTy(RigidTy::Pat(dst, _))) =
+        (&src_kind, &dst_kind)
+    {
+        // E.g.: the non-null pointer inside `NonNull<T>`.
+        return coerced_pointees(*src, *dst);
+    }
+    let (
+        TyKind::RigidTy(RigidTy::Adt(src_def, src_args)),
+        TyKind::RigidTy(RigidTy::Adt(dst_def, dst_args)),
+    ) = (src_kind, dst_kind)
+    else {
+        bail!("Unsupported coercion {src_ptr_ty} -> {dst_ptr_ty}")
+    };
+    if src_def != dst_def || src_def.kind() != AdtKind::Struct {
         bail!("Unsupported coercion {src_ptr_ty} -> {dst_ptr_ty}")
     }
+    let variant = &src_def.variants()[0];
+    variant
+        .fields()
+        .iter()
+        .map(|field| (field.ty_with_args(&src_args), field.ty_with_args(&dst_args)))
+        .find(|(src, dst)| src != dst)
+        .with_context(|| format!("Unsupported coercion {src_ptr_ty} -> {dst_ptr_ty}"))
+        .and_then(|(src, dst)| coerced_pointees(src, dst))
 }
 
-/// Extract the metadata for an unsized coercion from the source type.
+/// Compute the metadata for a pointer coerced from `src` to the unsized `dst`.
 ///
-/// For container coercion (e.g., Wrapper<[T; N]> → Wrapper<[T]>), this walks
-/// the struct to find the sized tail (an array) and returns its length.
-fn extract_unsized_metadata(src_pointee_kind: TyKind) -> Result<usize> {
-    match src_pointee_kind {
-        TyKind::RigidTy(RigidTy::Array(_, len_const)) => {
+/// This is either the length of an array coerced to a slice, or the vtable
+/// for a type coerced to a trait object. Coercions between trait objects
+/// (upcasting) look up the concrete type from the vtable in `old_metadata`.
+/// Structs recurse into their unsized tail, e.g., `Wrapper<[T; N]>` ->
+/// `Wrapper<[T]>`.
+fn unsized_metadata(
+    memory: &ThreadMemory,
+    src: Ty,
+    dst: Ty,
+    old_metadata: Option<usize>,
+) -> Result<usize> {
+    match (src.kind(), dst.kind()) {
+        (TyKind::RigidTy(RigidTy::Array(_, len_const)), TyKind::RigidTy(RigidTy::Slice(_))) => {
             Ok(len_const.eval_target_usize()? as usize)
         }
-        TyKind::RigidTy(RigidTy::Adt(def, ref args)) => {
+        (TyKind::RigidTy(RigidTy::Dynamic(..)), TyKind::RigidTy(RigidTy::Dynamic(..))) => {
+            let vtable =
+                old_metadata.with_context(|| format!("Expected a wide pointer to `{src}`"))?;
+            let concrete_ty = memory.vtable_ty(vtable)?;
+            memory.vtable(concrete_ty, dyn_principal(dst)?)
+        }
+        (_, TyKind::RigidTy(RigidTy::Dynamic(..))) => memory.vtable(src, dyn_principal(dst)?),
+        (
+            TyKind::RigidTy(RigidTy::Adt(src_def, src_args)),
+            TyKind::RigidTy(RigidTy::Adt(dst_def, dst_args)),
+        ) if src_def == dst_def => {
             // The unsized tail is the last field. Recurse into it.
-            let variants = def.variants();
-            let fields = variants[0].fields();
-            let last_field = fields
-                .last()
+            let variants = src_def.variants();
+            let last_field = variants[0]
+                .fields()
+                .pop()
                 .context("Expected at least one field in container struct")?;
-            let field_ty = last_field.ty_with_args(args);
-            extract_unsized_metadata(field_ty.kind())
+            unsized_metadata(
+                memory,
+                last_field.ty_with_args(&src_args),
+                last_field.ty_with_args(&dst_args),
+                old_metadata,
+            )
         }
-        _ => bail!("Cannot extract unsized metadata from {src_pointee_kind:?}"),
+        _ => bail!("Unsupported unsized coercion `{src}` -> `{dst}`"),
     }
 }
 
@@ -645,7 +793,7 @@ pub(super) fn write_discriminant(
                     }
                     let niche_start_idx = niche_variants.start().to_index();
                     let relative = variant_idx.to_index() - niche_start_idx;
-                    niche_start.wrapping_add(relative as u128)
+                    (*niche_start).wrapping_add(relative as u128)
                 }
             };
             write_uint(&mut enum_val[tag_off..tag_off + tag_sz], tag_val);
@@ -656,7 +804,7 @@ pub(super) fn write_discriminant(
 }
 
 /// Build an enum variant value with fields placed at the correct offsets.
-fn build_enum_variant(
+pub(super) fn build_enum_variant(
     field_values: &[Value],
     enum_ty: Ty,
     _def: AdtDef,
@@ -725,6 +873,12 @@ mod tests {
             &Value::from_type(1i128),
             RigidTy::Int(IntTy::I128),
         );
+        assert_eq!(result.unwrap(), Value::from_type(i128::MIN));
+        let result = BinOp::AddUnchecked.eval(
+            &Value::from_type(i128::MAX),
+            &Value::from_type(1i128),
+            RigidTy::Int(IntTy::I128),
+        );
         assert!(result.is_err());
     }
 
@@ -816,6 +970,16 @@ mod tests {
                 .unwrap(),
             Value::from_bool(true)
         );
+        assert_eq!(
+            BinOp::BitXor
+                .eval(
+                    &Value::from_bool(true),
+                    &Value::from_bool(true),
+                    RigidTy::Bool
+                )
+                .unwrap(),
+            Value::from_bool(false)
+        );
         assert_eq!(
             BinOp::Eq
                 .eval(
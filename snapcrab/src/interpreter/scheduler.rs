@@ -0,0 +1,597 @@
+//! Scheduling of interpreted threads.
+//!
+//! Interpreted threads run as coroutines on the host thread of the
+//! interpreter, each on its own host stack, and only one of them runs at a
+//! time. A spawned thread runs as soon as it's spawned, until it blocks on a
+//! lock, a parker or a join, until it reaches an atomic operation or a spin
+//! hint, or until it finishes, and the thread that gives up control hands it
+//! to the next runnable thread. Since a thread that spins on an atomic gives
+//! up control on every iteration, it cannot starve the thread it waits for.
+//!
+//! When a schedule seed is set, the running thread also reaches a yield point
+//! at every function call, which includes atomic operations. A pseudo-random
+//! number generator seeded with the schedule seed picks the thread that runs
+//! after a yield point, or after a thread gives up control, so the same seed
+//! always replays the same interleaving.
+//!
+//! Native code may hold locks and thread-locals of the host thread, such as
+//! the lock of stdout that `println!` holds while it calls an interpreted
+//! `Display` implementation. Threads don't take turns until the native call
+//! returns, so another thread cannot take them over. A thread that blocks
+//! meanwhile fails, unless its wait may time out, in which case it times out
+//! right away.
+//!
+//! Timed waits, such as `sleep`, may expire whenever the scheduler picks the
+//! next thread. Without a seed, they only expire once no other thread can
+//! run. A thread that blocks while no other thread can run is reported as a
+//! deadlock, along with the interpreted backtrace of every thread.
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+mod coroutine;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+#[path = "scheduler/unsupported.rs"]
+mod coroutine;
+
+use super::native::in_native_call;
+use super::panic::Abort;
+use crate::memory::{Backtrace, ThreadMemory};
+use anyhow::{Error, Result, anyhow, bail};
+use coroutine::Coroutine;
+use std::cell::RefCell;
+use std::fmt::{self, Write};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tracing::debug;
+
+/// One in `PREEMPTION_ODDS` yield points lets the scheduler pick the next
+/// thread.
+const PREEMPTION_ODDS: u64 = 4;
+
+/// Index of the main thread, which reports the failures of other threads.
+const MAIN_THREAD: usize = 0;
+
+/// The error of a spawned thread, which was reported with the location and
+/// backtrace of that thread. The main thread propagates it unchanged.
+#[derive(Debug)]
+pub struct ThreadFailure(String);
+
+impl fmt::Display for ThreadFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ThreadFailure {}
+
+/// What a blocked thread is waiting for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// A wake up of the futex at `address`, which holds `expected`, and which
+    /// implements a `kind` of synchronization primitive, such as a mutex.
+    Futex {
+        address: usize,
+        expected: u32,
+        kind: &'static str,
+    },
+    /// The end of the thread with the given id.
+    Join(u64),
+    /// An unpark of a thread that has no handle, which cannot happen.
+    Park,
+    /// The end of a sleep, which is always a timed wait.
+    Sleep,
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Block::Futex { address, kind, .. } => {
+                write!(f, "waiting on the {kind} at 0x{address:x}")
+            }
+            Block::Join(id) => write!(f, "joining the thread with id {id}"),
+            Block::Park => write!(f, "parked"),
+            Block::Sleep => write!(f, "sleeping"),
+        }
+    }
+}
+
+/// The scheduler shared by all interpreted threads.
+#[derive(Clone)]
+pub struct Scheduler(Rc<RefCell<SchedulerImpl>>);
+
+struct SchedulerImpl {
+    /// Picks the next thread, if a schedule seed is set. Otherwise, threads
+    /// only give up control when they block, yield, reach a preemption point
+    /// or finish, and they take turns in spawn order.
+    rng: Option<SplitMix64>,
+    threads: Vec<SchedThread>,
+    /// Index of the running thread.
+    current: usize,
+    /// The first error of a thread other than the main thread, which the main
+    /// thread returns once it's resumed.
+    failure: Option<Error>,
+}
+
+struct SchedThread {
+    /// The `ThreadId` of the thread, or zero for the main thread.
+    id: u64,
+    name: String,
+    state: ThreadState,
+    /// Whether the last wait of the thread timed out.
+    timed_out: bool,
+    coroutine: Coroutine,
+}
+
+enum ThreadState {
+    Runnable,
+    Blocked {
+        on: Block,
+        /// Whether the wait may time out.
+        timeout: bool,
+        backtrace: Backtrace,
+    },
+    Finished,
+}
+
+impl Scheduler {
+    /// Create a scheduler whose main thread is the running code.
+    pub fn new(seed: Option<u64>, main_name: String) -> Self {
+        debug!("Starting the thread scheduler with seed {seed:?}");
+        let main = SchedThread {
+            id: 0,
+            name: main_name,
+            state: ThreadState::Runnable,
+            timed_out: false,
+            coroutine: Coroutine::current(),
+        };
+        Scheduler(Rc::new(RefCell::new(SchedulerImpl {
+            rng: seed.map(SplitMix64),
+            threads: vec![main],
+            current: MAIN_THREAD,
+            failure: None,
+        })))
+    }
+
+    /// Add a thread that runs `entry`. Without a schedule seed, the new thread
+    /// runs right away, unless the running thread is in a native call.
+    /// Otherwise, the scheduler gets a chance to run it.
+    ///
+    /// `entry` must finish by calling [`Scheduler::exit`].
+    pub fn spawn(&self, id: u64, name: String, entry: Box<dyn FnOnce()>) -> Result<()> {
+        let coroutine = Coroutine::new(entry)?;
+        let (idx, seeded) = {
+            let mut inner = self.0.borrow_mut();
+            inner.threads.push(SchedThread {
+                id,
+                name,
+                state: ThreadState::Runnable,
+                timed_out: false,
+                coroutine,
+            });
+            (inner.threads.len() - 1, inner.rng.is_some())
+        };
+        if seeded || in_native_call() {
+            self.yield_now()
+        } else {
+            self.switch_to(idx)
+        }
+    }
+
+    /// A yield point, where the scheduler may switch to another thread if a
+    /// schedule seed is set.
+    pub fn yield_point(&self) -> Result<()> {
+        if in_native_call() {
+            return Ok(());
+        }
+        let next = {
+            let mut inner = self.0.borrow_mut();
+            let Some(rng) = &mut inner.rng else {
+                return Ok(());
+            };
+            if rng.below(PREEMPTION_ODDS) != 0 {
+                return Ok(());
+            }
+            inner.pick_next()
+        };
+        // The running thread is runnable, so there is always a next thread.
+        next.map_or(Ok(()), |next| self.switch_to(next))
+    }
+
+    /// A preemption point, i.e., an atomic operation or a spin hint, where a
+    /// thread may be waiting for another one.
+    ///
+    /// Without a schedule seed, the next runnable thread takes its turn, so a
+    /// thread that spins cannot starve the others. With a seed, the call that
+    /// leads here already was a yield point.
+    pub fn preemption_point(&self) -> Result<()> {
+        if self.0.borrow().rng.is_some() {
+            return Ok(());
+        }
+        self.yield_now()
+    }
+
+    /// Let the scheduler pick the next thread, e.g., on `yield_now`.
+    pub fn yield_now(&self) -> Result<()> {
+        if in_native_call() {
+            return Ok(());
+        }
+        let next = self.0.borrow_mut().pick_next();
+        next.map_or(Ok(()), |next| self.switch_to(next))
+    }
+
+    /// Block the running thread until it's woken up or, if `timeout` is set,
+    /// until its wait times out.
+    ///
+    /// Returns `false` if the wait timed out. Fails if no thread can run.
+    pub fn block(&self, memory: &ThreadMemory, on: Block, timeout: bool) -> Result<bool> {
+        if in_native_call() {
+            if timeout {
+                return Ok(false);
+            }
+            bail!(
+                "thread '{}' is {on} during a native call, which cannot switch to another thread",
+                memory.thread_name
+            );
+        }
+        let (current, next) = {
+            let mut inner = self.0.borrow_mut();
+            let current = inner.current;
+            inner.threads[current].state = ThreadState::Blocked {
+                on,
+                timeout,
+                backtrace: memory.backtrace(),
+            };
+            let Some(next) = inner.pick_next() else {
+                let report = inner.deadlock_report();
+                inner.threads[current].state = ThreadState::Runnable;
+                return Err(report);
+            };
+            (current, next)
+        };
+        self.switch_to(next)?;
+        let timed_out = std::mem::take(&mut self.0.borrow_mut().threads[current].timed_out);
+        Ok(!timed_out)
+    }
+
+    /// Wake up to `count` threads that wait on the futex at `address`, and
+    /// return how many were woken up.
+    pub fn wake(&self, address: usize, count: usize) -> usize {
+        let mut inner = self.0.borrow_mut();
+        let waiters = inner.threads.iter_mut().filter(|thread| {
+            matches!(thread.state, ThreadState::Blocked { on: Block::Futex { address: waiting, .. }, .. } if waiting == address)
+        });
+        let mut woken = 0;
+        for thread in waiters.take(count) {
+            thread.state = ThreadState::Runnable;
+            woken += 1;
+        }
+        woken
+    }
+
+    /// Block the running thread until the thread with the given id finishes.
+    pub fn join(&self, memory: &ThreadMemory, id: u64) -> Result<()> {
+        let finished = self
+            .0
+            .borrow()
+            .threads
+            .iter()
+            .any(|thread| thread.id == id && matches!(thread.state, ThreadState::Finished));
+        if !finished {
+            self.block(memory, Block::Join(id), false)?;
+        }
+        Ok(())
+    }
+
+    /// Return the number of threads other than the running one that did not
+    /// finish.
+    pub fn unfinished_threads(&self) -> usize {
+        let inner = self.0.borrow();
+        inner
+            .threads
+            .iter()
+            .enumerate()
+            .filter(|(idx, thread)| {
+                *idx != inner.current && !matches!(thread.state, ThreadState::Finished)
+            })
+            .count()
+    }
+
+    /// Finish the running thread, which is never resumed again.
+    ///
+    /// If the thread failed, the main thread is resumed to report the error.
+    /// The thread gives up its clone of the scheduler, since its stack is
+    /// never unwound.
+    pub fn exit(self, result: Result<()>) -> ! {
+        let switch = {
+            let mut inner = self.0.borrow_mut();
+            let current = inner.current;
+            assert_ne!(current, MAIN_THREAD, "The main thread cannot exit");
+            debug!("Thread `{}` finished", inner.threads[current].name);
+            inner.threads[current].state = ThreadState::Finished;
+            let id = inner.threads[current].id;
+            for thread in &mut inner.threads {
+                if matches!(thread.state, ThreadState::Blocked { on: Block::Join(joined), .. } if joined == id)
+                {
+                    thread.state = ThreadState::Runnable;
+                }
+            }
+            if let Err(error) = result {
+                let error = if error.is::<Abort>() {
+                    error
+                } else {
+                    ThreadFailure(error.to_string()).into()
+                };
+                inner.failure.get_or_insert(error);
+            }
+            let next = match inner.failure {
+                Some(_) => MAIN_THREAD,
+                None => inner.pick_next().unwrap_or_else(|| {
+                    inner.failure = Some(inner.deadlock_report());
+                    MAIN_THREAD
+                }),
+            };
+            inner.prepare_switch(next)
+        };
+        drop(self);
+        // SAFETY: both coroutines belong to the scheduler, which is kept alive
+        // by the thread that is resumed.
+        unsafe { switch.run() };
+        unreachable!("A finished thread was resumed");
+    }
+
+    /// Drop the threads that did not finish when the main thread returned,
+    /// which are never resumed.
+    ///
+    /// Their host stacks are unmapped, and the entry points of the threads
+    /// that never started are dropped, along with their clones of the
+    /// scheduler. The frames of the threads that started are never unwound,
+    /// so what they own is leaked.
+    pub fn shut_down(self) {
+        let threads = {
+            let mut inner = self.0.borrow_mut();
+            assert_eq!(
+                inner.current, MAIN_THREAD,
+                "Only the main thread shuts down"
+            );
+            inner.threads.split_off(MAIN_THREAD + 1)
+        };
+        debug!("Shutting down the thread scheduler");
+        drop(threads);
+    }
+
+    /// Switch from the running thread to the thread at index `next`, and
+    /// return once the running thread is resumed.
+    fn switch_to(&self, next: usize) -> Result<()> {
+        let switch = {
+            let mut inner = self.0.borrow_mut();
+            if next == inner.current {
+                return Ok(());
+            }
+            inner.prepare_switch(next)
+        };
+        // SAFETY: both coroutines belong to the scheduler, which is kept alive
+        // by the running thread.
+        unsafe { switch.run() };
+
+        let mut inner = self.0.borrow_mut();
+        // The main thread may be resumed while it's blocked, to report a
+        // failure.
+        let current = inner.current;
+        inner.threads[current].state = ThreadState::Runnable;
+        // The stacks of finished threads are no longer in use.
+        for (idx, thread) in inner.threads.iter_mut().enumerate() {
+            if idx != current && matches!(thread.state, ThreadState::Finished) {
+                thread.coroutine.release_stack();
+            }
+        }
+        if current == MAIN_THREAD
+            && let Some(error) = inner.failure.take()
+        {
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+impl SchedulerImpl {
+    /// Pick the thread that runs next among the runnable threads and the
+    /// threads whose wait may time out, which time out if they are picked.
+    ///
+    /// Without a schedule seed, the first runnable thread after the running
+    /// one is picked, and waits only time out if no thread is runnable.
+    ///
+    /// Native code, such as the end of a scoped thread, may change a futex
+    /// and wake up its waiters without going through the scheduler. Like
+    /// the futexes of the host, threads that wait on a futex that no longer
+    /// holds the expected value are woken up spuriously.
+    fn pick_next(&mut self) -> Option<usize> {
+        for thread in &mut self.threads {
+            if let ThreadState::Blocked {
+                on: Block::Futex {
+                    address, expected, ..
+                },
+                ..
+            } = thread.state
+            {
+                // SAFETY: the futex outlives the threads that wait on it, and
+                // it was checked when the thread blocked on it.
+                let futex = unsafe { AtomicU32::from_ptr(address as *mut u32) };
+                if futex.load(Ordering::SeqCst) != expected {
+                    thread.state = ThreadState::Runnable;
+                }
+            }
+        }
+        let count = self.threads.len();
+        let threads = &self.threads;
+        let runnable = |idx: &usize| matches!(threads[*idx].state, ThreadState::Runnable);
+        let may_time_out = |idx: &usize| {
+            matches!(
+                threads[*idx].state,
+                ThreadState::Blocked { timeout: true, .. }
+            )
+        };
+        let next = match &mut self.rng {
+            Some(rng) => {
+                let candidates: Vec<usize> = (0..count)
+                    .filter(|idx| runnable(idx) || may_time_out(idx))
+                    .collect();
+                if candidates.is_empty() {
+                    return None;
+                }
+                candidates[rng.below(candidates.len() as u64) as usize]
+            }
+            None => {
+                // Start after the running thread, so threads take turns.
+                let mut order = (1..=count).map(|offset| (self.current + offset) % count);
+                order
+                    .clone()
+                    .find(runnable)
+                    .or_else(|| order.find(may_time_out))?
+            }
+        };
+        let thread = &mut self.threads[next];
+        if let ThreadState::Blocked { .. } = thread.state {
+            thread.state = ThreadState::Runnable;
+            thread.timed_out = true;
+        }
+        Some(next)
+    }
+
+    /// Make `next` the running thread, and prepare the switch to it.
+    fn prepare_switch(&mut self, next: usize) -> coroutine::Switch {
+        let current = std::mem::replace(&mut self.current, next);
+        debug!(
+            "Switching from thread `{}` to thread `{}`",
+            self.threads[current].name, self.threads[next].name
+        );
+        let (from, to) = if current < next {
+            let (left, right) = self.threads.split_at_mut(next);
+            (&mut left[current], &mut right[0])
+        } else {
+            let (left, right) = self.threads.split_at_mut(current);
+            (&mut right[0], &mut left[next])
+        };
+        Coroutine::switch(&mut from.coroutine, &mut to.coroutine)
+    }
+
+    /// Describe the deadlock of the threads that did not finish.
+    fn deadlock_report(&self) -> Error {
+        let mut report = "deadlock: every thread is blocked".to_string();
+        for thread in &self.threads {
+            let ThreadState::Blocked { on, backtrace, .. } = &thread.state else {
+                continue;
+            };
+            let waiting = match on {
+                Block::Join(id) => {
+                    let name = self
+                        .threads
+                        .iter()
+                        .find(|joined| joined.id == *id)
+                        .map_or("<unknown>", |joined| &joined.name);
+                    format!("joining thread '{name}'")
+                }
+                _ => on.to_string(),
+            };
+            let _ = write!(
+                report,
+                "\nthread '{}' is {waiting}\nstack backtrace:\n{backtrace}",
+                thread.name
+            );
+        }
+        anyhow!(report.trim_end().to_string())
+    }
+}
+
+/// A yield point of the running thread, if threads are scheduled.
+pub fn yield_point(memory: &ThreadMemory) -> Result<()> {
+    match &memory.scheduler {
+        Some(scheduler) => scheduler.yield_point(),
+        None => Ok(()),
+    }
+}
+
+/// A preemption point of the running thread, if threads are scheduled.
+pub fn preemption_point(memory: &ThreadMemory) -> Result<()> {
+    match &memory.scheduler {
+        Some(scheduler) => scheduler.preemption_point(),
+        None => Ok(()),
+    }
+}
+
+/// Block the running thread until it's woken up or, if `timeout` is set,
+/// until its wait times out. Returns `false` if the wait timed out.
+///
+/// Without a scheduler, no other thread is running, so nothing can wake the
+/// thread up, and a timed wait times out right away.
+pub fn block(memory: &ThreadMemory, on: Block, timeout: bool) -> Result<bool> {
+    match &memory.scheduler {
+        Some(scheduler) => scheduler.block(memory, on, timeout),
+        None if timeout => Ok(false),
+        None => bail!(
+            "deadlock: thread '{}' is {on}, and there is no other thread",
+            memory.thread_name
+        ),
+    }
+}
+
+/// Whether the host supports the coroutines that run scheduled threads.
+pub fn is_supported() -> bool {
+    cfg!(all(target_os = "linux", target_env = "gnu"))
+}
+
+/// Wake up to `count` threads that wait on the futex at `address`, and
+/// return how many were woken up.
+pub fn wake(memory: &ThreadMemory, address: usize, count: usize) -> usize {
+    match &memory.scheduler {
+        Some(scheduler) => scheduler.wake(address, count),
+        None => 0,
+    }
+}
+
+/// The SplitMix64 pseudo-random number generator, which is small and good
+/// enough to pick threads.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Return a number in `0..bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[cfg(all(test, target_os = "linux", target_env = "gnu"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_drops_scheduler_clone() {
+        let scheduler = Scheduler::new(None, "main".to_string());
+        let clone = scheduler.clone();
+        let entry = Box::new(move || clone.exit(Ok(())));
+        scheduler.spawn(1, "child".to_string(), entry).unwrap();
+        assert_eq!(Rc::strong_count(&scheduler.0), 1);
+        assert_eq!(scheduler.unfinished_threads(), 0);
+    }
+
+    #[test]
+    fn test_shut_down_drops_unfinished_threads() {
+        let scheduler = Scheduler::new(None, "main".to_string());
+        let clone = scheduler.clone();
+        let entry = Box::new(move || {
+            clone.yield_now().unwrap();
+            clone.exit(Ok(()))
+        });
+        scheduler.spawn(1, "child".to_string(), entry).unwrap();
+        assert_eq!(scheduler.unfinished_threads(), 1);
+        let inner = Rc::clone(&scheduler.0);
+        scheduler.shut_down();
+        assert_eq!(inner.borrow().threads.len(), 1);
+    }
+}
@@ -0,0 +1,169 @@
+//! Stackful coroutines that run interpreted threads on the host thread.
+//!
+//! Each coroutine has its own host stack, and switching between coroutines
+//! saves and restores the host registers with `swapcontext`. Coroutines never
+//! run in parallel, so the compiler context, which lives in the thread-local
+//! storage of the host thread, is available to all of them.
+
+use anyhow::{Result, bail};
+use std::cell::Cell;
+use std::ptr;
+
+/// Size of the host stack of a coroutine.
+///
+/// The interpreter recurses on the host stack for every interpreted call, so
+/// coroutines get a generous stack. Pages are only committed when used.
+const STACK_SIZE: usize = 256 << 20;
+
+thread_local! {
+    /// Entry point of the coroutine that is about to start.
+    static ENTRY: Cell<Option<Box<dyn FnOnce()>>> = const { Cell::new(None) };
+}
+
+/// A suspended or running coroutine.
+pub struct Coroutine {
+    /// Host registers saved when the coroutine was suspended.
+    context: Box<libc::ucontext_t>,
+    /// Entry point of a coroutine that has not started yet.
+    entry: Option<Box<dyn FnOnce()>>,
+    /// Host stack of the coroutine, or `None` for the code that was already
+    /// running on the host stack of the thread.
+    stack: Option<HostStack>,
+}
+
+impl Coroutine {
+    /// The coroutine of the code that is already running on the host thread.
+    pub fn current() -> Self {
+        Self {
+            // SAFETY: `ucontext_t` is plain data, and it's filled in by
+            // `swapcontext` before it's resumed.
+            context: Box::new(unsafe { std::mem::zeroed() }),
+            entry: None,
+            stack: None,
+        }
+    }
+
+    /// Create a coroutine that runs `entry` on a new host stack when it's
+    /// resumed for the first time.
+    ///
+    /// `entry` must not return. It must switch to another coroutine instead.
+    pub fn new(entry: Box<dyn FnOnce()>) -> Result<Self> {
+        let stack = HostStack::new(STACK_SIZE)?;
+        // SAFETY: `ucontext_t` is plain data, which `getcontext` initializes.
+        let mut context: Box<libc::ucontext_t> = Box::new(unsafe { std::mem::zeroed() });
+        // SAFETY: the context is initialized by `getcontext` and its stack is
+        // valid for the lifetime of the coroutine.
+        unsafe {
+            if libc::getcontext(context.as_mut()) != 0 {
+                bail!("Failed to initialize a coroutine context");
+            }
+            context.uc_stack.ss_sp = stack.base.cast();
+            context.uc_stack.ss_size = stack.size;
+            context.uc_link = ptr::null_mut();
+            libc::makecontext(context.as_mut(), start, 0);
+        }
+        Ok(Self {
+            context,
+            entry: Some(entry),
+            stack: Some(stack),
+        })
+    }
+
+    /// Free the host stack of a coroutine that will never be resumed again.
+    pub fn release_stack(&mut self) {
+        self.stack = None;
+    }
+
+    /// Prepare the switch from the running coroutine `from` to `to`.
+    ///
+    /// The switch only happens when [`Switch::run`] is called, so it can be
+    /// prepared while the coroutines are borrowed.
+    pub fn switch(from: &mut Coroutine, to: &mut Coroutine) -> Switch {
+        Switch {
+            from: from.context.as_mut(),
+            to: to.context.as_ref(),
+            entry: to.entry.take(),
+        }
+    }
+}
+
+/// A prepared switch between two coroutines.
+#[must_use]
+pub struct Switch {
+    from: *mut libc::ucontext_t,
+    to: *const libc::ucontext_t,
+    entry: Option<Box<dyn FnOnce()>>,
+}
+
+impl Switch {
+    /// Suspend the running coroutine and resume the other one. Returns once
+    /// the running coroutine is resumed again.
+    ///
+    /// # Safety
+    ///
+    /// Both coroutines must still be alive, and the running coroutine must be
+    /// the one the switch was prepared from.
+    pub unsafe fn run(self) {
+        if self.entry.is_some() {
+            ENTRY.set(self.entry);
+        }
+        // SAFETY: the caller guarantees that both contexts are alive.
+        let result = unsafe { libc::swapcontext(self.from, self.to) };
+        assert_eq!(result, 0, "Failed to switch coroutines");
+    }
+}
+
+/// The host function that starts every coroutine.
+extern "C" fn start() {
+    let entry = ENTRY
+        .take()
+        .expect("Coroutine started without an entry point");
+    entry();
+    // Coroutines switch away at the end of their entry point, and they are
+    // never resumed.
+    std::process::abort();
+}
+
+/// A host stack allocated with `mmap`, with a guard page at its end.
+struct HostStack {
+    base: *mut u8,
+    size: usize,
+}
+
+impl HostStack {
+    fn new(size: usize) -> Result<Self> {
+        // SAFETY: an anonymous private mapping doesn't alias any memory.
+        let base = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE | libc::MAP_STACK,
+                -1,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            bail!("Failed to allocate a coroutine stack of {size} bytes");
+        }
+        // The stack grows down, so a stack overflow hits the first page.
+        // SAFETY: the page belongs to the new mapping.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        if unsafe { libc::mprotect(base, page_size, libc::PROT_NONE) } != 0 {
+            // SAFETY: the mapping is not used by anything yet.
+            unsafe { libc::munmap(base, size) };
+            bail!("Failed to protect the guard page of a coroutine stack");
+        }
+        Ok(Self {
+            base: base.cast(),
+            size,
+        })
+    }
+}
+
+impl Drop for HostStack {
+    fn drop(&mut self) {
+        // SAFETY: the stack is not in use, since its coroutine finished.
+        unsafe { libc::munmap(self.base.cast(), self.size) };
+    }
+}
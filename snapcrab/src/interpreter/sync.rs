@@ -0,0 +1,492 @@
+//! Blocking operations of the synchronization primitives of `std`.
+//!
+//! `std` implements locks, condition variables and thread parking on Linux
+//! with futexes. The uncontended paths are inlined, so they are interpreted,
+//! but the paths that may block are only available as native code, which
+//! would block the host thread of the interpreter. Instead, these paths are
+//! implemented here, following the implementation of `std`, and they block
+//! the interpreted thread through the scheduler.
+//!
+//! Futex words are interpreter memory, so they are accessed with host atomics,
+//! like atomic intrinsics.
+
+use super::scheduler::{self, Block};
+use super::thread::parker_address;
+use crate::interpreter::rvalue::read_discriminant;
+use crate::memory::ThreadMemory;
+use crate::ty::{struct_field, wrapped_pointee};
+use crate::value::Value;
+use anyhow::Result;
+use rustc_public::CrateDef;
+use rustc_public::mir::mono::Instance;
+use rustc_public::ty::Ty;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tracing::debug;
+
+/// The blocking operations of `std` intercepted by the interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncFn {
+    /// `futex_wait(&Atomic<u32>, u32, Option<Duration>) -> bool`
+    FutexWait,
+    /// `futex_wake(&Atomic<u32>) -> bool`
+    FutexWake,
+    /// `futex_wake_all(&Atomic<u32>)`
+    FutexWakeAll,
+    /// `Mutex::lock_contended(&self)`
+    MutexLockContended,
+    /// `Mutex::wake(&self)`
+    MutexWake,
+    /// `Condvar::wait(&self, &Mutex)`
+    CondvarWait,
+    /// `Condvar::wait_timeout(&self, &Mutex, Duration) -> bool`
+    CondvarWaitTimeout,
+    /// `std::sync::Condvar::notify_one(&self)`
+    CondvarNotifyOne,
+    /// `std::sync::Condvar::notify_all(&self)`
+    CondvarNotifyAll,
+    /// `RwLock::read_contended(&self)`
+    RwLockReadContended,
+    /// `RwLock::write_contended(&self)`
+    RwLockWriteContended,
+    /// `RwLock::wake_writer_or_readers(&self, u32)`
+    RwLockWake,
+    /// `std::thread::park()`, or `Thread::park(&self)` on the handle of the
+    /// running thread
+    Park,
+    /// `std::thread::park_timeout(Duration)`, or `Thread::park_timeout(&self,
+    /// Duration)` on the handle of the running thread
+    ParkTimeout,
+    /// `std::thread::yield_now()`
+    YieldNow,
+    /// `std::hint::spin_loop()`, whose native implementation is an LLVM
+    /// intrinsic
+    SpinLoop,
+    /// `std::thread::sleep(Duration)`
+    Sleep,
+}
+
+impl SyncFn {
+    /// Return the blocking operation implemented by `instance`, if any.
+    pub fn from_instance(instance: Instance) -> Option<Self> {
+        match instance.def.name().as_str() {
+            "std::sys::pal::unix::futex::futex_wait" => Some(SyncFn::FutexWait),
+            "std::sys::pal::unix::futex::futex_wake" => Some(SyncFn::FutexWake),
+            "std::sys::pal::unix::futex::futex_wake_all" => Some(SyncFn::FutexWakeAll),
+            "std::sys::sync::mutex::futex::Mutex::lock_contended" => {
+                Some(SyncFn::MutexLockContended)
+            }
+            "std::sys::sync::mutex::futex::Mutex::wake" => Some(SyncFn::MutexWake),
+            "std::sys::sync::condvar::futex::Condvar::wait" => Some(SyncFn::CondvarWait),
+            "std::sys::sync::condvar::futex::Condvar::wait_timeout" => {
+                Some(SyncFn::CondvarWaitTimeout)
+            }
+            "std::sync::Condvar::notify_one" => Some(SyncFn::CondvarNotifyOne),
+            "std::sync::Condvar::notify_all" => Some(SyncFn::CondvarNotifyAll),
+            "std::sys::sync::rwlock::futex::RwLock::read_contended" => {
+                Some(SyncFn::RwLockReadContended)
+            }
+            "std::sys::sync::rwlock::futex::RwLock::write_contended" => {
+                Some(SyncFn::RwLockWriteContended)
+            }
+            "std::sys::sync::rwlock::futex::RwLock::wake_writer_or_readers" => {
+                Some(SyncFn::RwLockWake)
+            }
+            "std::thread::park" | "std::thread::Thread::park" => Some(SyncFn::Park),
+            "std::thread::park_timeout" | "std::thread::Thread::park_timeout" => {
+                Some(SyncFn::ParkTimeout)
+            }
+            "std::thread::yield_now" => Some(SyncFn::YieldNow),
+            "std::hint::spin_loop" | "core::hint::spin_loop" => Some(SyncFn::SpinLoop),
+            "std::thread::sleep" => Some(SyncFn::Sleep),
+            _ => None,
+        }
+    }
+
+    /// Execute the operation against the interpreter.
+    pub fn eval(self, args: &[Value], instance: Instance, memory: &ThreadMemory) -> Result<Value> {
+        debug!("Blocking operation: {self:?}");
+        let arg_tys: Vec<Ty> = instance.fn_abi()?.args.iter().map(|arg| arg.ty).collect();
+        // The futex of the primitive that the first argument points to.
+        let futex = |path: &[usize]| Futex::field(memory, &args[0], arg_tys[0], path);
+        let unit = Value::unit().clone();
+        match self {
+            SyncFn::FutexWait => {
+                let futex = Futex::field(memory, &args[0], arg_tys[0], &[])?;
+                let expected = args[1].read_uint() as u32;
+                let timeout = read_discriminant(&args[2], arg_tys[2])?.read_uint() != 0;
+                let woken = futex_wait(memory, futex, expected, "futex", timeout)?;
+                Ok(Value::from_bool(woken))
+            }
+            SyncFn::FutexWake => {
+                let futex = Futex::field(memory, &args[0], arg_tys[0], &[])?;
+                Ok(Value::from_bool(futex_wake(memory, futex, 1) > 0))
+            }
+            SyncFn::FutexWakeAll => {
+                let futex = Futex::field(memory, &args[0], arg_tys[0], &[])?;
+                futex_wake(memory, futex, usize::MAX);
+                Ok(unit)
+            }
+            SyncFn::MutexLockContended => {
+                mutex_lock_contended(memory, futex(&[0])?)?;
+                Ok(unit)
+            }
+            SyncFn::MutexWake => {
+                futex_wake(memory, futex(&[0])?, 1);
+                Ok(unit)
+            }
+            SyncFn::CondvarWait | SyncFn::CondvarWaitTimeout => {
+                let mutex = Futex::field(memory, &args[1], arg_tys[1], &[0])?;
+                let timeout = self == SyncFn::CondvarWaitTimeout;
+                let woken = condvar_wait(memory, futex(&[0])?, mutex, timeout)?;
+                Ok(if timeout {
+                    Value::from_bool(woken)
+                } else {
+                    unit
+                })
+            }
+            SyncFn::CondvarNotifyOne | SyncFn::CondvarNotifyAll => {
+                // `std::sync::Condvar` wraps the `Condvar` of the platform.
+                let condvar = futex(&[0, 0])?;
+                condvar.atomic().fetch_add(1, Ordering::SeqCst);
+                let count = if self == SyncFn::CondvarNotifyOne {
+                    1
+                } else {
+                    usize::MAX
+                };
+                futex_wake(memory, condvar, count);
+                Ok(unit)
+            }
+            SyncFn::RwLockReadContended => {
+                rwlock_read_contended(memory, futex(&[0])?)?;
+                Ok(unit)
+            }
+            SyncFn::RwLockWriteContended => {
+                rwlock_write_contended(memory, futex(&[0])?, futex(&[1])?)?;
+                Ok(unit)
+            }
+            SyncFn::RwLockWake => {
+                let state = args[1].read_uint() as u32;
+                rwlock_wake_writer_or_readers(memory, futex(&[0])?, futex(&[1])?, state);
+                Ok(unit)
+            }
+            SyncFn::Park | SyncFn::ParkTimeout => {
+                park(memory, self == SyncFn::ParkTimeout)?;
+                Ok(unit)
+            }
+            SyncFn::YieldNow => {
+                if let Some(scheduler) = &memory.scheduler {
+                    scheduler.yield_now()?;
+                }
+                Ok(unit)
+            }
+            SyncFn::SpinLoop => {
+                scheduler::preemption_point(memory)?;
+                Ok(unit)
+            }
+            SyncFn::Sleep => {
+                if memory.scheduler.is_some() {
+                    scheduler::block(memory, Block::Sleep, true)?;
+                } else {
+                    std::thread::sleep(read_duration(&args[0], arg_tys[0])?);
+                }
+                Ok(unit)
+            }
+        }
+    }
+}
+
+/// A futex word in interpreter memory.
+#[derive(Clone, Copy, Debug)]
+struct Futex(usize);
+
+impl Futex {
+    /// The futex at the field `path` of the struct that `ptr` points to, such
+    /// as the futex of a mutex.
+    fn field(memory: &ThreadMemory, ptr: &Value, ptr_ty: Ty, path: &[usize]) -> Result<Self> {
+        let mut address = ptr.read_uint() as usize;
+        let mut ty = wrapped_pointee(ptr_ty)?;
+        for idx in path {
+            let (offset, field_ty) = struct_field(ty, *idx)?;
+            address += offset;
+            ty = field_ty;
+        }
+        memory.check_atomic_access(address, size_of::<u32>(), true)?;
+        Ok(Futex(address))
+    }
+
+    fn atomic(self) -> &'static AtomicU32 {
+        // SAFETY: the access was checked when the futex was created, and the
+        // primitive that holds the futex outlives the blocking operation.
+        unsafe { AtomicU32::from_ptr(self.0 as *mut u32) }
+    }
+
+    fn load(self) -> u32 {
+        self.atomic().load(Ordering::SeqCst)
+    }
+
+    fn swap(self, value: u32) -> u32 {
+        self.atomic().swap(value, Ordering::SeqCst)
+    }
+
+    fn compare_exchange(self, current: u32, new: u32) -> Result<u32, u32> {
+        self.atomic()
+            .compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst)
+    }
+}
+
+/// Block until the futex is woken up, unless it doesn't hold `expected`.
+///
+/// Returns `false` if the wait timed out.
+fn futex_wait(
+    memory: &ThreadMemory,
+    futex: Futex,
+    expected: u32,
+    kind: &'static str,
+    timeout: bool,
+) -> Result<bool> {
+    if futex.load() != expected {
+        return Ok(true);
+    }
+    let block = Block::Futex {
+        address: futex.0,
+        expected,
+        kind,
+    };
+    scheduler::block(memory, block, timeout)
+}
+
+/// Wake up to `count` threads that wait on the futex.
+fn futex_wake(memory: &ThreadMemory, futex: Futex, count: usize) -> usize {
+    scheduler::wake(memory, futex.0, count)
+}
+
+// The states of a futex mutex.
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+const CONTENDED: u32 = 2;
+
+fn mutex_lock(memory: &ThreadMemory, mutex: Futex) -> Result<()> {
+    if mutex.compare_exchange(UNLOCKED, LOCKED).is_err() {
+        mutex_lock_contended(memory, mutex)?;
+    }
+    Ok(())
+}
+
+/// Lock a mutex that is locked by another thread.
+///
+/// Other threads may be waiting, so the mutex is marked as contended, which
+/// makes the thread that unlocks it wake up a waiter.
+fn mutex_lock_contended(memory: &ThreadMemory, mutex: Futex) -> Result<()> {
+    while mutex.swap(CONTENDED) != UNLOCKED {
+        futex_wait(memory, mutex, CONTENDED, "mutex", false)?;
+    }
+    Ok(())
+}
+
+fn mutex_unlock(memory: &ThreadMemory, mutex: Futex) {
+    if mutex.swap(UNLOCKED) == CONTENDED {
+        futex_wake(memory, mutex, 1);
+    }
+}
+
+/// Wait for a notification of a condition variable, whose futex counts the
+/// notifications, with its mutex unlocked. Returns `false` on timeout.
+fn condvar_wait(
+    memory: &ThreadMemory,
+    condvar: Futex,
+    mutex: Futex,
+    timeout: bool,
+) -> Result<bool> {
+    let notifications = condvar.load();
+    mutex_unlock(memory, mutex);
+    let woken = futex_wait(
+        memory,
+        condvar,
+        notifications,
+        "condition variable",
+        timeout,
+    )?;
+    mutex_lock(memory, mutex)?;
+    Ok(woken)
+}
+
+// The state of a futex read-write lock: the number of readers, or
+// `WRITE_LOCKED`, and whether readers or writers are waiting.
+const READ_LOCKED: u32 = 1;
+const MASK: u32 = (1 << 30) - 1;
+const WRITE_LOCKED: u32 = MASK;
+const MAX_READERS: u32 = MASK - 1;
+const READERS_WAITING: u32 = 1 << 30;
+const WRITERS_WAITING: u32 = 1 << 31;
+
+fn is_unlocked(state: u32) -> bool {
+    state & MASK == 0
+}
+
+fn is_write_locked(state: u32) -> bool {
+    state & MASK == WRITE_LOCKED
+}
+
+fn has_readers_waiting(state: u32) -> bool {
+    state & READERS_WAITING != 0
+}
+
+fn has_writers_waiting(state: u32) -> bool {
+    state & WRITERS_WAITING != 0
+}
+
+fn is_read_lockable(state: u32) -> bool {
+    state & MASK < MAX_READERS && !has_readers_waiting(state) && !has_writers_waiting(state)
+}
+
+fn is_read_lockable_after_wakeup(state: u32) -> bool {
+    state & MASK < MAX_READERS
+        && !has_readers_waiting(state)
+        && !is_write_locked(state)
+        && !is_unlocked(state)
+}
+
+/// Read-lock a read-write lock that is write-locked or has waiting threads.
+fn rwlock_read_contended(memory: &ThreadMemory, state: Futex) -> Result<()> {
+    let mut has_slept = false;
+    let mut current = state.load();
+    loop {
+        if (has_slept && is_read_lockable_after_wakeup(current)) || is_read_lockable(current) {
+            match state.compare_exchange(current, current + READ_LOCKED) {
+                Ok(_) => return Ok(()),
+                Err(actual) => {
+                    current = actual;
+                    continue;
+                }
+            }
+        }
+        anyhow::ensure!(
+            current & MASK != MAX_READERS,
+            "too many active read locks on RwLock"
+        );
+        if !has_readers_waiting(current)
+            && let Err(actual) = state.compare_exchange(current, current | READERS_WAITING)
+        {
+            current = actual;
+            continue;
+        }
+        futex_wait(
+            memory,
+            state,
+            current | READERS_WAITING,
+            "read-write lock",
+            false,
+        )?;
+        has_slept = true;
+        current = state.load();
+    }
+}
+
+/// Write-lock a read-write lock that is locked.
+fn rwlock_write_contended(memory: &ThreadMemory, state: Futex, writer_notify: Futex) -> Result<()> {
+    let mut current = state.load();
+    let mut other_writers_waiting = 0;
+    loop {
+        if is_unlocked(current) {
+            match state.compare_exchange(current, current | WRITE_LOCKED | other_writers_waiting) {
+                Ok(_) => return Ok(()),
+                Err(actual) => {
+                    current = actual;
+                    continue;
+                }
+            }
+        }
+        if !has_writers_waiting(current)
+            && let Err(actual) = state.compare_exchange(current, current | WRITERS_WAITING)
+        {
+            current = actual;
+            continue;
+        }
+        // Other writers may be waiting too, so keep the flag when locking.
+        other_writers_waiting = WRITERS_WAITING;
+        let seq = writer_notify.load();
+        current = state.load();
+        if is_unlocked(current) || !has_writers_waiting(current) {
+            continue;
+        }
+        futex_wait(memory, writer_notify, seq, "read-write lock", false)?;
+        current = state.load();
+    }
+}
+
+/// Wake up a writer or the readers of a read-write lock that was unlocked.
+fn rwlock_wake_writer_or_readers(
+    memory: &ThreadMemory,
+    state: Futex,
+    writer_notify: Futex,
+    mut current: u32,
+) {
+    let wake_writer = || {
+        writer_notify.atomic().fetch_add(1, Ordering::SeqCst);
+        futex_wake(memory, writer_notify, 1) > 0
+    };
+    if current == WRITERS_WAITING {
+        match state.compare_exchange(current, 0) {
+            Ok(_) => {
+                wake_writer();
+                return;
+            }
+            Err(actual) => current = actual,
+        }
+    }
+    if current == READERS_WAITING + WRITERS_WAITING {
+        if state.compare_exchange(current, READERS_WAITING).is_err() {
+            return;
+        }
+        if wake_writer() {
+            return;
+        }
+        current = READERS_WAITING;
+    }
+    if current == READERS_WAITING && state.compare_exchange(current, 0).is_ok() {
+        futex_wake(memory, state, usize::MAX);
+    }
+}
+
+// The states of a futex parker.
+const PARKED: u32 = u32::MAX;
+const EMPTY: u32 = 0;
+const NOTIFIED: u32 = 1;
+
+/// Park the running thread until it's unparked or, if `timeout` is set, until
+/// the wait times out.
+fn park(memory: &ThreadMemory, timeout: bool) -> Result<()> {
+    let Some(handle) = &memory.thread_handle else {
+        // Other threads can only unpark a thread through its handle.
+        scheduler::block(memory, Block::Park, timeout)?;
+        return Ok(());
+    };
+    let parker = Futex(parker_address(handle)?);
+    memory.check_atomic_access(parker.0, size_of::<u32>(), true)?;
+    // Consume the token, or mark the thread as parked.
+    if parker.atomic().fetch_sub(1, Ordering::SeqCst) == NOTIFIED {
+        return Ok(());
+    }
+    if timeout {
+        futex_wait(memory, parker, PARKED, "thread parker", true)?;
+        parker.swap(EMPTY);
+        return Ok(());
+    }
+    loop {
+        futex_wait(memory, parker, PARKED, "thread parker", false)?;
+        if parker.compare_exchange(NOTIFIED, EMPTY).is_ok() {
+            return Ok(());
+        }
+    }
+}
+
+/// Read a `Duration` argument.
+fn read_duration(value: &Value, ty: Ty) -> Result<Duration> {
+    let (secs_offset, _) = struct_field(ty, 0)?;
+    let (nanos_offset, _) = struct_field(ty, 1)?;
+    let bytes = value.as_bytes();
+    let secs = u64::from_ne_bytes(bytes[secs_offset..secs_offset + 8].try_into()?);
+    let nanos = u32::from_ne_bytes(bytes[nanos_offset..nanos_offset + 4].try_into()?);
+    Ok(Duration::new(secs, nanos))
+}
@@ -0,0 +1,451 @@
+//! Interpreted threads.
+//!
+//! `std` creates threads and their handles through a few platform functions
+//! that are only available as native code. Calling them natively would start
+//! a host thread that runs interpreted closures as native code, and would
+//! allocate thread handles out of the interpreter's sight. Instead, these
+//! calls are intercepted here, and the rest of `std::thread`, including
+//! `scope`, the join packets and the panic handling, is interpreted as usual.
+//!
+//! Each interpreted thread has its own [`ThreadMemory`], with its own stack
+//! and thread-local statics, which shares the heap, the statics and the JIT
+//! engine with the thread that spawned it. The compiler context that is used
+//! to query MIR is only available on the host thread running the compiler,
+//! so every interpreted thread runs on that host thread, and threads take
+//! turns through the [scheduler](super::scheduler). By default, a thread
+//! gives up control when it blocks, and at atomic operations and spin hints,
+//! so threads that wait for each other make progress as they would on host
+//! threads. Interleaving threads at every function call is opt-in, through a
+//! schedule seed. On hosts without coroutines, a spawned thread instead runs
+//! to completion as soon as it is spawned, and joining it only collects the
+//! result that it left behind.
+
+use super::function::{invoke_fn, virtual_callee};
+use super::panic::Panic;
+use super::rvalue::{build_enum_variant, read_discriminant};
+use super::scheduler::{self, Scheduler};
+use super::thread_local::run_tls_dtors;
+use crate::memory::{ThreadMemory, pointer_width};
+use crate::ty::{MonoType, named_field, wrapped_pointee};
+use crate::value::{Value, uint_from_bytes};
+use anyhow::{Context, Result, bail};
+use rustc_public::CrateDef;
+use rustc_public::mir::mono::Instance;
+use rustc_public::ty::{RigidTy, Ty, TyKind, VariantIdx};
+use rustc_public_bridge::IndexedVal;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::debug;
+
+/// Thread ids handed out to interpreted threads.
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Index of `call_once` in the vtable of `dyn FnOnce()`, after the drop
+/// glue, the size and the alignment.
+const CALL_ONCE_VTABLE_IDX: usize = 3;
+
+/// The `std::thread::Thread` handle of an interpreted thread.
+#[derive(Clone, Debug)]
+pub struct ThreadHandle {
+    /// Pointer to the reference counted handle data.
+    value: Value,
+    /// The `Thread` type.
+    ty: Ty,
+    /// Whether the thread memory owns a reference count, which it drops when
+    /// the thread exits.
+    owned: bool,
+}
+
+/// The thread runtime functions intercepted by the interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThreadFn {
+    /// `std::thread::ThreadId::new() -> ThreadId`
+    NewId,
+    /// `std::thread::Thread::new(ThreadId, Option<String>) -> Thread`
+    NewHandle,
+    /// `std::thread::current() -> Thread` and `current_or_unnamed`
+    Current,
+    /// `std::sys::thread::unix::Thread::new(usize, Box<ThreadInit>) -> io::Result<Thread>`
+    Spawn,
+    /// `std::sys::thread::unix::Thread::join(self)`
+    Join,
+    /// `<std::sys::thread::unix::Thread as Drop>::drop(&mut self)`, which
+    /// detaches the thread
+    Detach,
+}
+
+/// The paths of the intercepted functions. Most of them are private to `std`,
+/// so they may change with the toolchain, which [`ThreadFn::missing_paths`]
+/// detects.
+const THREAD_FN_PATHS: &[(&str, ThreadFn)] = &[
+    ("std::thread::ThreadId::new", ThreadFn::NewId),
+    ("std::thread::Thread::new", ThreadFn::NewHandle),
+    ("std::thread::current", ThreadFn::Current),
+    (
+        "std::thread::current::current_or_unnamed",
+        ThreadFn::Current,
+    ),
+    ("std::sys::thread::unix::Thread::new", ThreadFn::Spawn),
+    ("std::sys::thread::unix::Thread::join", ThreadFn::Join),
+    (
+        "<std::sys::thread::unix::Thread as std::ops::Drop>::drop",
+        ThreadFn::Detach,
+    ),
+];
+
+impl ThreadFn {
+    /// Return the thread runtime function implemented by `instance`, if any.
+    pub fn from_instance(instance: Instance) -> Option<Self> {
+        let name = instance.def.name();
+        THREAD_FN_PATHS
+            .iter()
+            .find(|(path, _)| *path == name)
+            .map(|(_, thread_fn)| *thread_fn)
+    }
+
+    /// Return the paths of the intercepted functions that `std` does not
+    /// define. Calls to them would run natively instead, and start host
+    /// threads or allocate handles out of the interpreter's sight.
+    pub fn missing_paths() -> Vec<&'static str> {
+        let defined: HashSet<String> = rustc_public::find_crates("std")
+            .iter()
+            .flat_map(|krate| krate.fn_defs())
+            .map(|def| def.name())
+            .collect();
+        THREAD_FN_PATHS
+            .iter()
+            .map(|(path, _)| *path)
+            .filter(|path| !defined.contains(*path))
+            .collect()
+    }
+
+    /// Execute the call against the interpreter.
+    pub fn eval(
+        self,
+        args: &[Value],
+        instance: Instance,
+        memory: &mut ThreadMemory,
+        error_depth: &mut Option<u16>,
+    ) -> Result<Value> {
+        debug!("Thread runtime call: {self:?}");
+        match self {
+            ThreadFn::NewId => Ok(Value::from_type(new_thread_id())),
+            ThreadFn::NewHandle => {
+                let fn_abi = instance.fn_abi()?;
+                let name = read_thread_name_arg(memory, &args[1], fn_abi.args[1].ty)?;
+                let id = args[0].read_uint() as u64;
+                new_handle(memory, fn_abi.ret.ty, id, name.as_deref())
+            }
+            ThreadFn::Current => {
+                let thread_ty = instance.fn_abi()?.ret.ty;
+                let handle = current_handle(memory, thread_ty)?;
+                clone_handle(memory, &handle)?;
+                Ok(handle.value)
+            }
+            ThreadFn::Spawn => {
+                let fn_abi = instance.fn_abi()?;
+                let init = args[1].read_uint() as usize;
+                let init_ty = wrapped_pointee(fn_abi.args[1].ty)?;
+                let id = spawn(memory, init, init_ty, error_depth)?;
+                io_result_ok(fn_abi.ret.ty, Value::from_type(id))
+            }
+            ThreadFn::Join => {
+                // Without a scheduler, the thread already ran to completion
+                // when it was spawned.
+                if let Some(scheduler) = &memory.scheduler {
+                    scheduler.join(memory, args[0].read_uint() as u64)?;
+                }
+                Ok(Value::unit().clone())
+            }
+            // A detached thread keeps running until the program ends.
+            ThreadFn::Detach => Ok(Value::unit().clone()),
+        }
+    }
+}
+
+/// Return a new thread id. Ids are never zero.
+fn new_thread_id() -> u64 {
+    NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Start the thread described by the `ThreadInit` at `init`, and return its
+/// id.
+///
+/// `ThreadInit` holds the handle of the new thread and its entry point, a
+/// `Box<dyn FnOnce() + Send>`. The thread is added to the scheduler, which is
+/// created by the first spawn. On hosts without coroutines, the thread runs to
+/// completion right away instead, unless a schedule seed asks for a scheduler.
+fn spawn(
+    memory: &mut ThreadMemory,
+    init: usize,
+    init_ty: Ty,
+    error_depth: &mut Option<u16>,
+) -> Result<u64> {
+    let (handle_offset, thread_ty) = named_field(init_ty, "handle")?;
+    let handle = ThreadHandle {
+        value: memory.read_bytes(init + handle_offset, pointer_width())?,
+        ty: thread_ty,
+        owned: false,
+    };
+    let id = handle_id(memory, &handle)?;
+    let name = handle_name(memory, &handle)?.unwrap_or_else(|| "<unnamed>".to_string());
+    debug!("Spawning thread `{name}` with id {id}");
+
+    let seed = memory.schedule_seed;
+    if memory.scheduler.is_none() && (scheduler::is_supported() || seed.is_some()) {
+        memory.scheduler = Some(Scheduler::new(seed, memory.thread_name.clone()));
+    }
+    let mut thread = memory.new_thread(name.clone());
+    thread.thread_handle = Some(handle);
+    match memory.scheduler.clone() {
+        Some(scheduler) => {
+            let entry = {
+                let scheduler = scheduler.clone();
+                move || {
+                    let result = run_thread(&mut thread, init, init_ty, &mut None);
+                    drop(thread);
+                    scheduler.exit(result)
+                }
+            };
+            scheduler.spawn(id, name, Box::new(entry))?;
+        }
+        None => run_thread(&mut thread, init, init_ty, error_depth)?,
+    }
+    Ok(id)
+}
+
+/// Run a spawned thread to completion, and free its `ThreadInit`.
+fn run_thread(
+    thread: &mut ThreadMemory,
+    init: usize,
+    init_ty: Ty,
+    error_depth: &mut Option<u16>,
+) -> Result<()> {
+    let (handle_offset, thread_ty) = named_field(init_ty, "handle")?;
+    let (start_offset, _) = named_field(init_ty, "rust_start")?;
+    let rust_start = thread.read_bytes(init + start_offset, 2 * pointer_width())?;
+    run_entry_point(thread, rust_start)?;
+    exit_thread(thread)?;
+
+    // The thread was the last user of its `ThreadInit`.
+    let drop_fn = Instance::resolve_drop_in_place(thread_ty);
+    let handle_ptr = Value::from_type(init + handle_offset);
+    invoke_fn(drop_fn, thread, vec![handle_ptr], error_depth)?;
+    thread.deallocate(init, init_ty.size()?, init_ty.alignment()?)
+}
+
+/// Call the boxed `dyn FnOnce()` entry point of a thread, and free the box.
+fn run_entry_point(thread: &mut ThreadMemory, entry: Value) -> Result<()> {
+    let ptr_width = pointer_width();
+    let bytes = entry.as_bytes();
+    let data = uint_from_bytes(&bytes[..ptr_width]) as usize;
+    let vtable = uint_from_bytes(&bytes[ptr_width..]) as usize;
+    let mut args = vec![entry.clone()];
+    let call_once = virtual_callee(thread, CALL_ONCE_VTABLE_IDX, &mut args)?
+        .context("Missing `call_once` in the vtable of a thread entry point")?;
+    invoke_fn(call_once, thread, args, &mut None)?;
+
+    let size = thread
+        .read_bytes(vtable + ptr_width, ptr_width)?
+        .read_uint() as usize;
+    let align = thread
+        .read_bytes(vtable + 2 * ptr_width, ptr_width)?
+        .read_uint() as usize;
+    if size > 0 {
+        thread.deallocate(data, size, align)?;
+    }
+    Ok(())
+}
+
+/// Return the handle of the current thread, creating it if needed.
+///
+/// Threads that were not spawned by the interpreted program, i.e., the main
+/// thread, get their handle on first use.
+fn current_handle(memory: &mut ThreadMemory, thread_ty: Ty) -> Result<ThreadHandle> {
+    if let Some(handle) = &memory.thread_handle {
+        return Ok(handle.clone());
+    }
+    let name = memory.thread_name.clone();
+    let value = new_handle(memory, thread_ty, new_thread_id(), Some(&name))?;
+    let handle = ThreadHandle {
+        value,
+        ty: thread_ty,
+        owned: true,
+    };
+    memory.thread_handle = Some(handle.clone());
+    Ok(handle)
+}
+
+/// Clean up after a thread whose main function returned.
+///
+/// The thread-local destructors run first, since they may still use the
+/// thread handle and other thread-locals.
+pub fn exit_thread(memory: &mut ThreadMemory) -> Result<()> {
+    run_tls_dtors(memory)?;
+    drop_thread_handle(memory)?;
+    memory.free_thread_locals()
+}
+
+/// Clean up after the main function of a thread returned `result`.
+///
+/// Thread-locals are also destroyed when a panic unwinds out of the thread,
+/// but not when it aborts or the interpreter fails. An error raised by a
+/// destructor comes later, so it takes precedence over the panic.
+pub fn finish_thread<T>(memory: &mut ThreadMemory, result: Result<T>) -> Result<T> {
+    match result {
+        Ok(value) => exit_thread(memory).map(|()| value),
+        Err(error) if error.is::<Panic>() => exit_thread(memory).and(Err(error)),
+        Err(error) => Err(error),
+    }
+}
+
+/// Release the handle owned by a thread that exited.
+fn drop_thread_handle(memory: &mut ThreadMemory) -> Result<()> {
+    let Some(handle) = memory.thread_handle.take_if(|handle| handle.owned) else {
+        return Ok(());
+    };
+    // The drop glue expects a pointer to the handle.
+    let ptr_width = pointer_width();
+    let slot = memory.allocate(ptr_width, ptr_width)?;
+    memory.write_bytes(slot, handle.value.as_bytes())?;
+    let drop_fn = Instance::resolve_drop_in_place(handle.ty);
+    invoke_fn(drop_fn, memory, vec![Value::from_type(slot)], &mut None)?;
+    memory.deallocate(slot, ptr_width, ptr_width)
+}
+
+/// Allocate the data of a new `Thread` handle, i.e., an `ArcInner<Inner>`
+/// with a single reference, and return the handle.
+///
+/// `Inner` holds the name of the thread as a nul-terminated boxed slice, its
+/// id and its parker, which starts out empty, i.e., zeroed.
+fn new_handle(
+    memory: &mut ThreadMemory,
+    thread_ty: Ty,
+    id: u64,
+    name: Option<&str>,
+) -> Result<Value> {
+    let arc_ty = wrapped_pointee(thread_ty)?;
+    let address = memory.allocate(arc_ty.size()?, arc_ty.alignment()?)?;
+    let one = 1usize.to_ne_bytes();
+    let (strong, _) = named_field(arc_ty, "strong")?;
+    let (weak, _) = named_field(arc_ty, "weak")?;
+    memory.write_bytes(address + strong, &one)?;
+    memory.write_bytes(address + weak, &one)?;
+
+    let (data, inner_ty) = named_field(arc_ty, "data")?;
+    let name_offset = name_slice_offset(inner_ty)?;
+    let (id_offset, _) = named_field(inner_ty, "id")?;
+    memory.write_bytes(address + data + id_offset, &id.to_ne_bytes())?;
+    if let Some(name) = name {
+        // `None` is represented by a null pointer, which the zeroed
+        // allocation already holds.
+        let len = name.len() + 1;
+        let buffer = memory.allocate(len, 1)?;
+        memory.write_bytes(buffer, name.as_bytes())?;
+        let mut slice = buffer.to_ne_bytes().to_vec();
+        slice.extend_from_slice(&len.to_ne_bytes());
+        memory.write_bytes(address + data + name_offset, &slice)?;
+    }
+    Ok(Value::from_type(address))
+}
+
+/// Increment the reference count of a `Thread` handle.
+fn clone_handle(memory: &mut ThreadMemory, handle: &ThreadHandle) -> Result<()> {
+    let address = handle.value.read_uint() as usize;
+    let (strong, _) = named_field(wrapped_pointee(handle.ty)?, "strong")?;
+    let count = memory
+        .read_bytes(address + strong, pointer_width())?
+        .read_uint() as usize;
+    memory.write_bytes(address + strong, &(count + 1).to_ne_bytes())
+}
+
+/// Return the address of the `Inner` data of a `Thread` handle, and its type.
+fn handle_inner(handle: &ThreadHandle) -> Result<(usize, Ty)> {
+    let (data, inner_ty) = named_field(wrapped_pointee(handle.ty)?, "data")?;
+    Ok((handle.value.read_uint() as usize + data, inner_ty))
+}
+
+/// Return the address of the futex of the parker of a `Thread` handle.
+pub(super) fn parker_address(handle: &ThreadHandle) -> Result<usize> {
+    let (inner, inner_ty) = handle_inner(handle)?;
+    let (parker_offset, parker_ty) = named_field(inner_ty, "parker")?;
+    let (state_offset, _) = named_field(parker_ty, "state")?;
+    Ok(inner + parker_offset + state_offset)
+}
+
+/// Read the id stored in a `Thread` handle.
+fn handle_id(memory: &ThreadMemory, handle: &ThreadHandle) -> Result<u64> {
+    let (inner, inner_ty) = handle_inner(handle)?;
+    let (id_offset, _) = named_field(inner_ty, "id")?;
+    Ok(memory.read_bytes(inner + id_offset, 8)?.read_uint() as u64)
+}
+
+/// Read the name stored in a `Thread` handle, without its nul terminator.
+fn handle_name(memory: &ThreadMemory, handle: &ThreadHandle) -> Result<Option<String>> {
+    let (inner, inner_ty) = handle_inner(handle)?;
+    let name_offset = name_slice_offset(inner_ty)?;
+    let ptr_width = pointer_width();
+    let slice = memory.read_bytes(inner + name_offset, 2 * ptr_width)?;
+    let bytes = slice.as_bytes();
+    let address = uint_from_bytes(&bytes[..ptr_width]) as usize;
+    let len = uint_from_bytes(&bytes[ptr_width..]) as usize;
+    if address == 0 || len == 0 {
+        return Ok(None);
+    }
+    let name = memory.read_bytes(address, len - 1)?;
+    Ok(Some(String::from_utf8_lossy(name.as_bytes()).into_owned()))
+}
+
+/// Return the offset of the name of a thread in the `Inner` data of its
+/// handle, which is a boxed slice wrapped in an `Option<ThreadNameString>`.
+fn name_slice_offset(inner_ty: Ty) -> Result<usize> {
+    let (name_offset, option_ty) = named_field(inner_ty, "name")?;
+    let TyKind::RigidTy(RigidTy::Adt(_, args)) = option_ty.kind() else {
+        bail!("Expected an `Option<ThreadNameString>`, but found `{option_ty}`");
+    };
+    let name_ty = *args.0[0].ty().context("Expected a type argument")?;
+    // `Some` uses the null niche of the slice, so they share an offset.
+    let (c_string_offset, c_string_ty) = named_field(name_ty, "inner")?;
+    let (slice_offset, _) = named_field(c_string_ty, "inner")?;
+    Ok(name_offset + c_string_offset + slice_offset)
+}
+
+/// Read and free the `Option<String>` name given to `Thread::new`.
+fn read_thread_name_arg(memory: &ThreadMemory, value: &Value, ty: Ty) -> Result<Option<String>> {
+    let TyKind::RigidTy(RigidTy::Adt(_, args)) = ty.kind() else {
+        bail!("Expected an `Option<String>`, but found `{ty}`");
+    };
+    if read_discriminant(value, ty)?.read_uint() == 0 {
+        return Ok(None);
+    }
+    let string_ty = *args.0[0].ty().context("Expected a type argument")?;
+    // `Some(String)` uses a niche of the string, so they share an offset.
+    let (vec_offset, vec_ty) = named_field(string_ty, "vec")?;
+    let (buf_offset, raw_vec_ty) = named_field(vec_ty, "buf")?;
+    let (len_offset, _) = named_field(vec_ty, "len")?;
+    let (inner_offset, raw_inner_ty) = named_field(raw_vec_ty, "inner")?;
+    let (ptr_offset, _) = named_field(raw_inner_ty, "ptr")?;
+    let (cap_offset, _) = named_field(raw_inner_ty, "cap")?;
+    let raw = vec_offset + buf_offset + inner_offset;
+    let ptr_width = pointer_width();
+    let word =
+        |offset: usize| uint_from_bytes(&value.as_bytes()[offset..offset + ptr_width]) as usize;
+    let (ptr, cap, len) = (
+        word(raw + ptr_offset),
+        word(raw + cap_offset),
+        word(vec_offset + len_offset),
+    );
+    let name = String::from_utf8_lossy(memory.read_bytes(ptr, len)?.as_bytes()).into_owned();
+    if cap > 0 {
+        memory.deallocate(ptr, cap, 1)?;
+    }
+    Ok(Some(name))
+}
+
+/// Build the `Ok(value)` variant of an `io::Result`.
+fn io_result_ok(result_ty: Ty, value: Value) -> Result<Value> {
+    let TyKind::RigidTy(RigidTy::Adt(def, _)) = result_ty.kind() else {
+        bail!("Expected an `io::Result`, but found `{result_ty}`");
+    };
+    build_enum_variant(&[value], result_ty, def, VariantIdx::to_val(0))
+}
@@ -0,0 +1,57 @@
+//! Thread-local destructors.
+//!
+//! `thread_local!` values that need dropping register a destructor with the
+//! C runtime the first time they are initialized. The registration is
+//! intercepted, so that the destructors are interpreted when the interpreted
+//! thread exits instead of when the host thread does.
+
+use super::function::invoke_fn;
+use crate::memory::ThreadMemory;
+use crate::value::Value;
+use anyhow::Result;
+use rustc_public::CrateDef;
+use rustc_public::mir::mono::Instance;
+use tracing::debug;
+
+/// The thread-local runtime functions intercepted by the interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsFn {
+    /// `std::sys::thread_local::destructors::register(*mut u8, unsafe extern "C" fn(*mut u8))`
+    RegisterDtor,
+}
+
+impl TlsFn {
+    /// Return the thread-local runtime function implemented by `instance`, if any.
+    pub fn from_instance(instance: Instance) -> Option<Self> {
+        match instance.def.name().as_str() {
+            "std::sys::thread_local::destructors::linux_like::register"
+            | "std::sys::thread_local::destructors::list::register" => Some(TlsFn::RegisterDtor),
+            _ => None,
+        }
+    }
+
+    /// Execute the call against the interpreter thread.
+    pub fn eval(self, args: &[Value], memory: &mut ThreadMemory) -> Result<Value> {
+        debug!("Thread-local runtime call: {self:?}");
+        match self {
+            TlsFn::RegisterDtor => {
+                let data = args[0].read_uint() as usize;
+                let dtor = memory.resolve_fn_ptr(args[1].read_uint() as usize)?;
+                memory.register_tls_dtor(data, dtor);
+                Ok(Value::unit().clone())
+            }
+        }
+    }
+}
+
+/// Run the thread-local destructors of a thread whose main function returned.
+///
+/// Destructors run in reverse registration order. A destructor may access
+/// other thread-locals and register new destructors, which also run.
+pub fn run_tls_dtors(memory: &mut ThreadMemory) -> Result<()> {
+    while let Some((data, dtor)) = memory.pop_tls_dtor() {
+        debug!("Running thread-local destructor `{}`", dtor.name());
+        invoke_fn(dtor, memory, vec![Value::from_type(data)], &mut None)?;
+    }
+    Ok(())
+}
@@ -8,6 +8,7 @@
 //! Semantic versioning will only apply snapcrab binary.
 
 #![feature(rustc_private)]
+#![feature(f16, f128, float_minimum_maximum)]
 #![doc(hidden)]
 
 extern crate rustc_driver;
@@ -21,8 +22,11 @@ mod memory;
 mod ty;
 mod value;
 
-pub use crate::interpreter::check::CheckConfig;
+pub use crate::interpreter::check::{CheckConfig, CheckKind, CheckLevel, parse_check_setting};
 use crate::interpreter::function::invoke_fn;
+pub use crate::interpreter::panic::{Abort, Panic};
+pub use crate::interpreter::thread::ThreadFn;
+use crate::interpreter::thread::finish_thread;
 use crate::memory::ThreadMemory;
 use crate::value::TypedValue;
 use anyhow::{Context, Result, bail};
@@ -33,6 +37,18 @@ use std::path::Path;
 use std::process::ExitCode;
 use tracing::info;
 
+/// Configuration of an interpreter run.
+#[derive(Clone, Debug, Default)]
+pub struct RunConfig {
+    /// Which undefined behavior checks to perform.
+    pub checks: CheckConfig,
+    /// Fail the run if heap allocations are still live when it returns.
+    pub deny_leaks: bool,
+    /// Interleave interpreted threads at every function call, with a scheduler
+    /// seeded with this value.
+    pub schedule_seed: Option<u64>,
+}
+
 /// Execute a specific function by name from the current crate.
 ///
 /// This function searches for a function definition with the given name,
@@ -53,7 +69,7 @@ use tracing::info;
 /// ```
 pub fn run_function(
     fn_name: &str,
-    check_config: CheckConfig,
+    config: RunConfig,
     native_libs: &[impl AsRef<Path>],
 ) -> Result<Vec<u8>> {
     // Load native libraries
@@ -90,8 +106,15 @@ pub fn run_function(
 
     // Execute function
     let mut memory = ThreadMemory::new();
-    memory.check_config = check_config;
-    let result = invoke_fn(instance, &mut memory, vec![], &mut None)?;
+    memory.check_config = config.checks;
+    memory.schedule_seed = config.schedule_seed;
+    let result = invoke_fn(instance, &mut memory, vec![], &mut None);
+    let result = finish_thread(&mut memory, result).and_then(|value| {
+        check_leaks(&memory, config.deny_leaks)?;
+        Ok(value)
+    });
+    stop_threads(&mut memory);
+    let result = result?;
 
     // Get return type from instance
     let body = instance.body().context("No body for function")?;
@@ -108,6 +131,37 @@ pub fn run_function(
     Ok(result.as_bytes().to_vec())
 }
 
+/// Report heap allocations that outlived the interpreted program.
+///
+/// Leaks are only a warning, unless `deny_leaks` is set in the run config.
+/// Threads that are still running when the program ends may own allocations
+/// that they would free later, so leaks are not checked in that case.
+fn check_leaks(memory: &ThreadMemory, deny_leaks: bool) -> Result<()> {
+    if let Some(scheduler) = &memory.scheduler {
+        let unfinished = scheduler.unfinished_threads();
+        if unfinished > 0 {
+            eprintln!(
+                "warning: skipped the leak check, since {unfinished} thread(s) did not finish"
+            );
+            return Ok(());
+        }
+    }
+    if let Some(report) = memory.leak_report() {
+        if deny_leaks {
+            bail!("{report}");
+        }
+        eprint!("warning: {report}");
+    }
+    Ok(())
+}
+
+/// Stop the threads that are still running once the main thread returned.
+fn stop_threads(memory: &mut ThreadMemory) {
+    if let Some(scheduler) = memory.scheduler.take() {
+        scheduler.shut_down();
+    }
+}
+
 /// Load native shared libraries so their symbols are available to the interpreter.
 ///
 /// Uses `RTLD_GLOBAL` so symbols are visible to `dlsym(RTLD_DEFAULT, ...)`,
@@ -137,7 +191,7 @@ fn load_native_libs(paths: &[impl AsRef<Path>]) -> Result<()> {
     Ok(())
 }
 
-pub fn run_main(check_config: CheckConfig, native_libs: &[impl AsRef<Path>]) -> Result<ExitCode> {
+pub fn run_main(config: RunConfig, native_libs: &[impl AsRef<Path>]) -> Result<ExitCode> {
     load_native_libs(native_libs)?;
     let entry_fn = entry_fn().context("No entry function found")?;
     info!("Found entry function: {}", entry_fn.name());
@@ -146,8 +200,15 @@ pub fn run_main(check_config: CheckConfig, native_libs: &[impl AsRef<Path>]) ->
         Instance::try_from(entry_fn).context("Failed to create instance from entry function")?;
 
     let mut memory = ThreadMemory::new();
-    memory.check_config = check_config;
-    let result = invoke_fn(instance, &mut memory, vec![], &mut None)?;
+    memory.check_config = config.checks;
+    memory.schedule_seed = config.schedule_seed;
+    let result = invoke_fn(instance, &mut memory, vec![], &mut None);
+    let result = finish_thread(&mut memory, result).and_then(|value| {
+        check_leaks(&memory, config.deny_leaks)?;
+        Ok(value)
+    });
+    stop_threads(&mut memory);
+    let result = result?;
 
     // Convert the result value to an exit code
     match result {
@@ -14,9 +14,10 @@ extern crate rustc_interface;
 extern crate rustc_middle;
 extern crate rustc_public;
 
-use clap::Parser;
+use clap::{ArgAction, Parser};
 use rustc_public::target::{Endian, MachineInfo, MachineSize};
 use rustc_public::{CompilerError, run};
+use std::hash::{BuildHasher, Hasher, RandomState};
 use std::ops::ControlFlow;
 use std::process::ExitCode;
 use tracing::info;
@@ -38,9 +39,42 @@ struct Args {
     )]
     start_fn: Option<String>,
 
-    /// Skip specific UB checks (comma-separated: validity, alignment, bounds)
+    /// Skip specific UB checks (comma-separated: validity, alignment, bounds,
+    /// load-validity)
     #[arg(long = "skip-check", value_delimiter = ',')]
-    skip_checks: Vec<String>,
+    skip_checks: Vec<snapcrab::CheckKind>,
+
+    /// Set the level of UB checks to error, warn or off (comma-separated, e.g.
+    /// `bounds=warn,validity=off`, or `all=warn` to report every violation)
+    #[arg(long = "check", value_delimiter = ',', value_parser = snapcrab::parse_check_setting)]
+    checks: Vec<(Vec<snapcrab::CheckKind>, snapcrab::CheckLevel)>,
+
+    /// Fail if heap allocations are still live when the program returns
+    #[arg(long)]
+    deny_leaks: bool,
+
+    /// Compile with debug assertions, like a dev build
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = ArgAction::Set)]
+    debug_assertions: bool,
+
+    /// Enable overflow checks (default: the value of --debug-assertions)
+    #[arg(long, value_name = "BOOL")]
+    overflow_checks: Option<bool>,
+
+    /// Enable the unsafe precondition checks of the standard library
+    /// (default: the value of --debug-assertions)
+    #[arg(long, value_name = "BOOL")]
+    ub_checks: Option<bool>,
+
+    /// Interleave interpreted threads at every function call, deterministically,
+    /// with a scheduler seeded with SEED
+    #[arg(long, value_name = "SEED")]
+    schedule_seed: Option<u64>,
+
+    /// Interleave interpreted threads with a scheduler seeded with a random
+    /// seed, which is printed if the program fails
+    #[arg(long, conflicts_with = "schedule_seed")]
+    random_schedule: bool,
 
     /// Native shared libraries to load before interpretation
     #[arg(long = "native-lib")]
@@ -68,22 +102,54 @@ fn main() -> ExitCode {
         rustc_args.push("--crate-type=lib".to_string());
     }
 
+    // Configure the session and the interpreter with the same runtime checks,
+    // so code that is checked at compile time and at runtime agrees. UB checks
+    // have no stable flag; they are only evaluated by the interpreter, and
+    // follow `-Cdebug-assertions` in the session.
+    let overflow_checks = args.overflow_checks.unwrap_or(args.debug_assertions);
+    let ub_checks = args.ub_checks.unwrap_or(args.debug_assertions);
+    rustc_args.push(format!("-Cdebug-assertions={}", args.debug_assertions));
+    rustc_args.push(format!("-Coverflow-checks={overflow_checks}"));
+
     rustc_args.push(args.input);
 
-    let check_config = snapcrab::CheckConfig::with_skipped(&args.skip_checks);
+    let mut config = snapcrab::RunConfig {
+        deny_leaks: args.deny_leaks,
+        schedule_seed: args
+            .schedule_seed
+            .or_else(|| args.random_schedule.then(random_seed)),
+        ..Default::default()
+    };
+    let check_config = &mut config.checks;
+    for kind in args.skip_checks {
+        check_config.set_level(kind, snapcrab::CheckLevel::Off);
+    }
+    for (kinds, level) in args.checks {
+        for kind in kinds {
+            check_config.set_level(kind, level);
+        }
+    }
+    check_config.overflow_checks = overflow_checks;
+    check_config.ub_checks = ub_checks;
     let native_libs = args.native_libs;
     let result = run!(&rustc_args, || start_interpreter(
         args.start_fn,
-        check_config,
+        config,
         &native_libs,
     ));
 
     match result {
-        Ok(_) | Err(CompilerError::Skipped | CompilerError::Interrupted(_)) => ExitCode::SUCCESS,
+        Err(CompilerError::Interrupted(code)) => code,
+        Ok(_) | Err(CompilerError::Skipped) => ExitCode::SUCCESS,
         _ => ExitCode::FAILURE,
     }
 }
 
+/// Pick a seed for the thread scheduler.
+fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
 /// Start the interpreter with optional custom start function.
 ///
 /// This function initializes the interpreter and executes either the main function
@@ -94,12 +160,13 @@ fn main() -> ExitCode {
 /// * `start_fn` - Optional name of custom function to execute instead of main
 ///
 /// # Returns
-/// * `ControlFlow::Break(())` - Always breaks to exit the compiler callback
+/// * `ControlFlow::Break(code)` - Always breaks to exit the compiler callback with
+///   the process exit code
 fn start_interpreter(
     start_fn: Option<String>,
-    check_config: snapcrab::CheckConfig,
+    config: snapcrab::RunConfig,
     native_libs: &[String],
-) -> ControlFlow<()> {
+) -> ControlFlow<ExitCode> {
     let target = MachineInfo::target();
     let host = MachineInfo {
         endian: Endian::Little,
@@ -109,25 +176,37 @@ fn start_interpreter(
         eprintln!(
             "error: snapcrab does not support interpreting code for a different target than the host machine"
         );
-        return ControlFlow::Break(());
+        return ControlFlow::Break(ExitCode::FAILURE);
     }
 
+    let schedule_seed = config.schedule_seed;
     let crate_name = rustc_public::local_crate().name;
     info!("Interpreting crate: {}", crate_name);
 
     let result = if let Some(fn_name) = start_fn {
         info!("Using custom start function: {}", fn_name);
-        snapcrab::run_function(&fn_name, check_config, native_libs).map(|_| ExitCode::SUCCESS)
+        snapcrab::run_function(&fn_name, config, native_libs).map(|_| ExitCode::SUCCESS)
     } else {
-        snapcrab::run_main(check_config, native_libs)
+        snapcrab::run_main(config, native_libs)
     };
 
-    match result {
-        Ok(_) => {}
+    let code = match result {
+        Ok(code) => code,
+        // The panic runtime already reported the panic.
+        Err(e) if e.is::<snapcrab::Panic>() => ExitCode::from(101),
+        Err(e) if e.is::<snapcrab::Abort>() => {
+            eprintln!("error: {e}");
+            ExitCode::from(134)
+        }
         Err(e) => {
             eprintln!("{e}");
+            ExitCode::FAILURE
         }
+    };
+    if let Some(seed) = schedule_seed
+        && code != ExitCode::SUCCESS
+    {
+        eprintln!("note: run with `--schedule-seed {seed}` to replay this thread interleaving");
     }
-
-    ControlFlow::Break(())
+    ControlFlow::Break(code)
 }
@@ -4,28 +4,42 @@
 //! - One ThreadMemory per thread. Obviously.
 //! - Each thread memory contains their own stack
 //! - Thread memories share heap and statics
+//! - Thread-local statics live in the heap, but each thread has its own copy
 //! - Read and write to all memory segments are validated to avoid access out of
 //!   bounds.
 
+mod foreign;
 pub mod heap;
 mod sanitizer;
 mod stack;
 mod statics;
+mod thread_local;
+
+pub use stack::Backtrace;
 
 use crate::interpreter::check::CheckConfig;
+use crate::interpreter::native::is_native_code;
 use crate::interpreter::native::jit::JitEngine;
-use crate::ty::MonoType;
+use crate::interpreter::panic::PanicPayload;
+use crate::interpreter::scheduler::Scheduler;
+use crate::interpreter::thread::ThreadHandle;
+use crate::ty::{MonoType, may_be_uninit};
 use crate::value::Value;
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
+use foreign::Foreign;
 use heap::Heap;
+use rustc_public::CrateDef;
 use rustc_public::mir::Body;
 use rustc_public::mir::alloc::AllocId;
-use rustc_public::mir::mono::Instance;
+use rustc_public::mir::mono::{Instance, StaticDef};
 use rustc_public::target::MachineInfo;
-use rustc_public::ty::Ty;
+use rustc_public::ty::Span;
+use rustc_public::ty::{Allocation, Binder, ExistentialTraitRef, Ty};
 use stack::Stack;
 use statics::Statics;
 use std::sync::LazyLock;
+use thread_local::{ThreadLocals, TlsBlock};
+use tracing::debug;
 
 static MACHINE_INFO: LazyLock<MachineInfo> = LazyLock::new(MachineInfo::target);
 
@@ -44,23 +58,46 @@ pub fn machine_info() -> &'static MachineInfo {
 /// This is the main structure exported to the rest of the interpreter.
 pub struct ThreadMemory {
     stack: Stack,
-    #[allow(unused)]
     heap: Heap,
     statics: Statics,
+    foreign: Foreign,
+    thread_locals: ThreadLocals,
     /// Configuration for which UB checks to perform.
     pub check_config: CheckConfig,
     /// JIT engine for native function calls.
     pub jit: JitEngine,
+    /// Name of the interpreted thread, used in panic messages.
+    pub thread_name: String,
+    /// Payload of the panic that was just caught by `catch_unwind`, until
+    /// the catch function claims it.
+    pub caught_panic: Option<PanicPayload>,
+    /// The `std::thread::Thread` handle of the interpreted thread, once known.
+    pub thread_handle: Option<ThreadHandle>,
+    /// The scheduler that interleaves interpreted threads, once the program
+    /// spawned a thread.
+    pub scheduler: Option<Scheduler>,
+    /// The seed of the scheduler, if threads take turns at every function
+    /// call.
+    pub schedule_seed: Option<u64>,
 }
 
 impl Default for ThreadMemory {
     fn default() -> Self {
+        let jit = JitEngine::new().expect("Failed to initialize JIT engine");
+        let foreign = Foreign::default();
         Self {
             stack: Stack::default(),
             heap: Heap::default(),
-            statics: Statics::default(),
+            statics: Statics::new(jit.clone(), foreign.clone()),
+            foreign,
+            thread_locals: ThreadLocals::default(),
             check_config: CheckConfig::default(),
-            jit: JitEngine::new().expect("Failed to initialize JIT engine"),
+            jit,
+            thread_name: "main".to_string(),
+            caught_panic: None,
+            thread_handle: None,
+            scheduler: None,
+            schedule_seed: None,
         }
     }
 }
@@ -73,12 +110,33 @@ impl ThreadMemory {
         ThreadMemory::default()
     }
 
+    /// Create the memory of a new thread named `thread_name`.
+    ///
+    /// The new thread has its own stack and thread-local statics, and shares
+    /// everything else with this thread, including its scheduler.
+    pub fn new_thread(&self, thread_name: String) -> Self {
+        Self {
+            stack: self.stack.new_thread(),
+            heap: self.heap.clone(),
+            statics: self.statics.clone(),
+            foreign: self.foreign.clone(),
+            thread_locals: ThreadLocals::default(),
+            check_config: self.check_config.clone(),
+            jit: self.jit.clone(),
+            thread_name,
+            caught_panic: None,
+            thread_handle: None,
+            scheduler: self.scheduler.clone(),
+            schedule_seed: self.schedule_seed,
+        }
+    }
+
     /// Runs a method with their own stack frame.
-    pub fn with_stack_frame<F, R>(&mut self, instance: Instance, func: F) -> R
+    pub fn with_stack_frame<F, R>(&mut self, instance: Instance, body: Body, func: F) -> R
     where
         F: FnOnce(&Body, &mut Self) -> R,
     {
-        Stack::with_stack_frame(instance, self, func)
+        Stack::with_stack_frame(instance, body, self, func)
     }
 
     /// Resolve a compiler AllocId to a real memory address.
@@ -86,6 +144,175 @@ impl ThreadMemory {
         self.statics.resolve_alloc(alloc_id)
     }
 
+    /// Read the value of a compiler allocation of type `ty`, such as a constant.
+    ///
+    /// Pointers to other allocations are resolved to their address.
+    /// Uninitialized bytes are only accepted if a valid value of `ty` may
+    /// contain them, e.g. padding or the payload of an inactive variant, and
+    /// they read as zero. Since memory doesn't track initialization,
+    /// uninitialized bytes in any other type are reported instead.
+    pub fn allocation_value(&self, alloc: &Allocation, ty: Ty) -> Result<Value> {
+        if alloc.bytes.iter().any(Option::is_none) && !may_be_uninit(ty) {
+            bail!("Found uninitialized bytes in a value of type `{ty}`");
+        }
+        let mut bytes: Vec<u8> = alloc.bytes.iter().map(|b| b.unwrap_or(0)).collect();
+        let ptr_size = pointer_width();
+        for (offset, prov) in &alloc.provenance.ptrs {
+            let base = self.resolve_alloc(prov.0)?;
+            statics::relocate_ptr(&mut bytes[*offset..*offset + ptr_size], base);
+        }
+        Ok(Value::from_bytes(&bytes))
+    }
+
+    /// Store bytes that live until the end of the program and return their address.
+    pub fn intern_bytes(&self, bytes: &[u8]) -> usize {
+        self.statics.intern_bytes(bytes)
+    }
+
+    /// Create a function pointer to `instance`.
+    pub fn fn_ptr(&self, instance: Instance) -> usize {
+        self.statics.fn_ptr(instance)
+    }
+
+    /// Return the address of the vtable of `ty` for a trait object type.
+    pub fn vtable(&self, ty: Ty, trait_ref: Option<Binder<ExistentialTraitRef>>) -> Result<usize> {
+        self.statics.vtable(ty, trait_ref)
+    }
+
+    /// Find the concrete type behind a vtable.
+    pub fn vtable_ty(&self, address: usize) -> Result<Ty> {
+        match self.statics.vtable_ty(address) {
+            Some(ty) => Ok(ty),
+            None => bail!("Undefined behavior: invalid vtable pointer 0x{address:x}"),
+        }
+    }
+
+    /// Find the function of the callback `id`.
+    pub fn callback_instance(&self, id: usize) -> Option<Instance> {
+        self.statics.callback_instance(id)
+    }
+
+    /// Find the function that a function pointer points to.
+    pub fn resolve_fn_ptr(&self, address: usize) -> Result<Instance> {
+        match self.statics.fn_ptr_instance(address) {
+            Some(instance) => Ok(instance),
+            None => {
+                bail!("Undefined behavior: call through invalid function pointer 0x{address:x}")
+            }
+        }
+    }
+
+    /// Find the function that a function pointer points to, or `None` if it
+    /// points to native code that the interpreter has no function for.
+    pub fn resolve_any_fn_ptr(&self, address: usize) -> Result<Option<Instance>> {
+        if let Some(instance) = self.statics.fn_ptr_instance(address) {
+            return Ok(Some(instance));
+        }
+        if is_native_code(address) {
+            return Ok(None);
+        }
+        bail!("Undefined behavior: call through invalid function pointer 0x{address:x}")
+    }
+
+    /// Record the span of the call the current frame is about to make.
+    ///
+    /// This is used to report where heap blocks were allocated and freed.
+    pub fn set_call_site(&mut self, span: Span) {
+        self.stack.set_call_site(span)
+    }
+
+    /// Take a snapshot of the interpreted call stack.
+    pub fn backtrace(&self) -> Backtrace {
+        self.stack.backtrace()
+    }
+
+    /// The function and call site of every interpreted frame, innermost first.
+    pub fn call_stack(&self) -> impl Iterator<Item = (Instance, Option<Span>)> + '_ {
+        self.stack.call_stack()
+    }
+
+    /// Allocate a zero-initialized heap block.
+    pub fn allocate(&self, size: usize, align: usize) -> Result<usize> {
+        self.heap.allocate(size, align, Some(self.backtrace()))
+    }
+
+    /// Free a heap block. The size and alignment must match the allocation.
+    pub fn deallocate(&self, address: usize, size: usize, align: usize) -> Result<()> {
+        self.heap
+            .deallocate(address, size, align, Some(self.backtrace()))
+    }
+
+    /// Move a heap block into a new allocation of `new_size` bytes.
+    pub fn reallocate(
+        &self,
+        address: usize,
+        old_size: usize,
+        align: usize,
+        new_size: usize,
+    ) -> Result<usize> {
+        self.heap
+            .reallocate(address, old_size, align, new_size, Some(self.backtrace()))
+    }
+
+    /// Report heap blocks that are still allocated, if any.
+    pub fn leak_report(&self) -> Option<heap::LeakReport> {
+        self.heap.leak_report()
+    }
+
+    /// Return the address of this thread's copy of the thread-local static `def`.
+    ///
+    /// The copy is created from the static's initializer on first access.
+    pub fn thread_local_addr(&self, def: StaticDef) -> Result<usize> {
+        if let Some(block) = self.thread_locals.get(def) {
+            return Ok(block.address);
+        }
+        let name = def.name();
+        let alloc = def
+            .eval_initializer()
+            .map_err(|e| anyhow::anyhow!("failed to evaluate thread-local static `{name}`: {e}"))?;
+        let value = self.allocation_value(&alloc, def.ty())?;
+        let (size, align) = (value.len(), alloc.align as usize);
+        let address = if size == 0 {
+            // Zero-sized statics only need an aligned non-null address.
+            align
+        } else {
+            let address = self.allocate(size, align)?;
+            self.heap.write_addr(address, value.as_bytes())?;
+            address
+        };
+        debug!(
+            "Thread `{}` created `{name}` at 0x{address:x}",
+            self.thread_name
+        );
+        let block = TlsBlock {
+            address,
+            size,
+            align,
+        };
+        self.thread_locals.insert(def, block);
+        Ok(address)
+    }
+
+    /// Register a destructor to be called with `data` when the thread exits.
+    pub fn register_tls_dtor(&mut self, data: usize, dtor: Instance) {
+        self.thread_locals.register_dtor(data, dtor);
+    }
+
+    /// Take the next destructor to run at thread exit, if any is left.
+    pub fn pop_tls_dtor(&mut self) -> Option<(usize, Instance)> {
+        self.thread_locals.pop_dtor()
+    }
+
+    /// Free the thread-local statics of the thread once it has exited.
+    pub fn free_thread_locals(&mut self) -> Result<()> {
+        for block in self.thread_locals.take_storage() {
+            if block.size > 0 {
+                self.deallocate(block.address, block.size, block.align)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Read local variable.
     #[inline]
     #[allow(dead_code)]
@@ -104,75 +331,117 @@ impl ThreadMemory {
     }
 
     pub fn read_addr(&self, address: usize, ty: Ty) -> Result<Value> {
+        self.read_addr_with_align(address, ty, ty.alignment()?)
+    }
+
+    /// Read a value of type `ty` from an address that only needs to be aligned
+    /// to `alignment`, such as a field of a packed struct.
+    pub fn read_addr_with_align(&self, address: usize, ty: Ty, alignment: usize) -> Result<Value> {
         let size = ty.size()?;
         if size == 0 {
             return Ok(Value::unit().clone());
         }
-        let alignment = ty.alignment()?;
-
-        // Check alignment
-        if !address.is_multiple_of(alignment) {
-            anyhow::bail!(
-                "Misaligned memory access: address 0x{:x} is not aligned to {} bytes",
-                address,
-                alignment
-            );
-        }
+        self.check_alignment(address, alignment)?;
+        self.read_bytes(address, size)
+    }
 
+    /// Read `size` raw bytes starting at `address`, without any alignment check.
+    ///
+    /// If the bounds check doesn't stop an out-of-bounds read, the bytes are
+    /// read from the host memory, as long as it is mapped.
+    pub fn read_bytes(&self, address: usize, size: usize) -> Result<Value> {
         // Try stack first
         match self.stack.read_addr(address, size) {
-            Ok(data) => return Ok(Value::from_bytes(data)),
+            Ok(data) => return Ok(data),
             Err(MemoryAccessError::OutOfBounds) => {
-                anyhow::bail!(
-                    "Stack memory access out of bounds at address 0x{:x}",
-                    address
-                )
+                self.check_bounds("Stack", address, size, false)?;
+                return Ok(read_host(address, size));
             }
-            Err(MemoryAccessError::NotFound) => {} // Continue to next segment
+            Err(
+                MemoryAccessError::NotFound
+                | MemoryAccessError::UseAfterFree(_)
+                | MemoryAccessError::ReadOnly,
+            ) => {} // Continue to next segment
         }
 
-        // Try heap
+        // Try heap. Freed blocks are reported once no other segment claims
+        // the address, since the host allocator may have reused it.
+        let mut freed = None;
         match self.heap.read_addr(address, size) {
-            Ok(data) => return Ok(Value::from_bytes(data)),
+            Ok(data) => return Ok(data),
             Err(MemoryAccessError::OutOfBounds) => {
-                anyhow::bail!(
-                    "Heap memory access out of bounds at address 0x{:x}",
-                    address
-                )
+                self.check_bounds("Heap", address, size, false)?;
+                return Ok(read_host(address, size));
             }
-            Err(MemoryAccessError::NotFound) => {} // Continue to next segment
+            Err(MemoryAccessError::UseAfterFree(block)) => freed = Some(block),
+            Err(MemoryAccessError::NotFound | MemoryAccessError::ReadOnly) => {} // Continue to next segment
         }
 
         // Try statics
         match self.statics.read_addr(address, size) {
-            Ok(data) => Ok(Value::from_bytes(data)),
+            Ok(data) => return Ok(data),
             Err(MemoryAccessError::OutOfBounds) => {
-                anyhow::bail!(
-                    "Static memory access out of bounds at address 0x{:x}",
-                    address
-                )
+                self.check_bounds("Static", address, size, false)?;
+                return Ok(read_host(address, size));
             }
-            Err(MemoryAccessError::NotFound) => {
-                anyhow::bail!("Address 0x{:x} not found in any memory segment", address)
+            Err(
+                MemoryAccessError::NotFound
+                | MemoryAccessError::UseAfterFree(_)
+                | MemoryAccessError::ReadOnly,
+            ) => {} // Continue to next segment
+        }
+
+        // Try native statics
+        match self.foreign.read_addr(address, size) {
+            Ok(data) => Ok(data),
+            Err(MemoryAccessError::OutOfBounds) => {
+                self.check_bounds("Foreign", address, size, false)?;
+                Ok(read_host(address, size))
             }
+            Err(
+                MemoryAccessError::NotFound
+                | MemoryAccessError::UseAfterFree(_)
+                | MemoryAccessError::ReadOnly,
+            ) => not_found(address, freed),
         }
     }
 
+    /// Check that an atomic access of `size` bytes at `address` is valid.
+    ///
+    /// The access must be in bounds and aligned to its size. Accesses that
+    /// modify memory must not target immutable statics.
+    /// The access itself is done directly on the address, so that it is
+    /// atomic with respect to native code as well.
+    pub fn check_atomic_access(&self, address: usize, size: usize, write: bool) -> Result<()> {
+        check_alignment(address, size)?;
+        self.read_bytes(address, size)?;
+        if write && self.is_immutable_static(address, size) {
+            bail!(
+                "Undefined behavior: atomic write to immutable static memory at address \
+                 0x{address:x}"
+            );
+        }
+        Ok(())
+    }
+
     pub fn write_addr(&mut self, address: usize, data: &[u8], ty: Ty) -> Result<()> {
+        self.write_addr_with_align(address, data, ty, ty.alignment()?)
+    }
+
+    /// Write a value of type `ty` to an address that only needs to be aligned
+    /// to `alignment`, such as a field of a packed struct.
+    pub fn write_addr_with_align(
+        &mut self,
+        address: usize,
+        data: &[u8],
+        ty: Ty,
+        alignment: usize,
+    ) -> Result<()> {
         let size = ty.size()?;
         if size == 0 {
             return Ok(());
         }
-        let alignment = ty.alignment()?;
-
-        // Check alignment
-        if !address.is_multiple_of(alignment) {
-            anyhow::bail!(
-                "Misaligned memory access: address 0x{:x} is not aligned to {} bytes",
-                address,
-                alignment
-            );
-        }
+        self.check_alignment(address, alignment)?;
 
         // Check data size matches type size
         if data.len() != size {
@@ -183,47 +452,266 @@ impl ThreadMemory {
             );
         }
 
+        self.write_bytes(address, data)
+    }
+
+    /// Write raw bytes starting at `address`, without any alignment check.
+    ///
+    /// If the bounds check doesn't stop an out-of-bounds write, the bytes are
+    /// written to the host memory, as long as it is mapped and writable.
+    pub fn write_bytes(&mut self, address: usize, data: &[u8]) -> Result<()> {
         // Try stack first
         match self.stack.write_addr(address, data) {
             Ok(()) => return Ok(()),
             Err(MemoryAccessError::OutOfBounds) => {
-                anyhow::bail!(
-                    "Stack memory access out of bounds at address 0x{:x}",
-                    address
-                )
+                self.check_bounds("Stack", address, data.len(), true)?;
+                write_host(address, data);
+                return Ok(());
             }
-            Err(MemoryAccessError::NotFound) => {} // Continue to next segment
+            Err(
+                MemoryAccessError::NotFound
+                | MemoryAccessError::UseAfterFree(_)
+                | MemoryAccessError::ReadOnly,
+            ) => {} // Continue to next segment
         }
 
-        // Try heap
+        // Try heap, reporting freed blocks like `read_bytes` does.
+        let mut freed = None;
         match self.heap.write_addr(address, data) {
             Ok(()) => return Ok(()),
             Err(MemoryAccessError::OutOfBounds) => {
-                anyhow::bail!(
-                    "Heap memory access out of bounds at address 0x{:x}",
-                    address
-                )
+                self.check_bounds("Heap", address, data.len(), true)?;
+                write_host(address, data);
+                return Ok(());
             }
-            Err(MemoryAccessError::NotFound) => {} // Continue to next segment
+            Err(MemoryAccessError::UseAfterFree(block)) => freed = Some(block),
+            Err(MemoryAccessError::NotFound | MemoryAccessError::ReadOnly) => {} // Continue to next segment
         }
 
-        // Try statics
-        match self.statics.write_addr(address, data) {
-            Ok(()) => Ok(()),
-            Err(MemoryAccessError::OutOfBounds) => {
-                anyhow::bail!(
-                    "Static memory access out of bounds at address 0x{:x}",
-                    address
-                )
+        // Try statics, and then native statics
+        let segments: [(&str, &dyn MemorySegment); 2] =
+            [("Static", &self.statics), ("Foreign", &self.foreign)];
+        for (segment, memory) in segments {
+            match memory.write_addr(address, data) {
+                Ok(()) => return Ok(()),
+                Err(MemoryAccessError::OutOfBounds) => {
+                    self.check_bounds(segment, address, data.len(), true)?;
+                    write_host(address, data);
+                    return Ok(());
+                }
+                Err(MemoryAccessError::ReadOnly) => {
+                    anyhow::bail!(
+                        "Undefined behavior: write to immutable static memory at address 0x{:x}",
+                        address
+                    )
+                }
+                Err(MemoryAccessError::NotFound | MemoryAccessError::UseAfterFree(_)) => {}
             }
-            Err(MemoryAccessError::NotFound) => {
-                // No more segments to try
-                anyhow::bail!("Address 0x{:x} not found in any memory segment", address)
+        }
+        // No more segments to try
+        not_found(address, freed)
+    }
+}
+
+/// Report an access to an address that no memory segment contains, which
+/// is a use after free if it belonged to the `freed` heap block.
+fn not_found<T>(address: usize, freed: Option<Box<sanitizer::FreedAlloc>>) -> Result<T> {
+    match freed {
+        Some(freed) => {
+            bail!("Undefined behavior: use after free at address 0x{address:x}; {freed}")
+        }
+        None => bail!("Address 0x{address:x} not found in any memory segment"),
+    }
+}
+
+impl ThreadMemory {
+    /// Check that `address` is aligned according to the alignment check level.
+    pub fn check_alignment(&self, address: usize, alignment: usize) -> Result<()> {
+        self.check_config
+            .alignment
+            .check(|| check_alignment(address, alignment))
+    }
+
+    /// Check that a reference to `size` bytes at `address` is dereferenceable.
+    ///
+    /// The range must be inside a single live allocation of one of the memory
+    /// segments, and the address must be aligned to `alignment`. The range is
+    /// checked according to the bounds check level, since a dangling reference
+    /// is an out-of-bounds access waiting to happen.
+    pub fn check_reference(&self, address: usize, size: usize, alignment: usize) -> Result<()> {
+        self.check_alignment(address, alignment)?;
+        self.check_config.bounds.check(|| {
+            if address == 0 {
+                bail!("null reference");
             }
+            if size == 0 {
+                // Zero-sized references only need to be non-null and aligned.
+                return Ok(());
+            }
+            let segments: [(&str, &dyn MemorySegment); 4] = [
+                ("Stack", &self.stack),
+                ("Heap", &self.heap),
+                ("Static", &self.statics),
+                ("Foreign", &self.foreign),
+            ];
+            let mut freed = None;
+            for (segment, memory) in segments {
+                match memory.check_addr(address, size) {
+                    Ok(()) => return Ok(()),
+                    Err(MemoryAccessError::OutOfBounds) => bail!(
+                        "{size} bytes at address 0x{address:x} are out of bounds of their \
+                         {segment} allocation"
+                    ),
+                    Err(MemoryAccessError::UseAfterFree(block)) => freed = Some(block),
+                    Err(MemoryAccessError::NotFound | MemoryAccessError::ReadOnly) => {}
+                }
+            }
+            match freed {
+                Some(freed) => bail!("dangling reference to address 0x{address:x}; {freed}"),
+                None => bail!("Address 0x{address:x} not found in any memory segment"),
+            }
+        })
+    }
+
+    /// Whether the `size` bytes at `address` are inside a single live
+    /// allocation of any memory segment.
+    pub fn in_allocation(&self, address: usize, size: usize) -> bool {
+        let segments: [&dyn MemorySegment; 4] =
+            [&self.stack, &self.heap, &self.statics, &self.foreign];
+        segments
+            .iter()
+            .any(|memory| memory.check_addr(address, size).is_ok())
+    }
+
+    /// Give the program access to the `size` bytes at `address`, which native
+    /// code passed to a callback, until [`ThreadMemory::release_native`].
+    ///
+    /// Returns whether the bytes were lent, which is only done if they don't
+    /// belong to any memory segment yet.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must be valid for reads until they are released, and valid
+    /// for writes as well if `writable` is set.
+    pub unsafe fn lend_native(&self, address: usize, size: usize, writable: bool) -> bool {
+        let segments: [&dyn MemorySegment; 3] = [&self.stack, &self.heap, &self.statics];
+        let unknown = segments.iter().all(|memory| {
+            matches!(
+                memory.check_addr(address, 1),
+                Err(MemoryAccessError::NotFound | MemoryAccessError::UseAfterFree(_))
+            )
+        });
+        // SAFETY: guaranteed by the caller.
+        unknown && unsafe { self.foreign.lend(address, size, writable) }
+    }
+
+    /// Revoke the access given by [`ThreadMemory::lend_native`].
+    pub fn release_native(&self, address: usize, size: usize) {
+        self.foreign.release(address, size);
+    }
+
+    /// Whether the `size` bytes at `address` belong to a static that cannot be written.
+    fn is_immutable_static(&self, address: usize, size: usize) -> bool {
+        let in_statics = self.statics.check_addr(address, size).is_ok();
+        let in_foreign = self.foreign.check_addr(address, size).is_ok();
+        (in_statics && !self.statics.is_writable(address, size))
+            || (in_foreign && !self.foreign.is_writable(address, size))
+    }
+
+    /// Report an access of `size` bytes at `address` that is out of bounds of
+    /// its allocation in `segment`, according to the bounds check level.
+    ///
+    /// The access is an error regardless of the level if the host doesn't have
+    /// the bytes mapped, or not writable for a `write`, since it would crash.
+    fn check_bounds(&self, segment: &str, address: usize, size: usize, write: bool) -> Result<()> {
+        let violation = || {
+            anyhow!("{segment} memory access out of bounds at address 0x{address:x} ({size} bytes)")
+        };
+        if !is_host_mapped(address, size, write) {
+            return Err(violation());
         }
+        self.check_config.bounds.check(|| Err(violation()))
     }
 }
 
+/// Read `size` bytes at `address` from the host memory, for an out-of-bounds
+/// access that the bounds check let through.
+fn read_host(address: usize, size: usize) -> Value {
+    // SAFETY: `check_bounds` verified that the host has the bytes mapped.
+    // The program reads them out of bounds, which is undefined behavior that
+    // the user chose not to stop.
+    Value::from_bytes(unsafe { std::slice::from_raw_parts(address as *const u8, size) })
+}
+
+/// Write `data` to `address` in the host memory, for an out-of-bounds access
+/// that the bounds check let through.
+fn write_host(address: usize, data: &[u8]) {
+    // SAFETY: `check_bounds` verified that the host has the bytes mapped and
+    // writable. The program writes them out of bounds, which is undefined
+    // behavior that the user chose not to stop.
+    unsafe { std::ptr::copy(data.as_ptr(), address as *mut u8, data.len()) };
+}
+
+/// Whether the host has the `size` bytes at `address` mapped for reading,
+/// and for writing as well if `write` is set.
+#[cfg(target_os = "linux")]
+fn is_host_mapped(address: usize, size: usize, write: bool) -> bool {
+    let Some(end) = address.checked_add(size) else {
+        return false;
+    };
+    let Ok(maps) = std::fs::read_to_string("/proc/self/maps") else {
+        return false;
+    };
+    // Mappings are sorted by address, and the range may span several of them.
+    let mut mapped_to = address;
+    for line in maps.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(range), Some(perms)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some((start, stop)) = range.split_once('-').and_then(|(start, stop)| {
+            Some((
+                usize::from_str_radix(start, 16).ok()?,
+                usize::from_str_radix(stop, 16).ok()?,
+            ))
+        }) else {
+            continue;
+        };
+        if stop <= mapped_to {
+            continue;
+        }
+        let readable = perms.starts_with('r');
+        let writable = perms.get(1..2) == Some("w");
+        if start > mapped_to || !readable || (write && !writable) {
+            return false;
+        }
+        mapped_to = stop;
+        if mapped_to >= end {
+            return true;
+        }
+    }
+    false
+}
+
+/// Whether the host has the `size` bytes at `address` mapped, which is only
+/// known on Linux, so accesses out of bounds are always errors elsewhere.
+#[cfg(not(target_os = "linux"))]
+fn is_host_mapped(_address: usize, _size: usize, _write: bool) -> bool {
+    false
+}
+
+/// Check that `address` is a multiple of `alignment`.
+pub fn check_alignment(address: usize, alignment: usize) -> Result<()> {
+    if !address.is_multiple_of(alignment) {
+        anyhow::bail!(
+            "Misaligned memory access: address 0x{:x} is not aligned to {} bytes",
+            address,
+            alignment
+        );
+    }
+    Ok(())
+}
+
 /// The type of errors that can be encountered during a memory access.
 #[derive(Debug)]
 enum MemoryAccessError {
@@ -231,6 +719,10 @@ enum MemoryAccessError {
     OutOfBounds,
     /// The base address is not in this memory segment.
     NotFound,
+    /// The base address belongs to a block that has already been freed.
+    UseAfterFree(Box<sanitizer::FreedAlloc>),
+    /// The request writes to memory that is read-only.
+    ReadOnly,
 }
 
 impl std::fmt::Display for MemoryAccessError {
@@ -238,6 +730,8 @@ impl std::fmt::Display for MemoryAccessError {
         match self {
             MemoryAccessError::OutOfBounds => write!(f, "Memory access out of bounds"),
             MemoryAccessError::NotFound => write!(f, "Address not found in memory segment"),
+            MemoryAccessError::UseAfterFree(freed) => write!(f, "Use after free: {freed}"),
+            MemoryAccessError::ReadOnly => write!(f, "Write to read-only memory"),
         }
     }
 }
@@ -251,19 +745,28 @@ impl std::error::Error for MemoryAccessError {}
 /// Implementors must guarantee:
 /// - The safety of all memory operations performed
 /// - Ownership or valid access rights to all memory accessed
-/// - That returned memory references remain valid for their lifetime
 /// - That concurrent access is properly synchronized if needed
 unsafe trait MemorySegment {
+    /// Checks that `size` bytes at `address` can be accessed.
+    ///
+    /// # Returns
+    /// * `Ok(())` - The range is within an allocation of this segment
+    /// * `Err` - Error found when trying to satisfy the request
+    fn check_addr(&self, address: usize, size: usize) -> Result<(), MemoryAccessError>;
+
     /// Reads data from a memory address.
     ///
+    /// The data is copied while the allocation is known to be live, so a
+    /// concurrent deallocation cannot invalidate it.
+    ///
     /// # Arguments
     /// * `address` - The memory address to read from
     /// * `size` - Number of bytes to read
     ///
     /// # Returns
-    /// * `Ok(&[u8])` - Reference to the memory data if the read is valid
+    /// * `Ok(Value)` - A copy of the memory data if the read is valid
     /// * `Err` - Error found when trying to satisfy the request
-    fn read_addr(&self, address: usize, size: usize) -> Result<&[u8], MemoryAccessError>;
+    fn read_addr(&self, address: usize, size: usize) -> Result<Value, MemoryAccessError>;
 
     /// Writes data to a memory address.
     ///
@@ -0,0 +1,117 @@
+//! Memory owned by native code that the interpreter accesses directly.
+//!
+//! Mutable statics of dependencies that also exist in a native library are
+//! aliased to the native symbol, so that the interpreter and native code share
+//! a single copy. The regions are registered here so that accesses to them are
+//! still bounds checked.
+//!
+//! Native memory that native code passes to a callback, e.g. a formatting
+//! buffer on its stack, is lent to the interpreter the same way, until the
+//! callback returns.
+
+use crate::memory::sanitizer::MemorySanitizer;
+use crate::memory::{MemoryAccessError, MemorySegment};
+use crate::value::Value;
+use std::sync::{Arc, RwLock};
+
+/// Thread safe registry of native memory regions.
+#[derive(Clone, Default)]
+pub struct Foreign(Arc<RwLock<ForeignInner>>);
+
+#[derive(Default)]
+struct ForeignInner {
+    /// Every registered region.
+    sanitizer: MemorySanitizer,
+    /// The regions that the program may write to.
+    writable: MemorySanitizer,
+}
+
+impl Foreign {
+    /// Register the `size` bytes at `address` as a native region.
+    ///
+    /// Registering the same region twice has no effect.
+    ///
+    /// # Safety
+    ///
+    /// The region must be valid for reads for the rest of the process, and
+    /// valid for writes as well if `writable` is set.
+    pub unsafe fn register(&self, address: usize, size: usize, writable: bool) {
+        let mut inner = self.0.write().unwrap();
+        if size == 0 || inner.sanitizer.contains(address, size) {
+            return;
+        }
+        // SAFETY: guaranteed by the caller.
+        let region = unsafe { std::slice::from_raw_parts(address as *const u8, size) };
+        inner.sanitizer.register_alloc(region);
+        if writable {
+            inner.writable.register_alloc(region);
+        }
+    }
+
+    /// Register the `size` bytes at `address` as a native region, until they
+    /// are released with [`Foreign::release`].
+    ///
+    /// Returns whether the region was registered, which is not the case if
+    /// it is empty or was already registered.
+    ///
+    /// # Safety
+    ///
+    /// The region must be valid for reads until it is released, and valid for
+    /// writes as well if `writable` is set.
+    pub unsafe fn lend(&self, address: usize, size: usize, writable: bool) -> bool {
+        let mut inner = self.0.write().unwrap();
+        if size == 0 || inner.sanitizer.check_access(address, 1).is_ok() {
+            return false;
+        }
+        // SAFETY: guaranteed by the caller.
+        let region = unsafe { std::slice::from_raw_parts(address as *const u8, size) };
+        inner.sanitizer.register_alloc(region);
+        if writable {
+            inner.writable.register_alloc(region);
+        }
+        true
+    }
+
+    /// Unregister a region registered by [`Foreign::lend`].
+    pub fn release(&self, address: usize, size: usize) {
+        let mut inner = self.0.write().unwrap();
+        // SAFETY: the region is still valid, since the caller of `lend` only
+        // releases it before it becomes invalid.
+        let region = unsafe { std::slice::from_raw_parts(address as *const u8, size) };
+        let _ = inner.sanitizer.deregister_alloc(region);
+        let _ = inner.writable.deregister_alloc(region);
+    }
+
+    /// Whether the `size` bytes at `address` belong to a writable region.
+    pub fn is_writable(&self, address: usize, size: usize) -> bool {
+        self.0.read().unwrap().writable.contains(address, size)
+    }
+}
+
+// SAFETY: Regions are only registered if they are valid for the rest of the
+// process, and only writable regions can be written.
+unsafe impl MemorySegment for Foreign {
+    fn check_addr(&self, address: usize, size: usize) -> Result<(), MemoryAccessError> {
+        self.0.read().unwrap().sanitizer.check_access(address, size)
+    }
+
+    fn read_addr(&self, address: usize, size: usize) -> Result<Value, MemoryAccessError> {
+        self.check_addr(address, size)?;
+        // SAFETY: the sanitizer confirmed the range is within a registered
+        // region, which stays valid for the rest of the process.
+        Ok(Value::from_bytes(unsafe {
+            std::slice::from_raw_parts(address as *const u8, size)
+        }))
+    }
+
+    fn write_addr(&self, address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let inner = self.0.read().unwrap();
+        if inner.writable.check_access(address, data.len()).is_err() {
+            inner.sanitizer.check_access(address, data.len())?;
+            return Err(MemoryAccessError::ReadOnly);
+        }
+        // SAFETY: the sanitizer confirmed the range is within a writable region.
+        unsafe { std::ptr::copy(data.as_ptr(), address as *mut u8, data.len()) };
+        Ok(())
+    }
+}
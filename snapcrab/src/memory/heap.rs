@@ -1,30 +1,265 @@
-use crate::memory::sanitizer::MemorySanitizer;
+//! Heap memory management.
+//!
+//! Allocations requested by the interpreted program through the global
+//! allocator entry points (`__rust_alloc` and friends) are served from here.
+//! Each block is a real, zero-initialized process allocation, so its address
+//! can be handed to native code unchanged.
+
+use crate::memory::sanitizer::{AllocInfo, MemorySanitizer};
+use crate::memory::stack::Backtrace;
 use crate::memory::{MemoryAccessError, MemorySegment};
 use crate::value::Value;
+use anyhow::{Context, Result, bail};
+use std::alloc::Layout;
+use std::fmt;
 use std::sync::{Arc, RwLock};
 
 /// Thread safe heap modeling
-#[allow(unused)] // TODO: Remove-me once we actually use heap
 #[derive(Clone, Default)]
 pub struct Heap(Arc<RwLock<HeapImpl>>);
 
-/// Heap memory manager containing sanitizer and values
-#[allow(unused)]
+/// Heap memory manager.
+///
+/// The sanitizer records the layout and allocation site of every live block,
+/// as well as blocks that have been freed.
 #[derive(Default)]
 struct HeapImpl {
     sanitizer: MemorySanitizer,
-    values: Vec<Value>,
 }
 
-// SAFETY: Not yet implemented — all operations return NotFound.
+impl Heap {
+    /// Allocate a new zero-initialized block with the given size and alignment.
+    pub fn allocate(&self, size: usize, align: usize, site: Option<Backtrace>) -> Result<usize> {
+        self.0.write().unwrap().allocate(size, align, site)
+    }
+
+    /// Free a block previously returned by [`Heap::allocate`].
+    ///
+    /// Fails if the block was already freed, was never allocated, or if the
+    /// size and alignment don't match the ones used to allocate it.
+    pub fn deallocate(
+        &self,
+        address: usize,
+        size: usize,
+        align: usize,
+        site: Option<Backtrace>,
+    ) -> Result<()> {
+        self.0
+            .write()
+            .unwrap()
+            .deallocate(address, size, align, site)
+    }
+
+    /// Resize a block, preserving its contents up to the smaller of both sizes.
+    ///
+    /// The block is always moved to a fresh allocation, which makes stale
+    /// pointers to the old block detectable. The heap stays locked for the
+    /// whole operation, so no other thread can free the block meanwhile.
+    pub fn reallocate(
+        &self,
+        address: usize,
+        old_size: usize,
+        align: usize,
+        new_size: usize,
+        site: Option<Backtrace>,
+    ) -> Result<usize> {
+        let mut heap = self.0.write().unwrap();
+        heap.sanitizer.check_free(address, old_size, align)?;
+        let new_address = heap.allocate(new_size, align, site.clone())?;
+        // SAFETY: Both blocks are live heap allocations with at least the copied
+        // size, and the new block is fresh so they cannot overlap.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                address as *const u8,
+                new_address as *mut u8,
+                old_size.min(new_size),
+            )
+        };
+        heap.deallocate(address, old_size, align, site)?;
+        Ok(new_address)
+    }
+
+    /// Collect every block that is still allocated.
+    ///
+    /// Returns `None` if there are no live allocations.
+    pub fn leak_report(&self) -> Option<LeakReport> {
+        let heap = self.0.read().unwrap();
+        let leaks: Vec<_> = heap
+            .sanitizer
+            .live_allocs()
+            .map(|(address, info)| (address, info.clone()))
+            .collect();
+        (!leaks.is_empty()).then_some(LeakReport(leaks))
+    }
+}
+
+impl HeapImpl {
+    fn allocate(&mut self, size: usize, align: usize, site: Option<Backtrace>) -> Result<usize> {
+        let layout = make_layout(size, align)?;
+        if size == 0 {
+            bail!("Undefined behavior: zero-sized heap allocation");
+        }
+        // SAFETY: The layout has a non-zero size.
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            bail!("Heap allocation of {size} bytes (align {align}) failed");
+        }
+
+        // SAFETY: The block was just allocated with `layout.size()` bytes.
+        let buf = unsafe { std::slice::from_raw_parts(ptr, size) };
+        self.sanitizer.register_alloc_with_info(buf, align, site);
+        Ok(ptr as usize)
+    }
+
+    fn deallocate(
+        &mut self,
+        address: usize,
+        size: usize,
+        align: usize,
+        site: Option<Backtrace>,
+    ) -> Result<()> {
+        self.sanitizer.free_alloc(address, size, align, site)?;
+        let layout = make_layout(size, align)?;
+        // SAFETY: The block was live and was allocated by us with `layout`.
+        unsafe { std::alloc::dealloc(address as *mut u8, layout) };
+        Ok(())
+    }
+}
+
+/// Heap blocks that were never freed, ordered by address.
+#[derive(Debug)]
+pub struct LeakReport(Vec<(usize, AllocInfo)>);
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: usize = self.0.iter().map(|(_, info)| info.size).sum();
+        writeln!(
+            f,
+            "{} heap allocation(s) leaked, {total} byte(s) in total",
+            self.0.len()
+        )?;
+        for (address, info) in &self.0 {
+            writeln!(
+                f,
+                "leaked 0x{address:x} (size {}, align {}), allocated at:",
+                info.size, info.align
+            )?;
+            match &info.alloc_site {
+                Some(site) => write!(f, "{site}")?,
+                None => writeln!(f, "  <unknown>")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build a `Layout`, reporting invalid size/alignment pairs as an error.
+fn make_layout(size: usize, align: usize) -> Result<Layout> {
+    Layout::from_size_align(size, align)
+        .with_context(|| format!("Invalid heap layout: size {size}, alignment {align}"))
+}
+
+// SAFETY: Every block tracked by the sanitizer is a live allocation owned by
+// the heap, and all accesses are checked to fall within one of them.
 unsafe impl MemorySegment for Heap {
-    fn read_addr(&self, _address: usize, _size: usize) -> Result<&[u8], MemoryAccessError> {
-        tracing::error!("Heap memory access not yet supported");
-        Err(MemoryAccessError::NotFound)
+    fn check_addr(&self, address: usize, size: usize) -> Result<(), MemoryAccessError> {
+        self.0.read().unwrap().sanitizer.check_access(address, size)
+    }
+
+    fn read_addr(&self, address: usize, size: usize) -> Result<Value, MemoryAccessError> {
+        // The block cannot be freed while the heap is locked.
+        let heap = self.0.read().unwrap();
+        heap.sanitizer.check_access(address, size)?;
+        // SAFETY: check_access verified the range is within a live allocation.
+        Ok(Value::from_bytes(unsafe {
+            std::slice::from_raw_parts(address as *const u8, size)
+        }))
+    }
+
+    fn write_addr(&self, address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let heap = self.0.read().unwrap();
+        heap.sanitizer.check_access(address, data.len())?;
+        // SAFETY: check_access verified the range is within a live allocation.
+        unsafe { std::ptr::copy(data.as_ptr(), address as *mut u8, data.len()) };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_is_zeroed_and_accessible() {
+        let heap = Heap::default();
+        let address = heap.allocate(16, 8, None).unwrap();
+        assert!(address.is_multiple_of(8));
+        assert_eq!(heap.read_addr(address, 16).unwrap().as_bytes(), &[0u8; 16]);
+        heap.write_addr(address + 8, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            heap.read_addr(address + 8, 3).unwrap().as_bytes(),
+            &[1, 2, 3]
+        );
+        assert!(matches!(
+            heap.read_addr(address + 8, 9),
+            Err(MemoryAccessError::OutOfBounds)
+        ));
+        heap.deallocate(address, 16, 8, None).unwrap();
+    }
+
+    #[test]
+    fn test_deallocate_removes_block() {
+        let heap = Heap::default();
+        let address = heap.allocate(4, 4, None).unwrap();
+        heap.deallocate(address, 4, 4, None).unwrap();
+        assert!(matches!(
+            heap.read_addr(address, 4),
+            Err(MemoryAccessError::UseAfterFree(_))
+        ));
+        assert!(heap.deallocate(address, 4, 4, None).is_err());
+    }
+
+    #[test]
+    fn test_deallocate_mismatched_layout() {
+        let heap = Heap::default();
+        let address = heap.allocate(8, 8, None).unwrap();
+        assert!(heap.deallocate(address, 4, 8, None).is_err());
+        assert!(heap.deallocate(address, 8, 4, None).is_err());
+        heap.deallocate(address, 8, 8, None).unwrap();
+    }
+
+    #[test]
+    fn test_leak_report() {
+        let heap = Heap::default();
+        assert!(heap.leak_report().is_none());
+        let address = heap.allocate(12, 4, None).unwrap();
+        let report = heap.leak_report().unwrap().to_string();
+        assert!(report.starts_with("1 heap allocation(s) leaked, 12 byte(s) in total"));
+        assert!(report.contains(&format!("leaked 0x{address:x} (size 12, align 4)")));
+        heap.deallocate(address, 12, 4, None).unwrap();
+        assert!(heap.leak_report().is_none());
+    }
+
+    #[test]
+    fn test_heap_is_thread_safe() {
+        // Allocation sites are stored as resolved backtraces, so the heap can
+        // be shared by threads without any `unsafe impl`.
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Heap>();
     }
 
-    fn write_addr(&self, _address: usize, _data: &[u8]) -> Result<(), MemoryAccessError> {
-        tracing::error!("Heap memory access not yet supported");
-        Err(MemoryAccessError::NotFound)
+    #[test]
+    fn test_reallocate_preserves_prefix() {
+        let heap = Heap::default();
+        let address = heap.allocate(4, 1, None).unwrap();
+        heap.write_addr(address, &[1, 2, 3, 4]).unwrap();
+        let grown = heap.reallocate(address, 4, 1, 8, None).unwrap();
+        assert_eq!(
+            heap.read_addr(grown, 8).unwrap().as_bytes(),
+            &[1, 2, 3, 4, 0, 0, 0, 0]
+        );
+        let shrunk = heap.reallocate(grown, 8, 1, 2, None).unwrap();
+        assert_eq!(heap.read_addr(shrunk, 2).unwrap().as_bytes(), &[1, 2]);
+        heap.deallocate(shrunk, 2, 1, None).unwrap();
     }
 }
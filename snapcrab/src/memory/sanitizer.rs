@@ -3,9 +3,15 @@
 //! Provides a memory tracker that records allocated memory regions and validates
 //! memory access bounds. Ensures no overlapping allocations and efficient
 //! bounds checking for memory safety.
+//!
+//! Allocations can optionally be freed through [`MemorySanitizer::free_alloc`],
+//! in which case the sanitizer remembers the freed region so that later
+//! accesses or frees can be reported as use-after-free or double-free.
 use std::collections::BTreeMap;
+use std::fmt;
 
 use super::MemoryAccessError;
+use super::stack::Backtrace;
 
 /// Tracks memory allocations and validates memory access bounds.
 ///
@@ -13,8 +19,46 @@ use super::MemoryAccessError;
 /// Prevents overlapping allocations and provides efficient bounds checking.
 #[derive(Debug, Default)]
 pub struct MemorySanitizer {
-    /// Map from allocation start address to allocation size
-    allocations: BTreeMap<usize, usize>,
+    /// Map from allocation start address to allocation information
+    allocations: BTreeMap<usize, AllocInfo>,
+    /// Map from start address to regions released via `free_alloc`.
+    ///
+    /// Entries are dropped once the address range is reused by a new allocation.
+    freed: BTreeMap<usize, FreedAlloc>,
+}
+
+/// Information recorded for each allocation.
+#[derive(Clone, Debug)]
+pub struct AllocInfo {
+    pub size: usize,
+    pub align: usize,
+    /// Call stack that created the allocation, if known.
+    pub alloc_site: Option<Backtrace>,
+}
+
+/// A region that was released through [`MemorySanitizer::free_alloc`].
+#[derive(Clone, Debug)]
+pub struct FreedAlloc {
+    pub address: usize,
+    pub info: AllocInfo,
+    /// Call stack that freed the allocation, if known.
+    pub free_site: Option<Backtrace>,
+}
+
+/// Errors reported when releasing an allocation.
+#[derive(Debug)]
+pub enum FreeError {
+    /// The address was already freed.
+    DoubleFree(Box<FreedAlloc>),
+    /// The address is not the start of any known allocation.
+    InvalidFree { address: usize },
+    /// The size or alignment doesn't match the ones used to allocate.
+    MismatchedLayout {
+        address: usize,
+        size: usize,
+        align: usize,
+        info: AllocInfo,
+    },
 }
 
 impl MemorySanitizer {
@@ -23,6 +67,19 @@ impl MemorySanitizer {
     /// # Arguments
     /// * `buf` - The buffer to be registered
     pub fn register_alloc(&mut self, buf: &[u8]) {
+        self.register_alloc_with_info(buf, 1, None);
+    }
+
+    /// Records a new memory allocation with its alignment and allocation site.
+    ///
+    /// Any freed region overlapping with the new allocation is forgotten, since
+    /// the underlying allocator reused its addresses.
+    pub fn register_alloc_with_info(
+        &mut self,
+        buf: &[u8],
+        align: usize,
+        alloc_site: Option<Backtrace>,
+    ) {
         let size = buf.len();
         if size > 0 {
             let address = buf.as_ptr() as usize;
@@ -34,23 +91,82 @@ impl MemorySanitizer {
                 address,
                 size
             );
-            self.allocations.insert(address, size);
+            self.forget_freed(address, size);
+            self.allocations.insert(
+                address,
+                AllocInfo {
+                    size,
+                    align,
+                    alloc_site,
+                },
+            );
         }
     }
 
     /// Removes a memory allocation record.
     ///
+    /// The region is not remembered as freed.
+    ///
     /// # Arguments
     /// * `buf`: The buffer to deregister
-    pub fn deregister_alloc(&mut self, buf: &[u8]) {
+    pub fn deregister_alloc(&mut self, buf: &[u8]) -> Result<(), FreeError> {
         if !buf.is_empty() {
             let address = buf.as_ptr() as usize;
             if self.allocations.remove(&address).is_none() {
-                // This shouldn't happen unless there is a bug which compromises
-                // safety. So, kaboom!
-                panic!("No allocation found at address 0x{:x}", address);
+                return Err(FreeError::InvalidFree { address });
             }
         }
+        Ok(())
+    }
+
+    /// Check that `address` is a live allocation with the given layout.
+    pub fn check_free(&self, address: usize, size: usize, align: usize) -> Result<(), FreeError> {
+        let Some(info) = self.allocations.get(&address) else {
+            return Err(match self.freed.get(&address) {
+                Some(freed) => FreeError::DoubleFree(Box::new(freed.clone())),
+                None => FreeError::InvalidFree { address },
+            });
+        };
+        if info.size != size || info.align != align {
+            return Err(FreeError::MismatchedLayout {
+                address,
+                size,
+                align,
+                info: info.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Releases an allocation, checking it against its recorded layout.
+    ///
+    /// The region is remembered as freed, so any later access through
+    /// [`MemorySanitizer::check_access`] or a second free is reported.
+    pub fn free_alloc(
+        &mut self,
+        address: usize,
+        size: usize,
+        align: usize,
+        free_site: Option<Backtrace>,
+    ) -> Result<(), FreeError> {
+        self.check_free(address, size, align)?;
+        let info = self.allocations.remove(&address).unwrap();
+        self.freed.insert(
+            address,
+            FreedAlloc {
+                address,
+                info,
+                free_site,
+            },
+        );
+        Ok(())
+    }
+
+    /// Iterate over all live allocations as `(address, info)` pairs.
+    pub fn live_allocs(&self) -> impl Iterator<Item = (usize, &AllocInfo)> {
+        self.allocations
+            .iter()
+            .map(|(&address, info)| (address, info))
     }
 
     /// Checks if a memory range is entirely contained within a single allocation.
@@ -58,23 +174,25 @@ impl MemorySanitizer {
         self.check_access(address, size).is_ok()
     }
 
-    /// Check whether an address range is valid, partially valid, or not found.
+    /// Check whether an address range is valid, partially valid, freed, or not found.
     pub fn check_access(&self, address: usize, size: usize) -> Result<(), MemoryAccessError> {
-        if let Some((&start, &alloc_size)) = self.allocations.range(..=address).next_back() {
-            if address >= start && address < start + alloc_size {
-                let request_end = address + size;
-                let alloc_end = start + alloc_size;
-                if request_end <= alloc_end {
-                    Ok(())
-                } else {
-                    Err(MemoryAccessError::OutOfBounds)
-                }
+        if let Some((&start, info)) = self.allocations.range(..=address).next_back()
+            && address < start + info.size
+        {
+            let request_end = address + size;
+            let alloc_end = start + info.size;
+            return if request_end <= alloc_end {
+                Ok(())
             } else {
-                Err(MemoryAccessError::NotFound)
-            }
-        } else {
-            Err(MemoryAccessError::NotFound)
+                Err(MemoryAccessError::OutOfBounds)
+            };
+        }
+        if let Some((&start, freed)) = self.freed.range(..=address).next_back()
+            && address < start + freed.info.size
+        {
+            return Err(MemoryAccessError::UseAfterFree(Box::new(freed.clone())));
         }
+        Err(MemoryAccessError::NotFound)
     }
 
     /// Checks if a proposed allocation would overlap with existing allocations.
@@ -89,19 +207,77 @@ impl MemorySanitizer {
     fn has_overlap(&self, address: usize, size: usize) -> bool {
         let end = address + size;
 
-        // Check all allocations that could potentially overlap
-        for (&start, &alloc_size) in &self.allocations {
-            let alloc_end = start + alloc_size;
+        // Only the last allocation starting before `end` can overlap, since
+        // allocations never overlap each other.
+        self.allocations
+            .range(..end)
+            .next_back()
+            .is_some_and(|(&start, info)| address < start + info.size)
+    }
 
-            // Two ranges [a1,a2) and [b1,b2) overlap if: a1 < b2 && b1 < a2
-            if address < alloc_end && start < end {
-                return true;
+    /// Drop freed records that overlap `[address, address + size)`.
+    fn forget_freed(&mut self, address: usize, size: usize) {
+        let end = address + size;
+        while let Some((&start, freed)) = self.freed.range(..end).next_back()
+            && address < start + freed.info.size
+        {
+            self.freed.remove(&start);
+        }
+    }
+}
+
+impl fmt::Display for FreedAlloc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "heap block 0x{:x} (size {}, align {}) was allocated at {} and freed at {}",
+            self.address,
+            self.info.size,
+            self.info.align,
+            site_diagnostic(&self.info.alloc_site),
+            site_diagnostic(&self.free_site),
+        )
+    }
+}
+
+impl fmt::Display for FreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FreeError::DoubleFree(freed) => {
+                write!(f, "Undefined behavior: double free; {freed}")
             }
+            FreeError::InvalidFree { address } => {
+                write!(
+                    f,
+                    "Undefined behavior: freeing 0x{address:x}, which is not the start \
+                     of a heap allocation"
+                )
+            }
+            FreeError::MismatchedLayout {
+                address,
+                size,
+                align,
+                info,
+            } => write!(
+                f,
+                "Undefined behavior: freeing heap block 0x{address:x} with size {size} and \
+                 alignment {align}, but it was allocated at {} with size {} and alignment {}",
+                site_diagnostic(&info.alloc_site),
+                info.size,
+                info.align
+            ),
         }
-        false
     }
 }
 
+impl std::error::Error for FreeError {}
+
+/// Render the call site of an optional backtrace.
+fn site_diagnostic(site: &Option<Backtrace>) -> String {
+    site.as_ref()
+        .map_or_else(|| "<unknown>".to_string(), Backtrace::call_site_diagnostic)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,12 +327,11 @@ mod tests {
         let base_addr = slice.as_ptr() as usize;
         assert!(tracker.contains(base_addr + 25, 10));
 
-        tracker.deregister_alloc(slice);
+        tracker.deregister_alloc(slice).unwrap();
         assert!(!tracker.contains(base_addr + 25, 10));
     }
 
     #[test]
-    #[should_panic(expected = "No allocation found at address")]
     fn test_deallocate_untracked() {
         let mut tracker = MemorySanitizer::default();
 
@@ -164,6 +339,58 @@ mod tests {
         let slice = &buffer[100..150]; // 50 bytes
 
         // Try to deregister a buffer that was never registered
-        tracker.deregister_alloc(slice);
+        assert!(matches!(
+            tracker.deregister_alloc(slice),
+            Err(FreeError::InvalidFree { .. })
+        ));
+    }
+
+    #[test]
+    fn test_free_remembers_region() {
+        let mut tracker = MemorySanitizer::default();
+
+        let buffer = vec![0u8; 1000];
+        let slice = &buffer[100..150];
+        tracker.register_alloc_with_info(slice, 2, None);
+
+        let base_addr = slice.as_ptr() as usize;
+        tracker.free_alloc(base_addr, 50, 2, None).unwrap();
+        assert!(matches!(
+            tracker.check_access(base_addr + 10, 4),
+            Err(MemoryAccessError::UseAfterFree(_))
+        ));
+        assert!(matches!(
+            tracker.free_alloc(base_addr, 50, 2, None),
+            Err(FreeError::DoubleFree(_))
+        ));
+
+        // Reusing the addresses forgets the freed region.
+        tracker.register_alloc(&buffer[90..110]);
+        assert!(tracker.contains(base_addr, 10));
+        assert!(matches!(
+            tracker.check_access(base_addr + 30, 4),
+            Err(MemoryAccessError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_free_mismatched_layout() {
+        let mut tracker = MemorySanitizer::default();
+
+        let buffer = vec![0u8; 1000];
+        let slice = &buffer[100..150];
+        tracker.register_alloc_with_info(slice, 2, None);
+
+        let base_addr = slice.as_ptr() as usize;
+        assert!(matches!(
+            tracker.free_alloc(base_addr, 40, 2, None),
+            Err(FreeError::MismatchedLayout { .. })
+        ));
+        assert!(matches!(
+            tracker.free_alloc(base_addr, 50, 4, None),
+            Err(FreeError::MismatchedLayout { .. })
+        ));
+        assert!(tracker.contains(base_addr, 50));
+        tracker.free_alloc(base_addr, 50, 2, None).unwrap();
     }
 }
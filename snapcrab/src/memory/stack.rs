@@ -20,12 +20,20 @@ use crate::value::Value;
 use anyhow::Result;
 use rustc_public::mir::Body;
 use rustc_public::mir::mono::Instance;
-use rustc_public::ty::Ty;
+use rustc_public::ty::{Span, Ty};
+use rustc_public::{CrateDef, local_crate};
+use std::cell::OnceCell;
+use std::fmt;
+use std::sync::{Arc, RwLock};
 
 /// Stack memory manager containing sanitizer and stack frames
+///
+/// The sanitizer is shared by the stacks of all threads, since a thread may
+/// access the stack of another thread, e.g., a scoped thread borrowing a local
+/// of its parent.
 #[derive(Default, Debug)]
 pub struct Stack {
-    sanitizer: MemorySanitizer,
+    sanitizer: Arc<RwLock<MemorySanitizer>>,
     frames: Vec<StackFrame>,
 }
 
@@ -33,14 +41,25 @@ pub struct Stack {
 // stable for the frame's lifetime. The sanitizer tracks these allocations and
 // validates that all accesses fall within registered ranges.
 unsafe impl MemorySegment for Stack {
-    fn read_addr(&self, address: usize, size: usize) -> Result<&[u8], MemoryAccessError> {
-        self.sanitizer.check_access(address, size)?;
+    fn check_addr(&self, address: usize, size: usize) -> Result<(), MemoryAccessError> {
+        self.sanitizer.read().unwrap().check_access(address, size)
+    }
+
+    fn read_addr(&self, address: usize, size: usize) -> Result<Value, MemoryAccessError> {
+        // The frame cannot be popped while the sanitizer is locked.
+        let sanitizer = self.sanitizer.read().unwrap();
+        sanitizer.check_access(address, size)?;
         // SAFETY: check_access verified the range is within a live allocation.
-        Ok(unsafe { std::slice::from_raw_parts(address as *const u8, size) })
+        Ok(Value::from_bytes(unsafe {
+            std::slice::from_raw_parts(address as *const u8, size)
+        }))
     }
 
     fn write_addr(&self, address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
-        self.sanitizer.check_access(address, data.len())?;
+        self.sanitizer
+            .read()
+            .unwrap()
+            .check_access(address, data.len())?;
         // SAFETY: check_access verified the range is within a live allocation.
         unsafe { std::ptr::copy(data.as_ptr(), address as *mut u8, data.len()) };
         Ok(())
@@ -48,19 +67,36 @@ unsafe impl MemorySegment for Stack {
 }
 
 impl Stack {
+    /// Create an empty stack for a new thread.
+    pub fn new_thread(&self) -> Self {
+        Self {
+            sanitizer: self.sanitizer.clone(),
+            frames: Vec::new(),
+        }
+    }
+
     /// Runs a method with their own stack frame.
     ///
     /// This ensures that the stack frame is allocated just for the duration
     /// of the execution, and sanitizer is kept up-to-date.
-    pub fn with_stack_frame<F, R>(instance: Instance, memory: &mut ThreadMemory, func: F) -> R
+    pub fn with_stack_frame<F, R>(
+        instance: Instance,
+        body: Body,
+        memory: &mut ThreadMemory,
+        func: F,
+    ) -> R
     where
         F: FnOnce(&Body, &mut ThreadMemory) -> R,
     {
         // Create frame and register in the sanitizer.
-        let body = instance.body().expect("Caller should ensure body exists");
-        let frame = StackFrame::new(&body);
+        let frame = StackFrame::new(instance, &body);
         let address = frame.data.as_ptr();
-        memory.stack.sanitizer.register_alloc(&frame.data);
+        memory
+            .stack
+            .sanitizer
+            .write()
+            .unwrap()
+            .register_alloc(&frame.data);
         memory.stack.frames.push(frame);
 
         // Call function.
@@ -73,7 +109,13 @@ impl Stack {
             address as usize,
             "Unexpected stack frame"
         );
-        memory.stack.sanitizer.deregister_alloc(data);
+        memory
+            .stack
+            .sanitizer
+            .write()
+            .unwrap()
+            .deregister_alloc(data)
+            .expect("Stack frame should be registered");
         memory.stack.frames.pop();
 
         // Return actual result.
@@ -85,6 +127,38 @@ impl Stack {
         self.frames.last().unwrap().read_local(local, local_ty)
     }
 
+    /// Record the span of the call the current frame is about to make.
+    pub fn set_call_site(&mut self, span: Span) {
+        if let Some(frame) = self.frames.last_mut()
+            && frame.call_site != Some(span)
+        {
+            frame.call_site = Some(span);
+            frame.resolved = OnceCell::new();
+        }
+    }
+
+    /// Take a snapshot of the current call stack.
+    ///
+    /// Frames are only resolved the first time they are captured at a given
+    /// call site, and snapshots share them.
+    pub fn backtrace(&self) -> Backtrace {
+        Backtrace(
+            self.frames
+                .iter()
+                .rev()
+                .map(|frame| frame.resolved.get_or_init(|| frame.resolve()).clone())
+                .collect(),
+        )
+    }
+
+    /// The function and call site of every frame, innermost first.
+    pub fn call_stack(&self) -> impl Iterator<Item = (Instance, Option<Span>)> + '_ {
+        self.frames
+            .iter()
+            .rev()
+            .map(|frame| (frame.instance, frame.call_site))
+    }
+
     pub fn write_local(&mut self, local: usize, value: Value) -> Result<()> {
         self.frames.last_mut().unwrap().write_local(local, value)
     }
@@ -104,6 +178,12 @@ pub struct StackFrame {
     data: Box<[u8]>,
     /// Maps local to the data[offset].
     offsets: Vec<usize>,
+    /// The function executing in this frame.
+    instance: Instance,
+    /// Span of the last call made from this frame.
+    call_site: Option<Span>,
+    /// This frame resolved for backtraces, at its current call site.
+    resolved: OnceCell<Arc<BacktraceFrame>>,
 }
 
 impl StackFrame {
@@ -111,7 +191,7 @@ impl StackFrame {
     ///
     /// Calculates the total size needed for all local variables and allocates
     /// a contiguous block of memory to store them.
-    pub fn new(body: &Body) -> Self {
+    pub fn new(instance: Instance, body: &Body) -> Self {
         let mut offsets = Vec::new();
         let mut current_offset = 0;
 
@@ -134,7 +214,22 @@ impl StackFrame {
 
         let data = vec![0; current_offset].into_boxed_slice();
 
-        Self { data, offsets }
+        Self {
+            data,
+            offsets,
+            instance,
+            call_site: None,
+            resolved: OnceCell::new(),
+        }
+    }
+
+    /// Resolve the function and call site of this frame for a backtrace.
+    fn resolve(&self) -> Arc<BacktraceFrame> {
+        Arc::new(BacktraceFrame {
+            function: self.instance.name(),
+            location: self.call_site.map(|span| span.diagnostic()),
+            local: self.instance.def.krate() == local_crate(),
+        })
     }
 
     /// Sets a local variable to the given value
@@ -182,3 +277,60 @@ impl StackFrame {
         Ok(self.data.as_ptr() as usize + offset)
     }
 }
+
+/// A snapshot of the interpreted call stack, innermost frame first.
+///
+/// Frames are resolved when the snapshot is taken, so it can outlive the
+/// compiler session and be shared by threads.
+#[derive(Clone, Debug, Default)]
+pub struct Backtrace(Vec<Arc<BacktraceFrame>>);
+
+/// One frame of a [`Backtrace`].
+#[derive(Debug)]
+pub struct BacktraceFrame {
+    /// Name of the function executing in this frame.
+    pub function: String,
+    /// Location of the call this frame was executing when the snapshot was
+    /// taken.
+    pub location: Option<String>,
+    /// Whether the function belongs to the local crate.
+    pub local: bool,
+}
+
+impl Backtrace {
+    /// All frames, innermost first.
+    pub fn frames(&self) -> impl Iterator<Item = &BacktraceFrame> {
+        self.0.iter().map(Arc::as_ref)
+    }
+
+    /// The call site that best identifies this backtrace for diagnostics.
+    ///
+    /// That is the innermost call made from the local crate, since frames
+    /// from the standard library rarely point at the user's mistake.
+    /// Falls back to the innermost call if no local frame exists.
+    pub fn call_site(&self) -> Option<&str> {
+        self.frames()
+            .find(|frame| frame.local && frame.location.is_some())
+            .or_else(|| self.frames().find(|frame| frame.location.is_some()))
+            .and_then(|frame| frame.location.as_deref())
+    }
+
+    /// Render the call site as a diagnostic location.
+    pub fn call_site_diagnostic(&self) -> String {
+        self.call_site().unwrap_or("<unknown>").to_string()
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, frame) in self.frames().enumerate() {
+            writeln!(f, "  {idx}: at {}", frame.function)?;
+            writeln!(
+                f,
+                "      {}",
+                frame.location.as_deref().unwrap_or("<unknown>")
+            )?;
+        }
+        Ok(())
+    }
+}
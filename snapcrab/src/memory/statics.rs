@@ -3,25 +3,41 @@
 //! Materializes compiler-known allocations (string literals, const statics, vtables)
 //! into real process memory so that pointers to them are valid addresses.
 //!
-//! Uses interior mutability (`RefCell`) because constant evaluation needs to
-//! materialize allocations lazily while the interpreter holds shared references.
+//! Uses interior mutability because constant evaluation needs to materialize
+//! allocations lazily while the interpreter holds shared references.
+//! Like the heap, the statics are shared by every thread of a run, so that
+//! mutable statics have a single copy.
+//!
+//! Mutable statics of other crates that also exist in native code are not
+//! materialized. They are aliased to the native symbol instead, which is
+//! tracked by the [`Foreign`] segment.
 
-use crate::interpreter::native;
+use crate::interpreter::native::compile_callback;
+use crate::interpreter::native::jit::JitEngine;
+use crate::memory::foreign::Foreign;
 use crate::memory::sanitizer::MemorySanitizer;
 use crate::memory::{MemoryAccessError, MemorySegment};
-use crate::ty::contains_mutable_ptr;
+use crate::ty::{MonoType, contains_mutable_ptr};
+use crate::value::Value;
+use anyhow::Context;
 use rustc_public::mir::Mutability;
 use rustc_public::mir::alloc::{AllocId, GlobalAlloc};
 use rustc_public::mir::mono::{Instance, StaticDef};
+use rustc_public::ty::{Binder, ExistentialTraitRef, Ty};
 use rustc_public::{CrateDef, local_crate};
 use rustc_public_bridge::IndexedVal;
-use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
 
 /// Manages static/global allocations materialized from the compiler.
-#[derive(Default)]
+#[derive(Clone)]
 pub struct Statics {
-    inner: RefCell<StaticsInner>,
+    inner: Arc<RwLock<StaticsInner>>,
+    /// Used to look up the native copy of statics.
+    jit: JitEngine,
+    /// Native statics that the interpreter aliases.
+    foreign: Foreign,
 }
 
 #[derive(Default)]
@@ -34,22 +50,41 @@ struct StaticsInner {
     allocations: Vec<Box<[u8]>>,
     /// Maps AllocId index to the index in `allocations`.
     alloc_map: HashMap<usize, usize>,
+    /// Maps AllocId index to the address of the native static it aliases.
+    native_statics: HashMap<usize, usize>,
     /// Tracks which addresses belong to us for bounds checking.
     sanitizer: MemorySanitizer,
+    /// Tracks the allocations that the program may write to, i.e., `static mut`
+    /// items and statics with interior mutability.
+    writable: MemorySanitizer,
+    /// Functions that a function pointer was created for, by address.
+    fn_ptrs: HashMap<usize, Instance>,
+    /// Reverse map of `fn_ptrs`, so each function has a single address.
+    fn_addrs: HashMap<Instance, usize>,
+    /// The function of each callback, by callback id.
+    callbacks: Vec<Instance>,
+    /// The concrete type behind each materialized vtable, by address.
+    vtable_tys: HashMap<usize, Ty>,
 }
 
 impl Statics {
+    /// Create the statics of a run. Aliased native statics are registered in `foreign`.
+    pub fn new(jit: JitEngine, foreign: Foreign) -> Self {
+        Self {
+            inner: Default::default(),
+            jit,
+            foreign,
+        }
+    }
+
     /// Resolve an AllocId to a real memory address.
     ///
     /// Materializes the allocation on first access, recursively resolving
     /// nested provenance (e.g., a `&str` constant pointing to string bytes).
     pub fn resolve_alloc(&self, alloc_id: AllocId) -> anyhow::Result<usize> {
         let id_idx = alloc_id.to_index();
-        {
-            let inner = self.inner.borrow();
-            if let Some(&alloc_idx) = inner.alloc_map.get(&id_idx) {
-                return Ok(inner.allocations[alloc_idx].as_ptr() as usize);
-            }
+        if let Some(addr) = self.inner.read().unwrap().alloc_addr(id_idx) {
+            return Ok(addr);
         }
 
         let global = GlobalAlloc::from(alloc_id);
@@ -61,92 +96,224 @@ impl Statics {
                     .eval_initializer()
                     .map_err(|e| anyhow::anyhow!("failed to evaluate static `{name}`: {e}"))?;
                 let is_mutable = alloc.mutability == Mutability::Mut;
-                if is_mutable || contains_mutable_ptr(def.ty()) {
-                    check_static_not_duplicated(def)?;
+                if (is_mutable || contains_mutable_ptr(def.ty()))
+                    && let Some(addr) = self.alias_native_static(def, is_mutable)?
+                {
+                    let mut inner = self.inner.write().unwrap();
+                    inner.native_statics.insert(id_idx, addr);
+                    return Ok(addr);
                 }
                 Ok(self.materialize_alloc(alloc_id, &alloc))
             }
-            GlobalAlloc::Function(_) | GlobalAlloc::VTable(..) | GlobalAlloc::TypeId { .. } => {
-                Ok(0)
-            }
+            GlobalAlloc::Function(instance) => Ok(self.fn_ptr(instance)),
+            GlobalAlloc::VTable(ty, trait_ref) => self.vtable(ty, trait_ref),
+            // `TypeId` pointers carry the type hash in their offset, so their
+            // base address is zero, like in codegen.
+            GlobalAlloc::TypeId { .. } => Ok(0),
         }
     }
 
     /// Materialize an allocation into real memory, resolving nested provenance.
+    ///
+    /// Allocations that the compiler interned as mutable are writable.
     fn materialize_alloc(&self, alloc_id: AllocId, alloc: &rustc_public::ty::Allocation) -> usize {
         let id_idx = alloc_id.to_index();
 
-        let bytes = match alloc.raw_bytes() {
-            Ok(b) => b,
-            Err(_) => vec![0; alloc.bytes.len()],
-        };
-
-        let mut buf = bytes;
+        // Uninitialized bytes, e.g. an empty `MaybeUninit`, read as zero.
+        let mut buf: Vec<u8> = alloc.bytes.iter().map(|b| b.unwrap_or(0)).collect();
 
         // Resolve provenance: patch pointer-sized segments with real addresses.
         let ptr_size = crate::memory::pointer_width();
         for (offset, prov) in &alloc.provenance.ptrs {
-            // Nested provenance (e.g., &str pointing to string bytes) cannot
-            // be a duplicated mutable static, so unwrap is safe here.
+            // The initializer of nested statics was already evaluated by
+            // the compiler, so resolving them cannot fail.
             let target_addr = self
                 .resolve_alloc(prov.0)
                 .expect("nested provenance resolution");
-            let addr_bytes = target_addr.to_le_bytes();
-            buf[*offset..*offset + ptr_size].copy_from_slice(&addr_bytes[..ptr_size]);
+            relocate_ptr(&mut buf[*offset..*offset + ptr_size], target_addr);
+        }
+
+        let mut inner = self.inner.write().unwrap();
+        // Another thread may have materialized the allocation in the meantime.
+        if let Some(addr) = inner.alloc_addr(id_idx) {
+            return addr;
+        }
+        let (addr, alloc_idx) = inner.store(buf);
+        inner.alloc_map.insert(id_idx, alloc_idx);
+        if alloc.mutability == Mutability::Mut {
+            let alloc = &inner.allocations[alloc_idx];
+            // SAFETY: Box heap pointer remains stable, see `StaticsInner::store`.
+            let slice = unsafe { std::slice::from_raw_parts(alloc.as_ptr(), alloc.len()) };
+            inner.writable.register_alloc(slice);
         }
+        addr
+    }
+
+    /// Find the native copy of a static that allows mutation, if it has one.
+    ///
+    /// If both the interpreter and native code had their own copy, mutations
+    /// from one side would be invisible to the other. Statics of the local
+    /// crate only exist in the interpreter.
+    fn alias_native_static(&self, def: StaticDef, writable: bool) -> anyhow::Result<Option<usize>> {
+        if def.krate() == local_crate() {
+            return Ok(None);
+        }
+        let instance = Instance::from(def);
+        let Ok(symbol) = self.jit.resolve_symbol(instance.mangled_name().as_str()) else {
+            return Ok(None);
+        };
+        let address = symbol as usize;
+        let size = def.ty().size()?;
+        // SAFETY: the symbol of a static is valid for the rest of the process,
+        // and it is writable if the static is mutable.
+        unsafe { self.foreign.register(address, size, writable) };
+        Ok(Some(address))
+    }
 
+    /// Return the address of a function pointer to `instance`.
+    ///
+    /// Interpreted functions don't have machine code, so the address is a
+    /// callback that runs `instance` when native code calls it. If the ABI of
+    /// `instance` isn't supported by callbacks, a unique placeholder
+    /// allocation is used instead. Either way, the address maps back to the
+    /// function with [`Statics::fn_ptr_instance`].
+    pub fn fn_ptr(&self, instance: Instance) -> usize {
+        let mut inner = self.inner.write().unwrap();
+        if let Some(&addr) = inner.fn_addrs.get(&instance) {
+            return addr;
+        }
+        let id = inner.callbacks.len();
+        let addr = match compile_callback(&self.jit, instance, id) {
+            Ok(addr) => {
+                inner.callbacks.push(instance);
+                addr
+            }
+            Err(error) => {
+                debug!("No callback for `{}`: {error}", instance.name());
+                inner.store(vec![0]).0
+            }
+        };
+        inner.fn_ptrs.insert(addr, instance);
+        inner.fn_addrs.insert(instance, addr);
+        addr
+    }
+
+    /// Find the function of the callback `id`.
+    pub fn callback_instance(&self, id: usize) -> Option<Instance> {
+        self.inner.read().unwrap().callbacks.get(id).copied()
+    }
+
+    /// Find the function a function pointer points to.
+    pub fn fn_ptr_instance(&self, address: usize) -> Option<Instance> {
+        self.inner.read().unwrap().fn_ptrs.get(&address).copied()
+    }
+
+    /// Return the address of the vtable of `ty` for the trait object with
+    /// the given principal trait.
+    ///
+    /// The vtable is built by the compiler. Its drop glue and method entries
+    /// are function pointer allocations, which resolve to [`Statics::fn_ptr`].
+    pub fn vtable(
+        &self,
+        ty: Ty,
+        trait_ref: Option<Binder<ExistentialTraitRef>>,
+    ) -> anyhow::Result<usize> {
+        let alloc_id = GlobalAlloc::VTable(ty, trait_ref)
+            .vtable_allocation()
+            .with_context(|| format!("Failed to build the vtable of `{ty}`"))?;
+        let addr = self.resolve_alloc(alloc_id)?;
+        self.inner.write().unwrap().vtable_tys.insert(addr, ty);
+        Ok(addr)
+    }
+
+    /// Find the concrete type of a vtable created by [`Statics::vtable`].
+    pub fn vtable_ty(&self, address: usize) -> Option<Ty> {
+        self.inner.read().unwrap().vtable_tys.get(&address).copied()
+    }
+
+    /// Store bytes created by the interpreter itself as a new read-only allocation.
+    ///
+    /// This is used for data that must live until the end of the program,
+    /// such as the `&'static str` payload of a caught panic.
+    pub fn intern_bytes(&self, bytes: &[u8]) -> usize {
+        self.inner.write().unwrap().store(bytes.to_vec()).0
+    }
+
+    /// Whether the `size` bytes at `address` belong to a writable static.
+    pub fn is_writable(&self, address: usize, size: usize) -> bool {
+        self.inner.read().unwrap().writable.contains(address, size)
+    }
+}
+
+impl StaticsInner {
+    /// The address of an allocation that was already materialized.
+    fn alloc_addr(&self, id_idx: usize) -> Option<usize> {
+        if let Some(&addr) = self.native_statics.get(&id_idx) {
+            return Some(addr);
+        }
+        let alloc_idx = *self.alloc_map.get(&id_idx)?;
+        Some(self.allocations[alloc_idx].as_ptr() as usize)
+    }
+
+    /// Move `buf` into stable storage and return its address and index.
+    fn store(&mut self, buf: Vec<u8>) -> (usize, usize) {
         let boxed: Box<[u8]> = buf.into_boxed_slice();
         let addr = boxed.as_ptr() as usize;
         let len = boxed.len();
 
-        let mut inner = self.inner.borrow_mut();
-        let alloc_idx = inner.allocations.len();
-        inner.allocations.push(boxed);
+        let alloc_idx = self.allocations.len();
+        self.allocations.push(boxed);
         // SAFETY: Box heap pointer remains stable after push (only the Box struct moves).
         let slice = unsafe { std::slice::from_raw_parts(addr as *const u8, len) };
-        inner.sanitizer.register_alloc(slice);
-        inner.alloc_map.insert(id_idx, alloc_idx);
+        self.sanitizer.register_alloc(slice);
 
-        addr
+        (addr, alloc_idx)
     }
 }
 
-/// Bail if an external static with interior mutability also exists as a
-/// native symbol.
+/// Patch a pointer with provenance to point into the allocation at `base`.
 ///
-/// If both the interpreter and native code have their own copy, mutations
-/// from one side are invisible to the other.
-fn check_static_not_duplicated(def: StaticDef) -> anyhow::Result<()> {
-    if def.krate() == local_crate() {
-        return Ok(());
-    }
-    let instance = Instance::from(def);
-    let mangled = instance.mangled_name();
-    if native::resolve_symbol(mangled.as_str()).is_some() {
-        anyhow::bail!(
-            "unsupported: static `{}` allows mutation and is duplicated in \
-             native code — mutations from one side would be invisible to \
-             the other",
-            instance.name()
-        );
-    }
-    Ok(())
+/// The bytes of a pointer with provenance hold its offset into the target
+/// allocation, which is preserved.
+pub(super) fn relocate_ptr(ptr_bytes: &mut [u8], base: usize) {
+    let offset = crate::value::uint_from_bytes(ptr_bytes) as usize;
+    let address = base.wrapping_add(offset).to_le_bytes();
+    ptr_bytes.copy_from_slice(&address[..ptr_bytes.len()]);
 }
 
 // SAFETY: Allocations are stored in Box<[u8]> that are never moved or reallocated
-// after creation. The sanitizer tracks their addresses for bounds checking.
+// after creation. The sanitizer tracks their addresses for bounds checking, and
+// only allocations that the compiler interned as mutable can be written.
 unsafe impl MemorySegment for Statics {
-    fn read_addr(&self, address: usize, size: usize) -> Result<&[u8], MemoryAccessError> {
-        let inner = self.inner.borrow();
+    fn check_addr(&self, address: usize, size: usize) -> Result<(), MemoryAccessError> {
+        let inner = self.inner.read().unwrap();
         if !inner.sanitizer.contains(address, size) {
             return Err(MemoryAccessError::NotFound);
         }
-        let ptr = address as *const u8;
-        // SAFETY: sanitizer confirmed the range is within a live allocation.
-        Ok(unsafe { std::slice::from_raw_parts(ptr, size) })
+        Ok(())
     }
 
-    fn write_addr(&self, _address: usize, _data: &[u8]) -> Result<(), MemoryAccessError> {
-        Err(MemoryAccessError::OutOfBounds)
+    fn read_addr(&self, address: usize, size: usize) -> Result<Value, MemoryAccessError> {
+        let inner = self.inner.read().unwrap();
+        if !inner.sanitizer.contains(address, size) {
+            return Err(MemoryAccessError::NotFound);
+        }
+        // SAFETY: sanitizer confirmed the range is within a live allocation,
+        // which is copied before the lock is released.
+        Ok(Value::from_bytes(unsafe {
+            std::slice::from_raw_parts(address as *const u8, size)
+        }))
+    }
+
+    fn write_addr(&self, address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let inner = self.inner.read().unwrap();
+        if inner.writable.check_access(address, data.len()).is_err() {
+            // Read-only allocations are only tracked by `sanitizer`.
+            inner.sanitizer.check_access(address, data.len())?;
+            return Err(MemoryAccessError::ReadOnly);
+        }
+        // SAFETY: the writable sanitizer confirmed the range is within a mutable allocation.
+        unsafe { std::ptr::copy(data.as_ptr(), address as *mut u8, data.len()) };
+        Ok(())
     }
 }
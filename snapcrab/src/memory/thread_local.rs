@@ -0,0 +1,66 @@
+//! Thread-local statics.
+//!
+//! Each thread has its own copy of every thread-local static that it accesses.
+//! The copy is a heap block, which is initialized from the static's initializer
+//! on first access and freed when the thread exits. Pointers to it that outlive
+//! the thread are dangling, just like in compiled code.
+//!
+//! Using the heap keeps thread-local storage reachable by other threads, e.g.
+//! through a reference passed to a scoped thread.
+
+use rustc_public::mir::mono::{Instance, StaticDef};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// The thread-local statics of a thread and their destructors.
+#[derive(Default)]
+pub struct ThreadLocals {
+    /// The storage of each thread-local static accessed by the thread.
+    ///
+    /// Thread-local references are created while evaluating an rvalue, which
+    /// only has shared access to the memory.
+    storage: RefCell<HashMap<StaticDef, TlsBlock>>,
+    /// Destructors registered by the thread, as `(data, dtor)` pairs in
+    /// registration order.
+    dtors: Vec<(usize, Instance)>,
+}
+
+/// The heap block holding the value of a thread-local static.
+#[derive(Clone, Copy, Debug)]
+pub struct TlsBlock {
+    pub address: usize,
+    pub size: usize,
+    pub align: usize,
+}
+
+impl ThreadLocals {
+    /// The storage of `def`, if the thread already accessed it.
+    pub fn get(&self, def: StaticDef) -> Option<TlsBlock> {
+        self.storage.borrow().get(&def).copied()
+    }
+
+    /// Record the storage of `def`.
+    pub fn insert(&self, def: StaticDef, block: TlsBlock) {
+        self.storage.borrow_mut().insert(def, block);
+    }
+
+    /// Remove the storage of every thread-local static.
+    pub fn take_storage(&mut self) -> Vec<TlsBlock> {
+        self.storage
+            .get_mut()
+            .drain()
+            .map(|(_, block)| block)
+            .collect()
+    }
+
+    /// Register `dtor` to be called with `data` when the thread exits.
+    pub fn register_dtor(&mut self, data: usize, dtor: Instance) {
+        self.dtors.push((data, dtor));
+    }
+
+    /// Take the destructor that was registered last, since destructors run
+    /// in reverse registration order.
+    pub fn pop_dtor(&mut self) -> Option<(usize, Instance)> {
+        self.dtors.pop()
+    }
+}
@@ -1,8 +1,8 @@
 //! Module with type extensions.
-use anyhow::Result;
-use rustc_public::abi::{Primitive, Scalar, ValueAbi};
+use anyhow::{Result, bail};
+use rustc_public::abi::{FieldsShape, Primitive, Scalar, ValueAbi, VariantsShape};
 use rustc_public::mir::Mutability;
-use rustc_public::ty::{RigidTy, Ty, TyKind};
+use rustc_public::ty::{Binder, ExistentialPredicate, ExistentialTraitRef, RigidTy, Ty, TyKind};
 
 pub trait MonoType {
     /// Return the size of the type in bytes.
@@ -43,6 +43,75 @@ impl MonoType for Ty {
     }
 }
 
+/// Return the principal trait of a trait object type `dyn Trait`.
+///
+/// Returns `None` for trait objects with only auto traits, such as `dyn Send`.
+pub fn dyn_principal(dyn_ty: Ty) -> Result<Option<Binder<ExistentialTraitRef>>> {
+    let TyKind::RigidTy(RigidTy::Dynamic(predicates, _)) = dyn_ty.kind() else {
+        bail!("Expected a trait object type, found `{dyn_ty}`");
+    };
+    Ok(predicates.into_iter().find_map(|predicate| {
+        let ExistentialPredicate::Trait(trait_ref) = predicate.value else {
+            return None;
+        };
+        Some(Binder {
+            value: trait_ref,
+            bound_vars: predicate.bound_vars,
+        })
+    }))
+}
+
+/// Return the offset and the type of field `idx` of the struct type `ty`.
+pub fn struct_field(ty: Ty, idx: usize) -> Result<(usize, Ty)> {
+    let TyKind::RigidTy(RigidTy::Adt(def, args)) = ty.kind() else {
+        bail!("Expected a struct, but found `{ty}`");
+    };
+    let Some(field) = def
+        .variants()
+        .first()
+        .and_then(|v| v.fields().get(idx).cloned())
+    else {
+        bail!("Field index {idx} out of bounds for `{ty}`");
+    };
+    let FieldsShape::Arbitrary { offsets } = ty.layout()?.shape().fields else {
+        bail!("Unexpected layout for `{ty}`");
+    };
+    Ok((offsets[idx].bytes(), field.ty_with_args(&args)))
+}
+
+/// Return the offset and the type of the field `name` of the struct type `ty`.
+///
+/// Types private to `std` that the interpreter reads or builds by hand are
+/// accessed by field name, so a change of their definition is reported
+/// instead of silently using the wrong field.
+pub fn named_field(ty: Ty, name: &str) -> Result<(usize, Ty)> {
+    let TyKind::RigidTy(RigidTy::Adt(def, _)) = ty.kind() else {
+        bail!("Expected a struct, but found `{ty}`");
+    };
+    let Some(idx) = def
+        .variants()
+        .first()
+        .and_then(|v| v.fields().iter().position(|field| field.name == name))
+    else {
+        bail!("Expected a field `{name}` in `{ty}`, which may have changed in `std`");
+    };
+    struct_field(ty, idx)
+}
+
+/// Return the pointee of a pointer that may be wrapped in structs, such as
+/// `Pin<Arc<T>>`, by following the first field of each struct.
+pub fn wrapped_pointee(ty: Ty) -> Result<Ty> {
+    if let Some(pointee) = ty.kind().builtin_deref(true) {
+        return Ok(pointee.ty);
+    }
+    // `NonNull` wraps a `*const T is !null` pattern type.
+    if let TyKind::RigidTy(RigidTy::Pat(base, _)) = ty.kind() {
+        return wrapped_pointee(base);
+    }
+    let (_, field_ty) = struct_field(ty, 0)?;
+    wrapped_pointee(field_ty)
+}
+
 /// Check if a type contains a mutable pointer (`&mut T` or `*mut T`).
 ///
 /// Traverses struct/tuple/array fields recursively.
@@ -148,8 +217,6 @@ fn has_ptr_to_padded_inner(ty: Ty, mutable_only: bool) -> bool {
 /// shape: arrays have padding if stride > element size; structs are checked
 /// by comparing offsets against total size.
 pub fn has_padding(ty: Ty) -> bool {
-    use rustc_public::abi::FieldsShape;
-
     let Ok(layout) = ty.layout() else {
         return false;
     };
@@ -209,6 +276,28 @@ fn composite_has_padding(ty: Ty, total: usize) -> bool {
     }
 }
 
+/// Check if a valid value of this type may contain uninitialized bytes.
+///
+/// Besides padding, this is the case for unions (e.g. `MaybeUninit`) and for
+/// enums, whose inactive variants leave payload bytes uninitialized.
+pub fn may_be_uninit(ty: Ty) -> bool {
+    let Ok(layout) = ty.layout() else {
+        return false;
+    };
+    let shape = layout.shape();
+    let has_union_scalar = match &shape.abi {
+        ValueAbi::Scalar(scalar) => matches!(scalar, Scalar::Union { .. }),
+        ValueAbi::ScalarPair(first, second) => {
+            matches!(first, Scalar::Union { .. }) || matches!(second, Scalar::Union { .. })
+        }
+        _ => false,
+    };
+    has_union_scalar
+        || matches!(shape.variants, VariantsShape::Multiple { .. })
+        || matches!(shape.fields, FieldsShape::Union(_))
+        || has_padding(ty)
+}
+
 fn scalar_primitive(scalar: &Scalar) -> Primitive {
     match scalar {
         Scalar::Initialized { value, .. } | Scalar::Union { value } => *value,
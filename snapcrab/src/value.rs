@@ -109,6 +109,17 @@ impl std::fmt::Display for TypedValue<'_> {
                     UintTy::U128 => write!(f, "{}", u128::read_from_bytes(self.value).unwrap()),
                 }
             }
+            TyKind::RigidTy(RigidTy::Float(float_ty)) => {
+                use rustc_public::ty::FloatTy;
+                let bits = uint_from_bytes(self.value);
+                match float_ty {
+                    FloatTy::F16 => write!(f, "{}", f16::from_bits(bits as u16)),
+                    FloatTy::F32 => write!(f, "{}", f32::from_bits(bits as u32)),
+                    FloatTy::F64 => write!(f, "{}", f64::from_bits(bits as u64)),
+                    // The standard library can't display `f128` yet.
+                    FloatTy::F128 => write!(f, "{}", f128::from_bits(bits) as f64),
+                }
+            }
             TyKind::RigidTy(RigidTy::Tuple(fields)) if fields.is_empty() => write!(f, "()"),
             TyKind::RigidTy(RigidTy::Tuple(fields)) => {
                 write!(f, "(")?;
@@ -218,6 +229,28 @@ impl Value {
         }
     }
 
+    /// Split a tuple value of type `ty` into the values of its fields.
+    ///
+    /// This is the inverse of [`Value::from_tuple_with_layout`].
+    pub fn tuple_fields(&self, ty: Ty) -> Result<Vec<Self>> {
+        let TyKind::RigidTy(RigidTy::Tuple(field_tys)) = ty.kind() else {
+            bail!("Expected tuple type, but found `{ty}`")
+        };
+        let layout = ty.layout()?;
+        let FieldsShape::Arbitrary { offsets } = &layout.shape().fields else {
+            bail!("Expected tuple with arbitrary layout for `{ty}`")
+        };
+        field_tys
+            .iter()
+            .zip(offsets)
+            .map(|(field_ty, offset)| {
+                let offset = offset.bytes();
+                let size = field_ty.size()?;
+                Ok(self.data[offset..offset + size].into())
+            })
+            .collect()
+    }
+
     /// Create value from raw bytes
     pub fn from_bytes(bytes: &[u8]) -> Self {
         Self {
@@ -306,6 +339,12 @@ impl Value {
         Self::from_type([data_addr, metadata])
     }
 
+    /// Create a dangling pointer with the given alignment, like
+    /// `NonNull::dangling`, which is valid for zero-sized accesses.
+    pub fn dangling_ptr(align: usize) -> Self {
+        Self::from_type(align)
+    }
+
     /// Get metadata from a possibly wide pointer
     ///
     /// - Wide pointers are represented as [data_addr: usize, metadata: usize]
@@ -660,6 +699,13 @@ mod tests {
         assert_eq!(ptr.as_type::<[usize; 2]>(), Some([0x1000, 42]));
     }
 
+    #[test]
+    fn test_dangling_ptr() {
+        let ptr = Value::dangling_ptr(8);
+        assert_eq!(ptr.len(), size_of::<usize>());
+        assert_eq!(ptr.read_uint(), 8);
+    }
+
     #[test]
     fn test_ptr_metadata_thin_pointer() {
         let thin_ptr = Value::from_type(0x1000usize);
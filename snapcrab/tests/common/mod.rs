@@ -51,7 +51,7 @@ pub fn run_interpreter_test(input_file: &Path) -> TestResult {
     // Use rustc_public to run the interpreter
     let no_libs: &[&Path] = &[];
     let result = rustc_public::run!(&rustc_args, || {
-        match snapcrab::run_main(snapcrab::CheckConfig::default(), no_libs) {
+        match snapcrab::run_main(snapcrab::RunConfig::default(), no_libs) {
             Ok(_) => std::ops::ControlFlow::Continue(()),
             Err(e) => std::ops::ControlFlow::Break(TestResult::Error(e.to_string())),
         }
@@ -71,6 +71,20 @@ pub fn run_custom_start_test_with_libs(
     input_file: &Path,
     start_fn: &str,
     native_libs: &[impl AsRef<std::path::Path> + Sync],
+) -> TestResult {
+    run_custom_start_test_with_config(
+        input_file,
+        start_fn,
+        snapcrab::RunConfig::default(),
+        native_libs,
+    )
+}
+
+pub fn run_custom_start_test_with_config(
+    input_file: &Path,
+    start_fn: &str,
+    config: snapcrab::RunConfig,
+    native_libs: &[impl AsRef<std::path::Path> + Sync],
 ) -> TestResult {
     // Set up rustc environment to compile the input file
     // Custom function tests use lib crate type
@@ -83,7 +97,7 @@ pub fn run_custom_start_test_with_libs(
     // Use rustc_public to run the interpreter
     let result: Result<(), rustc_public::CompilerError<TestResult>> =
         rustc_public::run!(&rustc_args, || {
-            match snapcrab::run_function(start_fn, snapcrab::CheckConfig::default(), native_libs) {
+            match snapcrab::run_function(start_fn, config.clone(), native_libs) {
                 Ok(value) => std::ops::ControlFlow::Break(TestResult::SuccessWithValue(value)),
                 Err(e) => std::ops::ControlFlow::Break(TestResult::Error(e.to_string())),
             }
@@ -96,6 +110,23 @@ pub fn run_custom_start_test_with_libs(
     }
 }
 
+/// Compile `input_file` as a library, and return the result of `query`,
+/// which runs with the compiler context.
+pub fn run_compiler_query<T: Send>(input_file: &Path, query: impl Fn() -> T + Send + Sync) -> T {
+    let rustc_args = vec![
+        "snapcrab".to_string(),
+        "--crate-type=lib".to_string(),
+        input_file.to_string_lossy().to_string(),
+    ];
+    let result = rustc_public::run!(&rustc_args, || {
+        std::ops::ControlFlow::<T, ()>::Break(query())
+    });
+    match result {
+        Err(rustc_public::CompilerError::Interrupted(value)) => value,
+        _ => panic!("Failed to compile {input_file:?}"),
+    }
+}
+
 /// Compile a Rust source file to a cdylib shared library.
 ///
 /// Uses `rustc_public::run!()` to invoke the same compiler linked into the
@@ -203,7 +234,7 @@ pub fn run_extern_crate_test(dep_source: &Path, input_file: &Path, start_fn: &st
     let native_libs: &[&Path] = &[dylib_path.as_path()];
     let result: Result<(), rustc_public::CompilerError<TestResult>> =
         rustc_public::run!(&rustc_args, || {
-            match snapcrab::run_function(start_fn, snapcrab::CheckConfig::default(), native_libs) {
+            match snapcrab::run_function(start_fn, snapcrab::RunConfig::default(), native_libs) {
                 Ok(value) => std::ops::ControlFlow::Break(TestResult::SuccessWithValue(value)),
                 Err(e) => std::ops::ControlFlow::Break(TestResult::Error(e.to_string())),
             }
@@ -274,6 +305,37 @@ macro_rules! check_custom_start {
             assert_eq!(result, $expected);
         }
     };
+    ($(#[$attr:meta])* $test_name:ident, input=$input_file:expr, start_fn=$start_fn:expr, check_config=$config:expr, result=$expected:expr $(,)?) => {
+        check_custom_start!(
+            $(#[$attr])*
+            $test_name,
+            input = $input_file,
+            start_fn = $start_fn,
+            run_config = snapcrab::RunConfig {
+                checks: $config,
+                ..Default::default()
+            },
+            result = $expected
+        );
+    };
+    ($(#[$attr:meta])* $test_name:ident, input=$input_file:expr, start_fn=$start_fn:expr, run_config=$config:expr, result=$expected:expr $(,)?) => {
+        $(#[$attr])*
+        #[test]
+        fn $test_name() {
+            let input_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("inputs")
+                .join($input_file);
+
+            let result = $crate::common::run_custom_start_test_with_config(
+                &input_path,
+                $start_fn,
+                $config,
+                &[] as &[&std::path::Path],
+            );
+            assert_eq!(result, $expected);
+        }
+    };
 }
 
 /// Declare a native call test that compiles a cdylib, loads it, then interprets a function.
@@ -0,0 +1,57 @@
+//! Runtime checks configured from the command line.
+//!
+//! This file doesn't use unstable features, so it builds on stable.
+
+use std::hint::black_box;
+
+/// Wraps around, unless overflow checks are enabled.
+pub fn add_u8() -> u8 {
+    black_box(200u8) + black_box(100)
+}
+
+/// A misaligned pointer violates the precondition of `slice::from_raw_parts`,
+/// which is only checked if UB checks are enabled.
+pub fn precondition() -> usize {
+    let ptr = std::ptr::without_provenance::<u32>(black_box(1));
+    let slice = unsafe { std::slice::from_raw_parts(ptr, 0) };
+    slice.len() + 1
+}
+
+/// Two threads increment a counter with a separate load and store, which
+/// loses increments when their accesses interleave.
+pub fn lost_update() {
+    let counter = std::sync::atomic::AtomicUsize::new(0);
+    std::thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                for _ in 0..10 {
+                    let value = counter.load(std::sync::atomic::Ordering::Relaxed);
+                    counter.store(value + 1, std::sync::atomic::Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    assert!(counter.into_inner() == 20, "lost an update");
+}
+
+/// Prints the value four times, through interpreted calls.
+struct Repeat(usize);
+
+impl std::fmt::Display for Repeat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for _ in 0..4 {
+            write!(f, "{}", self.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Threads print values whose `Display` is interpreted, which runs while
+/// `println!` holds the lock of stdout.
+pub fn print_display() {
+    std::thread::scope(|s| {
+        for i in 0..3 {
+            s.spawn(move || println!("{} {}", Repeat(i), Repeat(i)));
+        }
+    });
+}
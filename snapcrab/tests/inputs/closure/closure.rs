@@ -0,0 +1,110 @@
+//! Closure calls, directly and through the `Fn*` traits.
+
+fn apply<F: Fn(u32) -> u32>(f: F, x: u32) -> u32 {
+    f(x)
+}
+
+fn apply_mut<F: FnMut(u32)>(mut f: F, n: u32) {
+    let mut i = 0;
+    while i < n {
+        f(i);
+        i += 1;
+    }
+}
+
+/// A value that can only be moved, not copied.
+struct Token(u32);
+
+fn apply_once<F: FnOnce() -> Token>(f: F) -> u32 {
+    f().0
+}
+
+fn apply_dyn(f: &dyn Fn(u32, u32) -> u32) -> u32 {
+    f(3, 4)
+}
+
+fn double(x: u32) -> u32 {
+    2 * x
+}
+
+/// Call a closure directly.
+pub fn test_direct_call() -> u32 {
+    let offset = 10;
+    let add = |x: u32| x + offset;
+    add(5) + add(7)
+}
+
+/// Call closures through a generic `Fn` bound.
+pub fn test_generic_fn() -> u32 {
+    let factor = 3;
+    apply(|x| x * factor, 4) + apply(|x| x + 1, 1)
+}
+
+/// Call a closure that mutates its captures through `FnMut`.
+pub fn test_fn_mut() -> u32 {
+    let mut sum = 0;
+    apply_mut(|i| sum += i, 5);
+    sum
+}
+
+/// Call a closure that moves its captures out through `FnOnce`.
+pub fn test_fn_once() -> u32 {
+    let token = Token(8);
+    apply_once(move || token)
+}
+
+/// Call a closure through `&dyn Fn`.
+pub fn test_dyn_fn() -> u32 {
+    let base = 5;
+    apply_dyn(&|a, b| a * b + base)
+}
+
+/// Call a function item and a function pointer through `Fn`.
+pub fn test_fn_item_as_closure() -> u32 {
+    let ptr: fn(u32) -> u32 = double;
+    apply(double, 4) + apply(ptr, 5)
+}
+
+/// Call a boxed closure.
+pub fn test_box_dyn_fn_once() -> u32 {
+    let token = Token(1);
+    let f: Box<dyn FnOnce() -> Token> = Box::new(move || token);
+    apply_once(f)
+}
+
+/// Chain iterator adapters.
+pub fn test_iterator_chain() -> u32 {
+    let values = [1u32, 2, 3, 4, 5, 6];
+    values
+        .iter()
+        .map(|x| x * 10)
+        .filter(|x| *x > 25)
+        .fold(0, |acc, x| acc + x)
+}
+
+/// Closures returning closures.
+pub fn test_nested_closure() -> u32 {
+    let make_adder = |n: u32| move |x: u32| x + n;
+    let add2 = make_adder(2);
+    apply(add2, 40)
+}
+
+/// Chain iterator adapters over a range.
+pub fn test_range_chain() -> u32 {
+    (1..=10u32)
+        .map(|x| x * x)
+        .filter(|x| x & 1 == 1)
+        .take_while(|x| *x < 50)
+        .fold(0, |acc, x| acc + x)
+}
+
+/// Call `Option` combinators that take closures.
+pub fn test_option_combinators() -> u32 {
+    let limit = 10;
+    let some = Some(4u32)
+        .map(|x| x + 1)
+        .filter(|x| *x < limit)
+        .and_then(|x| x.checked_mul(2));
+    let none = None::<u32>.map(|x| x + 1);
+    some.unwrap_or(0) + none.unwrap_or_else(|| limit)
+}
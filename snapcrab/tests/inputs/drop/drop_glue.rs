@@ -0,0 +1,107 @@
+#![allow(unused)]
+use std::cell::Cell;
+use std::mem::needs_drop;
+
+struct Counter<'a> {
+    drops: &'a Cell<u32>,
+}
+
+impl Drop for Counter<'_> {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+/// Records the order in which values are dropped.
+struct Ordered<'a> {
+    id: u32,
+    log: &'a Cell<u32>,
+}
+
+impl Drop for Ordered<'_> {
+    fn drop(&mut self) {
+        self.log.set(self.log.get() * 10 + self.id);
+    }
+}
+
+struct Pair<'a> {
+    first: Ordered<'a>,
+    second: Ordered<'a>,
+}
+
+pub fn test_drop_impl() {
+    let drops = Cell::new(0);
+    {
+        let _c = Counter { drops: &drops };
+        assert!(drops.get() == 0);
+    }
+    assert!(drops.get() == 1);
+}
+
+pub fn test_explicit_drop() {
+    let drops = Cell::new(0);
+    let c = Counter { drops: &drops };
+    drop(c);
+    assert!(drops.get() == 1);
+}
+
+pub fn test_drop_order() {
+    let log = Cell::new(0);
+    {
+        let _a = Ordered { id: 1, log: &log };
+        let _b = Ordered { id: 2, log: &log };
+    }
+    // Locals are dropped in reverse declaration order.
+    assert!(log.get() == 21);
+}
+
+pub fn test_field_drop_order() {
+    let log = Cell::new(0);
+    {
+        let _pair = Pair {
+            first: Ordered { id: 1, log: &log },
+            second: Ordered { id: 2, log: &log },
+        };
+    }
+    // Fields are dropped in declaration order.
+    assert!(log.get() == 12);
+}
+
+pub fn test_array_drop() {
+    let drops = Cell::new(0);
+    {
+        let _arr = [
+            Counter { drops: &drops },
+            Counter { drops: &drops },
+            Counter { drops: &drops },
+        ];
+    }
+    assert!(drops.get() == 3);
+}
+
+pub fn test_option_drop() {
+    let drops = Cell::new(0);
+    {
+        let _some = Some(Counter { drops: &drops });
+        let _none: Option<Counter> = None;
+    }
+    assert!(drops.get() == 1);
+}
+
+pub fn test_moved_value_not_dropped() {
+    let drops = Cell::new(0);
+    let c = Counter { drops: &drops };
+    let moved = c;
+    assert!(drops.get() == 0);
+    std::mem::forget(moved);
+    assert!(drops.get() == 0);
+}
+
+pub fn test_needs_drop() {
+    assert!(!needs_drop::<u32>());
+    assert!(!needs_drop::<(u8, [u64; 4])>());
+    assert!(needs_drop::<Counter>());
+    assert!(needs_drop::<Box<u8>>());
+    assert!(needs_drop::<Option<Counter>>());
+    assert!(needs_drop::<Vec<u8>>());
+}
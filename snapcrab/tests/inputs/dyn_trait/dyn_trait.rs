@@ -0,0 +1,165 @@
+//! Trait objects: vtables, dynamic dispatch and upcasting.
+use std::cell::Cell;
+use std::rc::Rc;
+
+trait Shape {
+    fn area(&self) -> u32;
+
+    fn double_area(&self) -> u32 {
+        2 * self.area()
+    }
+}
+
+struct Square(u32);
+
+struct Rect {
+    width: u32,
+    height: u32,
+}
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+}
+
+impl Shape for Rect {
+    fn area(&self) -> u32 {
+        self.width * self.height
+    }
+
+    fn double_area(&self) -> u32 {
+        self.area() + self.area()
+    }
+}
+
+fn total_area(shapes: &[&dyn Shape]) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < shapes.len() {
+        total += shapes[i].area();
+        i += 1;
+    }
+    total
+}
+
+/// Dispatch through `&dyn Trait` to different implementations.
+pub fn test_dyn_ref_dispatch() -> u32 {
+    let square = Square(3);
+    let rect = Rect {
+        width: 2,
+        height: 5,
+    };
+    total_area(&[&square, &rect])
+}
+
+/// Default method bodies call back into the vtable.
+pub fn test_dyn_default_method() -> u32 {
+    let square: &dyn Shape = &Square(2);
+    let rect: &dyn Shape = &Rect {
+        width: 1,
+        height: 3,
+    };
+    square.double_area() + rect.double_area()
+}
+
+/// Dispatch on `Box<dyn Trait>`, including `size_of_val`.
+pub fn test_box_dyn() -> usize {
+    let square: Box<dyn Shape> = Box::new(Square(4));
+    square.area() as usize + std::mem::size_of_val(&*square)
+}
+
+struct Counter<'a> {
+    drops: &'a Cell<u32>,
+    value: u64,
+}
+
+impl Shape for Counter<'_> {
+    fn area(&self) -> u32 {
+        self.value as u32
+    }
+}
+
+impl Drop for Counter<'_> {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + self.value as u32);
+    }
+}
+
+/// Dropping `Box<dyn Trait>` runs the drop glue from the vtable.
+pub fn test_box_dyn_drop() -> u32 {
+    let drops = Cell::new(0);
+    {
+        let shapes: [Box<dyn Shape + '_>; 3] = [
+            Box::new(Counter {
+                drops: &drops,
+                value: 10,
+            }),
+            Box::new(Square(5)),
+            Box::new(Counter {
+                drops: &drops,
+                value: 7,
+            }),
+        ];
+        let areas = shapes[0].area() + shapes[1].area() + shapes[2].area();
+        assert!(areas == 42);
+    }
+    drops.get()
+}
+
+/// Coerce `Rc<T>` into `Rc<dyn Trait>`.
+pub fn test_rc_dyn() -> u32 {
+    let shape: Rc<dyn Shape> = Rc::new(Rect {
+        width: 6,
+        height: 7,
+    });
+    let other = Rc::clone(&shape);
+    shape.area() + other.area() + Rc::strong_count(&shape) as u32
+}
+
+trait Named {
+    fn name(&self) -> u32;
+}
+
+trait Labeled: Named {
+    fn label(&self) -> u32;
+}
+
+impl Named for Square {
+    fn name(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Labeled for Square {
+    fn label(&self) -> u32 {
+        self.0 * 10
+    }
+}
+
+/// Upcast `&dyn Sub` to `&dyn Super`.
+pub fn test_upcast() -> u32 {
+    let labeled: &dyn Labeled = &Square(4);
+    let named: &dyn Named = labeled;
+    labeled.label() + named.name()
+}
+
+/// Drop auto traits from a trait object.
+pub fn test_drop_auto_trait() -> u32 {
+    let boxed: Box<dyn Shape + Send> = Box::new(Square(3));
+    let boxed: Box<dyn Shape> = boxed;
+    boxed.area()
+}
+
+/// `size_of_val` and `align_of_val` read the vtable.
+pub fn test_dyn_size_of_val() -> usize {
+    let rect: &dyn Shape = &Rect {
+        width: 1,
+        height: 1,
+    };
+    let counter: &dyn Shape = &Counter {
+        drops: &Cell::new(0),
+        value: 1,
+    };
+    std::mem::size_of_val(rect) + std::mem::align_of_val(counter) + total_area(&[rect, counter]) as usize
+}
@@ -0,0 +1,71 @@
+//! Floating-point arithmetic, comparisons and casts.
+#![feature(f16, f128)]
+
+/// Basic arithmetic on `f64`.
+pub fn test_f64_arithmetic() -> f64 {
+    let a = 7.5f64;
+    let b = 2.0f64;
+    (a + b) * (a - b) / b + a % b - (-a)
+}
+
+/// Basic arithmetic on `f32`.
+pub fn test_f32_arithmetic() -> f32 {
+    let mut sum = 0.0f32;
+    let mut i = 0;
+    while i < 10 {
+        sum += 0.1;
+        i += 1;
+    }
+    sum
+}
+
+/// Comparisons follow IEEE semantics, NaN compares unequal to everything.
+pub fn test_nan_comparisons() -> bool {
+    let nan = f64::NAN;
+    let one = 1.0f64;
+    !(nan == nan) && nan != nan && !(nan < one) && !(nan >= one) && one > -one && -0.0 == 0.0
+}
+
+/// Infinities from overflow and division by zero.
+pub fn test_infinity() -> bool {
+    let inf = f32::MAX * 2.0;
+    let neg_inf = -1.0f32 / 0.0;
+    inf == f32::INFINITY && neg_inf == f32::NEG_INFINITY && inf > f32::MAX
+}
+
+/// Float-to-int casts saturate, and NaN becomes zero.
+pub fn test_float_to_int_saturates() -> bool {
+    300.5f32 as u8 == u8::MAX
+        && -200.0f64 as i8 == i8::MIN
+        && f64::NAN as i32 == 0
+        && -1.5f32 as u32 == 0
+        && 1e30f64 as i64 == i64::MAX
+        && f32::NEG_INFINITY as i128 == i128::MIN
+}
+
+/// Float-to-int casts truncate toward zero.
+pub fn test_float_to_int_truncates() -> bool {
+    3.99f64 as i32 == 3 && -3.99f32 as i32 == -3 && 12345.678f64 as u64 == 12345
+}
+
+/// Int-to-float casts round to the nearest representable value.
+pub fn test_int_to_float() -> bool {
+    16_777_217i32 as f32 == 16_777_216.0
+        && -42i8 as f64 == -42.0
+        && u64::MAX as f32 == 18_446_744_073_709_551_616.0
+}
+
+/// Casts between float types.
+pub fn test_float_to_float() -> bool {
+    let narrow = 0.1f64 as f32;
+    let wide = 0.1f32 as f64;
+    let nan = f32::NAN as f64;
+    narrow == 0.1f32 && wide == 0.10000000149011612 && nan != nan && 1e300f64 as f32 == f32::INFINITY
+}
+
+/// `f16` and `f128` arithmetic and casts.
+pub fn test_f16_f128() -> u32 {
+    let half = 1.5f16 * 3.0;
+    let quad = (1.0f128 / 3.0) * 3.0;
+    (half as f128 + quad) as u32
+}
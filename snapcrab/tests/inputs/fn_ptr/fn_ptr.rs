@@ -0,0 +1,78 @@
+//! Test calls through function pointers.
+#![allow(unused)]
+
+fn double(x: u32) -> u32 {
+    x * 2
+}
+
+fn square(x: u32) -> u32 {
+    x * x
+}
+
+fn identity<T>(x: T) -> T {
+    x
+}
+
+/// A plugin registry entry, like the ones in callback-heavy code.
+struct Plugin {
+    id: u32,
+    run: fn(u32) -> u32,
+}
+
+static HANDLERS: [fn(u32) -> u32; 2] = [double, square];
+
+const TRIPLE: fn(u32) -> u32 = |x| x * 3;
+
+fn apply(f: fn(u32) -> u32, x: u32) -> u32 {
+    f(x)
+}
+
+pub fn test_reify_fn_ptr() -> u32 {
+    let f: fn(u32) -> u32 = double;
+    f(21)
+}
+
+pub fn test_generic_fn_ptr() -> u64 {
+    let f: fn(u64) -> u64 = identity::<u64>;
+    f(42)
+}
+
+pub fn test_closure_fn_ptr() -> u32 {
+    let f: fn(u32, u32) -> u32 = |a, b| a * b;
+    f(6, 7)
+}
+
+pub fn test_fn_ptr_argument() -> u32 {
+    apply(double, 5) + apply(|x| x + 1, 5)
+}
+
+pub fn test_fn_ptr_in_struct() -> u32 {
+    let plugins = [
+        Plugin { id: 1, run: double },
+        Plugin { id: 2, run: square },
+        Plugin { id: 3, run: |x| x - 1 },
+    ];
+    let mut total = 0;
+    for plugin in &plugins {
+        total += (plugin.run)(plugin.id + 2);
+    }
+    total
+}
+
+pub fn test_fn_ptr_in_static() -> u32 {
+    HANDLERS[0](3) + HANDLERS[1](3)
+}
+
+pub fn test_fn_ptr_in_const() -> u32 {
+    TRIPLE(5)
+}
+
+pub fn test_native_fn_ptr() -> bool {
+    let f: fn() -> u32 = std::process::id;
+    f() != 0
+}
+
+pub fn test_invalid_fn_ptr() {
+    let f: fn() = unsafe { std::mem::transmute(0x10usize) };
+    f();
+}
@@ -0,0 +1,83 @@
+#![allow(unused, internal_features)]
+#![feature(core_intrinsics)]
+use std::alloc::{Layout, alloc, alloc_zeroed, dealloc, realloc};
+
+pub fn test_box_leak() {
+    let b = Box::leak(Box::new(5u32));
+    *b += 1;
+    assert!(*b == 6);
+}
+
+pub fn test_alloc_write_read() {
+    unsafe {
+        let layout = Layout::new::<[u64; 4]>();
+        let ptr = alloc(layout) as *mut u64;
+        *ptr.add(3) = 42;
+        assert!(*ptr.add(3) == 42);
+        dealloc(ptr as *mut u8, layout);
+    }
+}
+
+pub fn test_alloc_zeroed() {
+    unsafe {
+        let layout = Layout::new::<[u32; 8]>();
+        let ptr = alloc_zeroed(layout) as *mut u32;
+        assert!(*ptr.add(7) == 0);
+        dealloc(ptr as *mut u8, layout);
+    }
+}
+
+pub fn test_realloc_preserves_contents() {
+    unsafe {
+        let layout = Layout::new::<[u16; 2]>();
+        let ptr = alloc(layout) as *mut u16;
+        *ptr = 1;
+        *ptr.add(1) = 2;
+        let ptr = realloc(ptr as *mut u8, layout, 8) as *mut u16;
+        assert!(*ptr == 1);
+        assert!(*ptr.add(1) == 2);
+        *ptr.add(3) = 4;
+        dealloc(ptr as *mut u8, Layout::new::<[u16; 4]>());
+    }
+}
+
+pub fn test_read_out_of_bounds() -> [u64; 2] {
+    unsafe {
+        let layout = Layout::new::<u64>();
+        let ptr = alloc(layout) as *const [u64; 2];
+        *ptr
+    }
+}
+
+pub fn test_write_out_of_bounds() -> u32 {
+    unsafe {
+        let layout = Layout::new::<u32>();
+        let ptr = alloc_zeroed(layout);
+        *(ptr as *mut [u32; 2]) = [1, 2];
+        let value = *(ptr as *const u32);
+        dealloc(ptr, layout);
+        value
+    }
+}
+
+pub fn test_read_back_out_of_bounds() -> u32 {
+    unsafe {
+        let layout = Layout::new::<u32>();
+        let ptr = alloc_zeroed(layout) as *mut [u32; 2];
+        *ptr = [1, 2];
+        let array = *ptr;
+        dealloc(ptr as *mut u8, layout);
+        array[1]
+    }
+}
+
+/// Compare far more bytes than the host has mapped past the allocation.
+pub fn test_read_unmapped_out_of_bounds() -> i32 {
+    unsafe {
+        let layout = Layout::new::<u64>();
+        let ptr = alloc_zeroed(layout);
+        let ordering = std::intrinsics::compare_bytes(ptr, ptr, 1 << 46);
+        dealloc(ptr, layout);
+        ordering
+    }
+}
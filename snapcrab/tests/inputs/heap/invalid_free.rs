@@ -0,0 +1,44 @@
+#![allow(unused)]
+use std::alloc::{Layout, alloc, dealloc};
+
+pub fn test_use_after_free() -> u32 {
+    unsafe {
+        let layout = Layout::new::<u32>();
+        let ptr = alloc(layout) as *mut u32;
+        *ptr = 10;
+        dealloc(ptr as *mut u8, layout);
+        *ptr
+    }
+}
+
+pub fn test_double_free() {
+    unsafe {
+        let layout = Layout::new::<u32>();
+        let ptr = alloc(layout);
+        dealloc(ptr, layout);
+        dealloc(ptr, layout);
+    }
+}
+
+pub fn test_dealloc_wrong_size() {
+    unsafe {
+        let ptr = alloc(Layout::new::<u64>());
+        dealloc(ptr, Layout::new::<u32>());
+    }
+}
+
+pub fn test_dealloc_wrong_align() {
+    unsafe {
+        let ptr = alloc(Layout::new::<u64>());
+        dealloc(ptr, Layout::new::<[u16; 4]>());
+    }
+}
+
+pub fn test_stale_ptr_after_realloc() -> u8 {
+    unsafe {
+        let layout = Layout::new::<u8>();
+        let ptr = alloc(layout);
+        let _new = std::alloc::realloc(ptr, layout, 2);
+        *ptr
+    }
+}
@@ -0,0 +1,15 @@
+#![allow(unused)]
+use std::alloc::{Layout, alloc, dealloc};
+
+pub fn test_forget_box() {
+    let b = Box::new([1u16; 3]);
+    std::mem::forget(b);
+}
+
+pub fn test_no_leak() {
+    unsafe {
+        let layout = Layout::new::<u64>();
+        let ptr = alloc(layout);
+        dealloc(ptr, layout);
+    }
+}
@@ -0,0 +1,96 @@
+#![allow(unused, internal_features)]
+#![feature(core_intrinsics)]
+
+use std::hint::black_box;
+use std::intrinsics::{self, AtomicOrdering};
+use std::sync::atomic::{self, AtomicBool, AtomicI32, AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Loads, stores and swaps.
+pub fn test_load_store() -> usize {
+    let counter = AtomicUsize::new(5);
+    counter.store(black_box(7), Ordering::Release);
+    let old = counter.swap(10, Ordering::AcqRel);
+    old * 100 + counter.load(Ordering::Acquire)
+}
+
+/// Compare-exchange reports whether the value was replaced.
+pub fn test_compare_exchange() -> u8 {
+    let flag = AtomicBool::new(false);
+    assert!(flag.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst) == Ok(false));
+    assert!(flag.compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed) == Err(true));
+    let value = AtomicU8::new(3);
+    let mut current = value.load(Ordering::Relaxed);
+    while let Err(actual) =
+        value.compare_exchange_weak(current, current * 2, Ordering::SeqCst, Ordering::Relaxed)
+    {
+        current = actual;
+    }
+    value.into_inner()
+}
+
+/// Fetch operations return the previous value.
+pub fn test_fetch_ops() -> i32 {
+    let value = AtomicI32::new(-5);
+    assert!(value.fetch_add(10, Ordering::SeqCst) == -5);
+    assert!(value.fetch_sub(20, Ordering::SeqCst) == 5);
+    assert!(value.fetch_max(-3, Ordering::SeqCst) == -15);
+    assert!(value.fetch_min(-100, Ordering::SeqCst) == -3);
+    assert!(value.fetch_or(0b11, Ordering::SeqCst) == -100);
+    assert!(value.fetch_and(!1, Ordering::SeqCst) == -97);
+    assert!(value.fetch_xor(-1, Ordering::SeqCst) == -98);
+    assert!(value.fetch_nand(0xFF, Ordering::SeqCst) == 97);
+    value.load(Ordering::SeqCst)
+}
+
+/// Unsigned min and max compare the unsigned value.
+pub fn test_fetch_unsigned_max() -> u8 {
+    let value = AtomicU8::new(200);
+    value.fetch_max(100, Ordering::SeqCst);
+    value.fetch_min(250, Ordering::SeqCst);
+    value.load(Ordering::SeqCst)
+}
+
+/// Atomic pointers use the pointer width.
+pub fn test_atomic_ptr() -> u32 {
+    let mut first = 1u32;
+    let mut second = 2u32;
+    let ptr = AtomicPtr::new(&mut first);
+    let old = ptr.swap(&mut second, Ordering::SeqCst);
+    atomic::fence(Ordering::SeqCst);
+    atomic::compiler_fence(Ordering::SeqCst);
+    unsafe { *old * 10 + *ptr.load(Ordering::SeqCst) }
+}
+
+/// `Arc` reference counting.
+pub fn test_arc() -> usize {
+    let shared = Arc::new(black_box(42usize));
+    let clone = Arc::clone(&shared);
+    assert!(Arc::strong_count(&shared) == 2);
+    drop(clone);
+    assert!(Arc::strong_count(&shared) == 1);
+    *shared
+}
+
+/// Uncontended mutex lock and unlock.
+pub fn test_mutex() -> u32 {
+    let mutex = Mutex::new(1u32);
+    *mutex.lock().unwrap() += 2;
+    *mutex.lock().unwrap() *= 5;
+    mutex.into_inner().unwrap()
+}
+
+/// Lazy initialization with `OnceLock`.
+pub fn test_once_lock() -> u64 {
+    let cell = OnceLock::new();
+    let first = *cell.get_or_init(|| black_box(11u64));
+    let second = *cell.get_or_init(|| 22);
+    first + second
+}
+
+/// Atomic accesses are checked like other accesses.
+pub fn test_misaligned_atomic() -> u32 {
+    let data = [0u64; 2];
+    let ptr = (data.as_ptr() as *const u8).wrapping_add(2) as *const u32;
+    unsafe { intrinsics::atomic_load::<u32, { AtomicOrdering::SeqCst }>(ptr) }
+}
@@ -0,0 +1,93 @@
+#![allow(unused, internal_features)]
+#![feature(core_intrinsics, f16, f128, float_minimum_maximum, float_algebraic)]
+
+use std::hint::black_box;
+use std::intrinsics;
+
+/// Square roots and integer powers.
+pub fn test_sqrt_powi() -> u64 {
+    let x = black_box(2.0f64);
+    (x.sqrt() + x.powi(10) + black_box(1.5f64).powi(-3)).to_bits()
+}
+
+/// Transcendental functions must match the host bit for bit.
+pub fn test_transcendental() -> u64 {
+    let x = black_box(0.7f64);
+    let results = [
+        x.sin(),
+        x.cos(),
+        x.exp(),
+        x.exp2(),
+        x.ln(),
+        x.log2(),
+        x.log10(),
+        x.powf(2.5),
+    ];
+    results.iter().fold(0, |acc, value| acc ^ value.to_bits())
+}
+
+/// The same functions on `f32`.
+pub fn test_transcendental_f32() -> u32 {
+    let x = black_box(1.3f32);
+    let results = [x.sin(), x.cos(), x.exp(), x.ln(), x.powf(0.3), x.sqrt()];
+    results.iter().fold(0, |acc, value| acc ^ value.to_bits())
+}
+
+/// `mul_add` is fused and rounds only once.
+pub fn test_mul_add() -> u32 {
+    let a = black_box(0.1f32);
+    let fused = a.mul_add(10.0, -1.0);
+    assert!(fused != a * 10.0 - 1.0);
+    fused.to_bits()
+}
+
+/// `fmuladd` is not fused, so the product is rounded before the addition.
+pub fn test_fmuladd() -> u32 {
+    let a = black_box(0.1f32);
+    let result = intrinsics::fmuladdf32(a, 10.0, -1.0);
+    assert!(result != a.mul_add(10.0, -1.0));
+    result.to_bits()
+}
+
+/// Rounding functions.
+pub fn test_rounding() -> i32 {
+    let x = black_box(-2.5f64);
+    assert!(x.floor() == -3.0 && x.ceil() == -2.0 && x.trunc() == -2.0);
+    assert!(x.round() == -3.0 && x.round_ties_even() == -2.0);
+    assert!(black_box(3.5f32).round_ties_even() == 4.0);
+    (x.abs() * 2.0) as i32
+}
+
+/// `min` and `max` ignore NaN, while `minimum` and `maximum` propagate it.
+pub fn test_min_max() -> f64 {
+    let nan = black_box(f64::NAN);
+    assert!(nan.min(1.0) == 1.0 && black_box(2.0f64).max(nan) == 2.0);
+    assert!(nan.minimum(1.0).is_nan() && black_box(-0.0f64).maximum(0.0).is_sign_positive());
+    black_box(3.0f64).copysign(-0.0) + black_box(-4.0f64).abs() + black_box(1.0f64).min(0.5)
+}
+
+/// Math on the half and quad precision types.
+pub fn test_f16_f128() -> u32 {
+    let half = black_box(2.0f16);
+    let quad = black_box(2.0f128);
+    assert!(half.sqrt() == 1.4140625 && quad.mul_add(quad, 1.0) == 5.0);
+    (quad.sqrt().to_bits() >> 96) as u32 ^ half.sqrt().to_bits() as u32
+}
+
+/// Out of range values passed to `to_int_unchecked` are undefined behavior.
+pub fn test_to_int_unchecked_out_of_range() -> u8 {
+    assert!(unsafe { black_box(255.9f32).to_int_unchecked::<u8>() } == 255);
+    unsafe { black_box(256.0f32).to_int_unchecked::<u8>() }
+}
+
+/// Non-finite values passed to fast-math intrinsics are undefined behavior.
+pub fn test_fast_math_infinity() -> f64 {
+    assert!(unsafe { intrinsics::fmul_fast(black_box(1.5f64), 2.0) } == 3.0);
+    unsafe { intrinsics::fadd_fast(black_box(f64::MAX), f64::MAX) }
+}
+
+/// Algebraic intrinsics are regular float operations.
+pub fn test_algebraic() -> f32 {
+    let x = black_box(6.0f32);
+    x.algebraic_mul(2.0).algebraic_sub(1.0).algebraic_div(x.algebraic_add(5.0))
+}
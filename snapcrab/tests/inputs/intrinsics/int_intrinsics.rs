@@ -0,0 +1,123 @@
+#![allow(unused)]
+#![feature(core_intrinsics)]
+
+use std::cmp::Ordering;
+use std::hint::black_box;
+use std::intrinsics;
+
+/// Bit counting through the integer methods.
+pub fn test_bit_counts() -> u32 {
+    let x: u64 = black_box(0x00F0_0000_0000_0100);
+    x.count_ones() * 10000 + x.leading_zeros() * 100 + x.trailing_zeros()
+}
+
+/// Zero has no set bits, so the counts are the bit width.
+pub fn test_zero_bit_counts() -> u32 {
+    let x: u16 = black_box(0);
+    x.leading_zeros() + x.trailing_zeros()
+}
+
+/// Byte swaps and bit reversals.
+pub fn test_swap_and_reverse() {
+    let x: u32 = black_box(0x1234_5678);
+    assert!(x.swap_bytes() == 0x7856_3412);
+    assert!(x.reverse_bits() == 0x1E6A_2C48);
+    let y: i16 = black_box(-2);
+    assert!(y.swap_bytes() == -257);
+}
+
+/// Rotations wrap around the bit width.
+pub fn test_rotate() {
+    let x: u8 = black_box(0b1000_0001);
+    assert!(x.rotate_left(1) == 0b0000_0011);
+    assert!(x.rotate_right(1) == 0b1100_0000);
+    assert!(x.rotate_left(9) == 0b0000_0011);
+}
+
+/// The overflowing methods return the wrapped value.
+pub fn test_overflowing() -> u8 {
+    let (sum, overflow) = black_box(250u8).overflowing_add(black_box(10));
+    assert!(overflow);
+    let (diff, overflow) = black_box(5i8).overflowing_sub(black_box(3));
+    assert!(!overflow);
+    let (product, overflow) = black_box(16u8).overflowing_mul(black_box(17));
+    assert!(overflow);
+    sum + diff as u8 + product
+}
+
+/// Calling the intrinsics directly.
+pub fn test_with_overflow_intrinsics() -> i32 {
+    let (sum, overflow) = intrinsics::add_with_overflow(black_box(i32::MAX), black_box(1));
+    assert!(overflow);
+    let (product, overflow) = intrinsics::mul_with_overflow(black_box(-3i32), black_box(7));
+    assert!(!overflow);
+    sum.wrapping_sub(i32::MIN) + product
+}
+
+/// Saturating arithmetic clamps at the bounds of the type.
+pub fn test_saturating() {
+    assert!(black_box(200u8).saturating_add(black_box(100)) == u8::MAX);
+    assert!(black_box(5u8).saturating_sub(black_box(10)) == 0);
+    assert!(black_box(-100i8).saturating_sub(black_box(100)) == i8::MIN);
+    assert!(black_box(20i8).saturating_add(black_box(-30)) == -10);
+}
+
+/// Integer comparisons through `Ord::cmp`.
+pub fn test_three_way_compare() -> i8 {
+    let less = black_box(-1i64).cmp(&black_box(1));
+    let greater = black_box(u128::MAX).cmp(&black_box(0));
+    let equal = black_box(7usize).cmp(&black_box(7));
+    assert!(less == Ordering::Less);
+    assert!(greater == Ordering::Greater);
+    assert!(equal == Ordering::Equal);
+    intrinsics::three_way_compare(black_box(3u8), black_box(9)) as i8
+}
+
+/// Unchecked operations within their preconditions.
+pub fn test_unchecked() -> u32 {
+    let x: u32 = black_box(100);
+    let y: u32 = black_box(7);
+    unsafe {
+        let sum = x.unchecked_add(y);
+        let product = intrinsics::unchecked_mul(x, y);
+        let quotient = intrinsics::unchecked_div(x, y);
+        let rem = intrinsics::unchecked_rem(x, y);
+        let shifted = intrinsics::unchecked_shl(x, 2u8);
+        sum + product + quotient + rem + shifted
+    }
+}
+
+/// `exact_div` of evenly divisible values.
+pub fn test_exact_div() -> i64 {
+    unsafe { intrinsics::exact_div(black_box(-42i64), black_box(6)) }
+}
+
+/// Unchecked addition that overflows is undefined behavior.
+pub fn test_unchecked_add_overflow() -> u8 {
+    unsafe { intrinsics::unchecked_add(black_box(200u8), black_box(100)) }
+}
+
+/// Unchecked shifts by the bit width or more are undefined behavior.
+pub fn test_unchecked_shr_overflow() -> i32 {
+    unsafe { intrinsics::unchecked_shr(black_box(1i32), black_box(32u32)) }
+}
+
+/// `i32::MIN / -1` overflows.
+pub fn test_unchecked_div_overflow() -> i32 {
+    unsafe { intrinsics::unchecked_div(black_box(i32::MIN), black_box(-1)) }
+}
+
+/// Unchecked division by zero is undefined behavior.
+pub fn test_unchecked_rem_zero() -> u32 {
+    unsafe { intrinsics::unchecked_rem(black_box(7u32), black_box(0)) }
+}
+
+/// `exact_div` with a remainder is undefined behavior.
+pub fn test_exact_div_remainder() -> u32 {
+    unsafe { intrinsics::exact_div(black_box(10u32), black_box(4)) }
+}
+
+/// `ctlz_nonzero` of zero is undefined behavior.
+pub fn test_ctlz_nonzero_zero() -> u32 {
+    unsafe { intrinsics::ctlz_nonzero(black_box(0u64)) }
+}
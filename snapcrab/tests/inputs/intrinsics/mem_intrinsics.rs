@@ -0,0 +1,115 @@
+#![allow(unused)]
+#![feature(core_intrinsics)]
+
+use std::hint::black_box;
+use std::intrinsics;
+use std::ptr;
+
+/// `ptr::copy_nonoverlapping` between two arrays.
+pub fn test_copy_nonoverlapping() -> u32 {
+    let src: [u32; 4] = [1, 2, 3, 4];
+    let mut dst: [u32; 4] = [0; 4];
+    unsafe { ptr::copy_nonoverlapping(src.as_ptr().add(1), dst.as_mut_ptr(), 3) };
+    dst[0] * 100 + dst[1] * 10 + dst[2] + dst[3]
+}
+
+/// `ptr::copy` handles overlapping ranges like `memmove`.
+pub fn test_copy_overlapping() -> u32 {
+    let mut data: [u8; 6] = [1, 2, 3, 4, 5, 6];
+    let base = data.as_mut_ptr();
+    unsafe { ptr::copy(base, base.add(2), 4) };
+    assert!(data == [1, 2, 1, 2, 3, 4]);
+    unsafe { ptr::copy(base.add(2), base, 4) };
+    assert!(data == [1, 2, 3, 4, 3, 4]);
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+/// Overlapping ranges passed to `copy_nonoverlapping` are undefined behavior.
+pub fn test_copy_nonoverlapping_overlap() {
+    let mut data: [u16; 4] = [1, 2, 3, 4];
+    let base = data.as_mut_ptr();
+    unsafe { intrinsics::copy_nonoverlapping(base, base.add(1), 2) };
+}
+
+/// Copies are bounds checked like any other access.
+pub fn test_copy_out_of_bounds() {
+    let src = Box::new([0u64; 2]);
+    let mut dst: [u64; 4] = [0; 4];
+    unsafe { ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), black_box(4)) };
+}
+
+/// `ptr::write_bytes` fills `count` values.
+pub fn test_write_bytes() -> u64 {
+    let mut data: [u16; 4] = [0; 4];
+    unsafe { ptr::write_bytes(data.as_mut_ptr().add(1), 0xAB, 2) };
+    assert!(data == [0, 0xABAB, 0xABAB, 0]);
+    let mut value = 0u64;
+    unsafe { ptr::write_bytes(&mut value, 0x01, 1) };
+    value
+}
+
+/// Slice and string comparisons use `compare_bytes`.
+pub fn test_compare_bytes() -> i32 {
+    let left: &[u8] = black_box(b"abcd");
+    let right: &[u8] = black_box(b"abce");
+    assert!(left < right);
+    assert!("snap" == black_box("snap"));
+    assert!(black_box("crab") > "cra");
+    unsafe { intrinsics::compare_bytes(right.as_ptr(), left.as_ptr(), 4) }
+}
+
+/// Array equality uses `raw_eq`.
+pub fn test_raw_eq() -> bool {
+    let a: [u8; 16] = black_box([7; 16]);
+    let b: [u8; 16] = black_box([7; 16]);
+    let mut c = b;
+    c[15] = 0;
+    assert!(a == b);
+    assert!(a != c);
+    unsafe { intrinsics::raw_eq(&a, &c) }
+}
+
+/// Volatile loads and stores behave like regular accesses.
+pub fn test_volatile() -> u32 {
+    let mut value: u32 = 0;
+    unsafe {
+        ptr::write_volatile(&mut value, 41);
+        let loaded = ptr::read_volatile(&value);
+        ptr::write_volatile(&mut value, loaded + 1);
+    }
+    value
+}
+
+/// Volatile accesses are checked for alignment.
+pub fn test_volatile_misaligned() -> u32 {
+    let data: [u32; 2] = [0; 2];
+    let ptr = (data.as_ptr() as *const u8).wrapping_add(1) as *const u32;
+    unsafe { intrinsics::volatile_load(ptr) }
+}
+
+/// Pointer distances, e.g. the length of slice iterators.
+pub fn test_offset_from() -> isize {
+    let data: [u64; 8] = [0; 8];
+    let start = data.as_ptr();
+    let end = unsafe { start.add(6) };
+    assert!(unsafe { end.offset_from_unsigned(start) } == 6);
+    assert!(data[2..].iter().len() == 6);
+    unsafe { start.offset_from(end) }
+}
+
+/// Both pointers must be in the same allocation, possibly one past its end.
+pub fn test_offset_from_different_allocations() -> isize {
+    let first = Box::new([0u32; 4]);
+    let second = Box::new([0u32; 4]);
+    let start = first.as_ptr();
+    assert!(unsafe { start.add(4).offset_from(start) } == 4);
+    unsafe { second.as_ptr().offset_from(start) }
+}
+
+/// The unsigned distance requires the pointer to come after its base.
+pub fn test_offset_from_unsigned_negative() -> usize {
+    let data: [u32; 4] = [0; 4];
+    let start = data.as_ptr();
+    let end = unsafe { start.add(2) };
+    unsafe { intrinsics::ptr_offset_from_unsigned(start, end) }
+}
@@ -0,0 +1,105 @@
+#![allow(unused, internal_features)]
+#![feature(core_intrinsics, variant_count)]
+
+use std::any::{Any, TypeId, type_name};
+use std::hint::black_box;
+use std::mem;
+
+pub enum Shape {
+    Circle(u32),
+    Square { side: u16 },
+    Empty,
+}
+
+/// Type names of primitive and generic types.
+pub fn test_type_name() -> usize {
+    assert!(type_name::<u32>() == "u32");
+    assert!(type_name::<Option<&str>>() == "core::option::Option<&str>");
+    type_name::<Shape>().len()
+}
+
+/// `TypeId` equality distinguishes types.
+pub fn test_type_id() -> bool {
+    assert!(TypeId::of::<u8>() == TypeId::of::<u8>());
+    assert!(TypeId::of::<u8>() != TypeId::of::<i8>());
+    TypeId::of::<Vec<u8>>() == TypeId::of::<Vec<u16>>()
+}
+
+/// Downcasting through `Any`.
+pub fn test_downcast() -> u32 {
+    let value: Box<dyn Any> = Box::new(black_box(7u32));
+    assert!(value.downcast_ref::<i32>().is_none());
+    assert!(value.is::<u32>());
+    *value.downcast::<u32>().unwrap()
+}
+
+/// `size_of_val` and `align_of_val` of unsized values.
+pub fn test_size_of_val() -> usize {
+    let slice: &[u32] = &black_box([1, 2, 3]);
+    let text: &str = black_box("hello");
+    let any: &dyn Any = &black_box(1u16);
+    assert!(mem::align_of_val(slice) == 4);
+    mem::size_of_val(slice) + mem::size_of_val(text) + mem::size_of_val(any)
+}
+
+/// `mem::discriminant` compares variants only.
+pub fn test_discriminant() -> bool {
+    let a = Shape::Circle(1);
+    let b = Shape::Circle(2);
+    let c = Shape::Square { side: 3 };
+    assert!(mem::discriminant(&a) == mem::discriminant(&b));
+    mem::discriminant(&a) == mem::discriminant(&c)
+}
+
+/// `discriminant_value` returns the discriminant of the variant.
+pub fn test_discriminant_value() -> isize {
+    let shape = black_box(Shape::Empty);
+    std::intrinsics::discriminant_value(&shape)
+}
+
+/// The number of variants of an enum.
+pub fn test_variant_count() -> usize {
+    mem::variant_count::<Shape>() * 10 + mem::variant_count::<Option<u8>>()
+}
+
+/// `variant_count` called at runtime from generic code.
+pub fn test_runtime_variant_count() -> usize {
+    fn count<T>() -> usize {
+        std::intrinsics::variant_count::<T>()
+    }
+    assert!(count::<u8>() == 0);
+    count::<Shape>() * 10 + count::<Result<u8, ()>>()
+}
+
+trait Value {
+    fn value(&self) -> u8;
+}
+
+#[repr(align(16))]
+struct Aligned(u8);
+
+impl Value for Aligned {
+    fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+struct Tagged<T: ?Sized> {
+    tag: u8,
+    value: T,
+}
+
+/// `size_of_val` and `align_of_val` of structs whose unsized tail is a trait
+/// object aligned more than the other fields.
+pub fn test_size_of_val_aligned_dyn() -> usize {
+    let rc: std::rc::Rc<dyn Value> = std::rc::Rc::new(Aligned(black_box(5)));
+    assert!(rc.value() == 5);
+    assert!(mem::align_of_val(&*rc) == 16);
+    let tagged: &Tagged<dyn Value> = &Tagged {
+        tag: 1,
+        value: Aligned(black_box(7)),
+    };
+    assert!(tagged.tag == 1 && tagged.value.value() == 7);
+    assert!(mem::align_of_val(tagged) == 16);
+    mem::size_of_val(tagged) + mem::size_of_val(&*rc)
+}
@@ -0,0 +1,18 @@
+#![allow(unused, internal_features)]
+#![feature(core_intrinsics)]
+
+use std::hint::black_box;
+use std::intrinsics;
+
+/// A misaligned pointer violates the precondition of `slice::from_raw_parts`,
+/// which is only checked if UB checks are enabled.
+pub fn test_precondition() -> usize {
+    let ptr = std::ptr::without_provenance::<u32>(black_box(1));
+    let slice = unsafe { std::slice::from_raw_parts(ptr, 0) };
+    slice.len() + 1
+}
+
+/// Whether overflow checks are enabled in the code that checks them at runtime.
+pub fn test_overflow_checks() -> bool {
+    intrinsics::overflow_checks()
+}
@@ -0,0 +1,64 @@
+#![allow(unused, invalid_value, unnecessary_transmutes)]
+use std::hint::black_box;
+use std::mem::MaybeUninit;
+use std::num::NonZeroU32;
+
+#[repr(u8)]
+#[derive(Clone, Copy)]
+pub enum Sparse {
+    A = 1,
+    B = 5,
+}
+
+/// Read a byte through a `bool` pointer.
+pub fn read_invalid_bool() -> bool {
+    let byte: u8 = black_box(3);
+    unsafe { *(&byte as *const u8 as *const bool) }
+}
+
+/// A surrogate is not a valid `char`.
+pub fn read_surrogate_char() -> char {
+    let code: u32 = black_box(0xD800);
+    unsafe { *(&code as *const u32 as *const char) }
+}
+
+/// Surrogates are inside the valid range of the `char` layout.
+pub fn transmute_surrogate_char() -> u32 {
+    let code: char = unsafe { std::mem::transmute(black_box(0xD800u32)) };
+    code as u32
+}
+
+/// 3 is in the range of the tags, but it is not the discriminant of any variant.
+pub fn read_invalid_enum_tag() -> Sparse {
+    let tag: u8 = black_box(3);
+    unsafe { *(&tag as *const u8 as *const Sparse) }
+}
+
+/// Zeroed memory is not a valid reference.
+pub fn assume_init_null_ref() -> u8 {
+    let reference: &u8 = unsafe { MaybeUninit::zeroed().assume_init() };
+    *reference
+}
+
+/// Zero is the niche of `NonZero`.
+pub fn assume_init_zero_nonzero() -> u32 {
+    let value: NonZeroU32 = unsafe { MaybeUninit::zeroed().assume_init() };
+    value.get()
+}
+
+/// Valid values of the same types.
+pub fn read_valid_values() -> u32 {
+    let byte: u8 = black_box(1);
+    let flag = unsafe { *(&byte as *const u8 as *const bool) };
+    let tag: u8 = black_box(5);
+    let sparse = unsafe { *(&tag as *const u8 as *const Sparse) };
+    let value = NonZeroU32::new(black_box(7)).unwrap();
+    let code = black_box('a');
+    flag as u32 + sparse as u32 + value.get() + code as u32
+}
+
+/// `mem::zeroed` checks that zero is valid before the value is read.
+pub fn zeroed_ref() -> u8 {
+    let reference: &u8 = unsafe { std::mem::zeroed() };
+    *reference
+}
@@ -167,14 +167,17 @@ pub fn test_external_static() {
     assert!(dep_rust_abi::MAGIC == 42);
 }
 
-/// Access an external mutable static (triggers divergence warning).
+/// Access an external mutable static, which is shared with native code.
 pub fn test_external_mutable_static() {
     unsafe {
-        assert!(dep_rust_abi::MUTABLE_COUNTER == 0);
+        let start = dep_rust_abi::MUTABLE_COUNTER;
+        dep_rust_abi::MUTABLE_COUNTER += 10;
+        assert!(dep_rust_abi::increment_counter() == start + 11);
+        assert!(dep_rust_abi::MUTABLE_COUNTER == start + 11);
     }
 }
 
-/// Access an immutable static containing a mutable pointer (triggers divergence warning).
+/// Access an immutable static containing a mutable pointer, which is shared with native code.
 pub fn test_static_with_mut_ptr() {
     assert!(dep_rust_abi::STATIC_WITH_PTR.0 == std::ptr::null_mut());
 }
@@ -201,3 +204,32 @@ pub fn test_return_mut_padded() {
     let mut p = dep_rust_abi::Padded { a: 0, b: 0 };
     let _ = dep_rust_abi::get_mut_padded(&mut p);
 }
+
+// --- Function pointers ---
+
+pub fn test_native_fn_ptr() {
+    let double = dep_rust_abi::get_doubler();
+    assert_eq!(double(21), 42);
+}
+
+// --- Panics ---
+
+pub fn test_native_panic() {
+    dep_rust_abi::checked_half(3);
+}
+
+pub fn test_catch_native_panic() {
+    let result = std::panic::catch_unwind(|| dep_rust_abi::checked_half(3));
+    let payload = result.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"odd value"));
+    assert_eq!(dep_rust_abi::checked_half(4), 2);
+}
+
+// --- Track caller ---
+
+pub fn test_native_track_caller() {
+    let location = dep_rust_abi::caller_location();
+    assert_eq!(location.line(), line!() - 1);
+    assert_eq!(location.column(), 20);
+    assert!(location.file().ends_with("call_rust_abi.rs"));
+}
@@ -200,3 +200,29 @@ pub fn read_wraps_mut(w: &WrapsMutPadded) -> u8 {
 pub fn get_mut_padded(p: &mut Padded) -> *mut Padded {
     p as *mut Padded
 }
+
+// --- Function pointers ---
+
+fn double(x: u32) -> u32 {
+    x * 2
+}
+
+pub fn get_doubler() -> fn(u32) -> u32 {
+    double
+}
+
+// --- Panics ---
+
+pub fn checked_half(x: u32) -> u32 {
+    if x % 2 != 0 {
+        panic!("odd value");
+    }
+    x / 2
+}
+
+// --- Track caller ---
+
+#[track_caller]
+pub fn caller_location() -> &'static std::panic::Location<'static> {
+    std::panic::Location::caller()
+}
@@ -0,0 +1,103 @@
+#![allow(unused)]
+use std::hint::black_box;
+
+/// A wire-format header where every multi-byte field is misaligned.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct Header {
+    tag: u8,
+    len: u32,
+    id: u64,
+}
+
+#[derive(Clone, Copy)]
+pub struct Point {
+    x: u16,
+    y: u32,
+}
+
+#[repr(C, packed)]
+pub struct Record {
+    kind: u8,
+    point: Point,
+    values: [u32; 2],
+}
+
+#[repr(C, packed(2))]
+pub struct Pair {
+    flag: u8,
+    value: u64,
+}
+
+#[repr(C, packed)]
+pub struct Indirect {
+    tag: u8,
+    ptr: *const u32,
+}
+
+/// Read and write the fields of a packed struct.
+pub fn test_packed_fields() -> u64 {
+    let mut header = Header {
+        tag: 1,
+        len: black_box(10),
+        id: 100,
+    };
+    header.len += 5;
+    header.id *= 2;
+    header.tag as u64 + header.len as u64 + header.id
+}
+
+/// Fields of a struct nested inside a packed struct are misaligned too.
+pub fn test_nested_fields() -> u32 {
+    let mut record = Record {
+        kind: 2,
+        point: Point { x: 3, y: 4 },
+        values: [5, 6],
+    };
+    record.point.y = black_box(40);
+    record.values[1] = 60;
+    let i = black_box(0);
+    record.values[i] += 50;
+    record.kind as u32 + record.point.x as u32 + record.point.y + record.values[0] + record.values[1]
+}
+
+/// Packed structs in an array start at odd addresses.
+pub fn test_packed_array() -> u64 {
+    let mut headers = [Header { tag: 0, len: 0, id: 0 }; 3];
+    for (i, header) in headers.iter_mut().enumerate() {
+        header.len = i as u32;
+        header.id = 10 * i as u64;
+    }
+    headers.iter().map(|header| header.len as u64 + header.id).sum()
+}
+
+/// `packed(2)` lowers the alignment of the fields to 2.
+pub fn test_packed_n() -> u64 {
+    let mut pair = Pair { flag: 1, value: 0 };
+    pair.value = black_box(41);
+    pair.flag as u64 + pair.value
+}
+
+/// Copying a packed struct out of an unaligned location.
+pub fn test_packed_copy() -> u32 {
+    let headers = [Header { tag: 0, len: 7, id: 0 }; 2];
+    let copy = headers[1];
+    copy.len
+}
+
+/// A pointer stored in a packed field can be read, and its pointee is aligned.
+pub fn test_packed_pointer_field() -> u32 {
+    let value = 33u32;
+    let indirect = Indirect {
+        tag: 1,
+        ptr: &value,
+    };
+    unsafe { *indirect.ptr }
+}
+
+/// Reading a packed field through a raw pointer requires `read_unaligned`.
+pub fn test_packed_field_raw_ptr() -> u32 {
+    let header = Header { tag: 0, len: 12, id: 0 };
+    let ptr = &raw const header.len;
+    unsafe { ptr.read_unaligned() }
+}
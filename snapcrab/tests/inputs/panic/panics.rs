@@ -0,0 +1,83 @@
+#![allow(unused)]
+use std::hint::black_box;
+
+pub fn test_explicit_panic() {
+    panic!("boom");
+}
+
+pub fn test_unreachable() {
+    unreachable!();
+}
+
+pub fn test_unwrap_none() -> u32 {
+    let x: Option<u32> = black_box(None);
+    x.unwrap()
+}
+
+pub fn test_expect_none() -> u32 {
+    let x: Option<u32> = black_box(None);
+    x.expect("value must be present")
+}
+
+pub fn test_assert() {
+    let x = black_box(1);
+    assert!(x == 2);
+}
+
+pub fn test_index_out_of_bounds() -> u8 {
+    let arr = [1u8, 2, 3];
+    let idx = black_box(5);
+    arr[idx]
+}
+
+pub fn test_add_overflow() -> u8 {
+    let x: u8 = black_box(255);
+    x + 1
+}
+
+#[track_caller]
+fn checked_get(values: &[u32], idx: usize) -> u32 {
+    match values.get(idx) {
+        Some(v) => *v,
+        None => None::<u32>.unwrap(),
+    }
+}
+
+pub fn test_track_caller_location() -> u32 {
+    let values = [1, 2];
+    checked_get(&values, 3)
+}
+
+pub fn test_abort() {
+    std::process::abort();
+}
+
+pub fn test_no_panic() -> u32 {
+    let x: Option<u32> = black_box(Some(3));
+    x.unwrap()
+}
+
+pub fn test_internal_error() {
+    unsafe { std::arch::asm!("nop") };
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl std::fmt::Display for Point {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+pub fn test_format_panic() {
+    let x = black_box(7);
+    panic!("bad value {}", x);
+}
+
+pub fn test_format_panic_display() {
+    let point = black_box(Point { x: 1, y: -2 });
+    panic!("bad point {point} {:?} {:>5.1}", Some("s"), 2.25f64);
+}
@@ -0,0 +1,86 @@
+//! Test unwinding through cleanup blocks and catching panics.
+use std::cell::Cell;
+use std::panic;
+
+/// Count how many times it was dropped.
+struct Count<'a>(&'a Cell<u32>);
+
+impl Drop for Count<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+/// Abort if the counter doesn't have the expected value when dropped.
+struct Check<'a>(&'a Cell<u32>, u32);
+
+impl Drop for Check<'_> {
+    fn drop(&mut self) {
+        if self.0.get() != self.1 {
+            std::process::abort();
+        }
+    }
+}
+
+struct AbortOnDrop;
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        std::process::abort();
+    }
+}
+
+struct PanicOnDrop;
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("drop panicked");
+    }
+}
+
+fn nested(count: &Cell<u32>) {
+    let _count = Count(count);
+    panic!("nested panic");
+}
+
+pub fn test_unwind_runs_drop() {
+    let _guard = AbortOnDrop;
+    panic!("unwinding");
+}
+
+pub fn test_unwind_drops_in_order() {
+    let count = Cell::new(0);
+    let _check = Check(&count, 2);
+    let _count = Count(&count);
+    nested(&count);
+}
+
+pub fn test_panic_in_cleanup() {
+    let _guard = PanicOnDrop;
+    panic!("first panic");
+}
+
+pub fn test_catch_unwind() -> bool {
+    let result = panic::catch_unwind(|| {
+        panic!("caught");
+    });
+    result.is_err()
+}
+
+pub fn test_catch_unwind_no_panic() -> u32 {
+    panic::catch_unwind(|| 42).unwrap()
+}
+
+pub fn test_catch_unwind_payload() -> bool {
+    let count = Cell::new(0);
+    let payload = panic::catch_unwind(panic::AssertUnwindSafe(|| nested(&count))).unwrap_err();
+    count.get() == 1 && payload.downcast_ref::<&str>() == Some(&"nested panic")
+}
+
+pub fn test_resume_unwind() {
+    let payload = panic::catch_unwind(|| {
+        panic!("resumed");
+    })
+    .unwrap_err();
+    panic::resume_unwind(payload);
+}
@@ -45,3 +45,37 @@ pub fn test_tuple_field_ref() -> (i8, i8) {
     let r = &tuple.0;
     (*r, tuple.2)
 }
+
+pub fn test_misaligned_ref() -> usize {
+    let data = [0u32; 2];
+    let ptr = (data.as_ptr() as *const u8).wrapping_add(1) as *const u32;
+    let r = unsafe { std::slice::from_raw_parts(ptr, 1) };
+    r.as_ptr().addr() - data.as_ptr().addr()
+}
+
+pub fn test_out_of_bounds_ref() -> usize {
+    let value = Box::new(7u32);
+    let ptr = &*value as *const u32 as *const [u32; 2];
+    let r = unsafe { &*ptr };
+    r.len()
+}
+
+pub fn test_dangling_ref() -> usize {
+    let ptr = Box::into_raw(Box::new(5u64));
+    unsafe { drop(Box::from_raw(ptr)) };
+    let r = unsafe { &*ptr };
+    (r as *const u64).addr() - ptr.addr()
+}
+
+pub fn test_dangling_raw_ptr() -> usize {
+    let ptr = Box::into_raw(Box::new(5u64));
+    unsafe { drop(Box::from_raw(ptr)) };
+    let raw = &raw const *ptr;
+    raw.addr() - ptr.addr()
+}
+
+pub fn test_zero_sized_ref() -> usize {
+    let ptr = std::ptr::NonNull::<()>::dangling().as_ptr();
+    let r = unsafe { &*ptr };
+    std::mem::size_of_val(r)
+}
@@ -32,3 +32,76 @@ pub fn test_byte_array_ref() {
     assert!(bytes[2] == b's');
     assert!(bytes[3] == b't');
 }
+
+static mut COUNTER: u32 = 0;
+
+/// `static mut` items can be written.
+pub fn test_static_mut_counter() -> u32 {
+    for _ in 0..3u32 {
+        unsafe { COUNTER += 2 };
+    }
+    unsafe { COUNTER }
+}
+
+static HITS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(10);
+
+/// Statics with interior mutability can be written through shared references.
+pub fn test_static_atomic() -> usize {
+    use std::sync::atomic::Ordering;
+    HITS.fetch_add(5, Ordering::Relaxed);
+    HITS.fetch_sub(1, Ordering::Relaxed);
+    HITS.load(Ordering::Relaxed)
+}
+
+struct SyncCell(std::cell::Cell<u64>);
+
+// SAFETY: the tests are single-threaded.
+unsafe impl Sync for SyncCell {}
+
+static CELL: SyncCell = SyncCell(std::cell::Cell::new(1));
+
+pub fn test_static_cell() -> u64 {
+    CELL.0.set(CELL.0.get() * 7);
+    CELL.0.replace(3) + CELL.0.get()
+}
+
+static REGISTRY: std::sync::atomic::AtomicPtr<Vec<&'static str>> =
+    std::sync::atomic::AtomicPtr::new(std::ptr::null_mut());
+
+/// Get the registry, initializing it on first use.
+fn registry(init: &[&'static str]) -> &'static Vec<&'static str> {
+    use std::sync::atomic::Ordering;
+    let mut ptr = REGISTRY.load(Ordering::Acquire);
+    if ptr.is_null() {
+        let new = Box::into_raw(Box::new(init.to_vec()));
+        ptr = match REGISTRY.compare_exchange(
+            std::ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            Err(existing) => {
+                drop(unsafe { Box::from_raw(new) });
+                existing
+            }
+        };
+    }
+    unsafe { &*ptr }
+}
+
+/// A lazily initialized registry is only initialized once.
+pub fn test_static_lazy_registry() -> usize {
+    let first = registry(&["alpha", "beta"]);
+    let second = registry(&["gamma"]);
+    first.len() + second.len()
+}
+
+static FROZEN: u32 = 5;
+
+/// Immutable statics are read-only.
+pub fn test_write_immutable_static() -> u32 {
+    let ptr = &FROZEN as *const u32 as *mut u32;
+    unsafe { ptr.write(6) };
+    FROZEN
+}
@@ -0,0 +1,187 @@
+//! Programs whose threads block on each other, which are run with a thread
+//! scheduler.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+/// Threads that contend for a mutex all get their turn.
+pub fn test_mutex_contention() -> usize {
+    let counter = Arc::new(Mutex::new(0));
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    let mut guard = counter.lock().unwrap();
+                    let value = *guard;
+                    thread::yield_now();
+                    *guard = value + 1;
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let count = *counter.lock().unwrap();
+    count
+}
+
+/// A consumer waits on a condition variable for the items of a producer.
+pub fn test_condvar() -> u32 {
+    let queue = Arc::new((Mutex::new(Vec::new()), Condvar::new()));
+    let producer = {
+        let queue = Arc::clone(&queue);
+        thread::spawn(move || {
+            for item in 1..=5 {
+                queue.0.lock().unwrap().push(item);
+                queue.1.notify_one();
+            }
+        })
+    };
+    let mut sum = 0;
+    let mut received = 0;
+    while received < 5 {
+        let guard = queue.0.lock().unwrap();
+        let mut items = queue.1.wait_while(guard, |items| items.is_empty()).unwrap();
+        for item in items.drain(..) {
+            sum += item;
+            received += 1;
+        }
+    }
+    producer.join().unwrap();
+    sum
+}
+
+/// Readers and writers share a read-write lock.
+pub fn test_rwlock() -> usize {
+    let lock = Arc::new(RwLock::new(0));
+    let writers: Vec<_> = (0..2)
+        .map(|_| {
+            let lock = Arc::clone(&lock);
+            thread::spawn(move || {
+                for _ in 0..5 {
+                    *lock.write().unwrap() += 1;
+                }
+            })
+        })
+        .collect();
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let lock = Arc::clone(&lock);
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..5 {
+                    let value = *lock.read().unwrap();
+                    assert!(value >= last);
+                    last = value;
+                }
+            })
+        })
+        .collect();
+    for handle in writers.into_iter().chain(readers) {
+        handle.join().unwrap();
+    }
+    let value = *lock.read().unwrap();
+    value
+}
+
+/// A parked thread waits for its unpark.
+pub fn test_park_unpark() -> bool {
+    let flag = Arc::new(AtomicBool::new(false));
+    let parked = {
+        let flag = Arc::clone(&flag);
+        thread::spawn(move || {
+            while !flag.load(Ordering::Acquire) {
+                thread::park();
+            }
+        })
+    };
+    flag.store(true, Ordering::Release);
+    parked.thread().unpark();
+    parked.join().is_ok()
+}
+
+/// A thread that sleeps lets the other threads run.
+pub fn test_sleep() -> bool {
+    let flag = Arc::new(AtomicBool::new(false));
+    let setter = {
+        let flag = Arc::clone(&flag);
+        thread::spawn(move || flag.store(true, Ordering::Release))
+    };
+    while !flag.load(Ordering::Acquire) {
+        thread::sleep(Duration::from_secs(3600));
+    }
+    setter.join().is_ok()
+}
+
+/// A thread spins on an atomic until its parent sets it.
+pub fn test_spin_wait() -> bool {
+    let flag = Arc::new(AtomicBool::new(false));
+    let spinner = {
+        let flag = Arc::clone(&flag);
+        thread::spawn(move || {
+            while !flag.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+        })
+    };
+    flag.store(true, Ordering::Release);
+    spinner.join().is_ok()
+}
+
+/// Two threads record the order in which they take turns. Each turn is a
+/// bit, which is set for the turns of the second thread.
+pub fn test_interleaving() -> u32 {
+    let turns = Arc::new(Mutex::new(Vec::new()));
+    thread::scope(|s| {
+        for id in 0..2 {
+            let turns = &turns;
+            s.spawn(move || {
+                for _ in 0..4 {
+                    turns.lock().unwrap().push(id);
+                }
+            });
+        }
+    });
+    let turns = turns.lock().unwrap();
+    turns.iter().fold(0, |bits, id| (bits << 1) | id)
+}
+
+/// Two threads increment a counter with a separate load and store, so
+/// increments are lost when their accesses interleave.
+pub fn test_lost_update() -> usize {
+    let counter = AtomicUsize::new(0);
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                for _ in 0..10 {
+                    let value = counter.load(Ordering::Relaxed);
+                    counter.store(value + 1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    counter.load(Ordering::Relaxed)
+}
+
+/// The main thread joins a thread that waits for a lock held by the main
+/// thread.
+pub fn test_join_deadlock() {
+    let lock = Arc::new(Mutex::new(()));
+    let _guard = lock.lock().unwrap();
+    let worker = {
+        let lock = Arc::clone(&lock);
+        thread::spawn(move || drop(lock.lock().unwrap()))
+    };
+    worker.join().unwrap();
+}
+
+/// A thread that locks a mutex it already holds blocks forever.
+pub fn test_relock() {
+    let lock = Mutex::new(());
+    let _first = lock.lock().unwrap();
+    let _second = lock.lock().unwrap();
+}
@@ -0,0 +1,127 @@
+#![allow(unused)]
+
+use std::cell::Cell;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+/// The result of the closure is returned by `join`.
+pub fn test_spawn_join() -> u32 {
+    let handle = thread::spawn(|| black_box(40) + 2);
+    handle.join().unwrap()
+}
+
+/// Threads share the heap through an `Arc`.
+pub fn test_spawn_shared_counter() -> usize {
+    let counter = Arc::new(Mutex::new(0));
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || *counter.lock().unwrap() += i)
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let total = *counter.lock().unwrap();
+    total
+}
+
+/// Scoped threads can borrow from the parent stack.
+pub fn test_scope() -> usize {
+    let data = vec![1, 2, 3, 4];
+    let sum = AtomicUsize::new(0);
+    let sum = &sum;
+    thread::scope(|s| {
+        for chunk in data.chunks(2) {
+            s.spawn(move || sum.fetch_add(chunk.iter().sum(), Ordering::Relaxed));
+        }
+    });
+    sum.load(Ordering::Relaxed)
+}
+
+/// Scoped threads can be joined explicitly.
+pub fn test_scope_join() -> usize {
+    let data = vec![1, 2, 3];
+    thread::scope(|s| {
+        let handle = s.spawn(|| data.len());
+        handle.join().unwrap()
+    })
+}
+
+/// A panic in the spawned thread is returned by `join`.
+pub fn test_join_panic() -> bool {
+    let handle = thread::spawn(|| panic!("boom"));
+    let payload = handle.join().unwrap_err();
+    payload.downcast_ref::<&str>() == Some(&"boom")
+}
+
+/// A panic in a scoped thread that isn't joined makes the scope panic.
+pub fn test_scope_panic() {
+    thread::scope(|s| {
+        s.spawn(|| panic!("boom"));
+    });
+}
+
+thread_local! {
+    static ID: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Each thread has its own copy of thread-local statics.
+pub fn test_thread_local_per_thread() -> u32 {
+    ID.set(1);
+    let child = thread::spawn(|| {
+        ID.set(ID.get() + 10);
+        ID.get()
+    });
+    child.join().unwrap() + ID.get()
+}
+
+/// The spawned thread waits for a value that its parent sends afterwards.
+pub fn test_handoff() -> u32 {
+    let slot = Arc::new((Mutex::new(None::<u32>), Condvar::new()));
+    let child = {
+        let slot = Arc::clone(&slot);
+        thread::spawn(move || {
+            let (lock, ready) = &*slot;
+            let value = ready.wait_while(lock.lock().unwrap(), |value| value.is_none());
+            value.unwrap().unwrap() + 1
+        })
+    };
+    *slot.0.lock().unwrap() = Some(41);
+    slot.1.notify_one();
+    child.join().unwrap()
+}
+
+/// A detached thread keeps running after its handle is dropped.
+pub fn test_detach() -> u32 {
+    let slot = Arc::new((Mutex::new(0u32), Condvar::new()));
+    let started = Arc::new(AtomicBool::new(false));
+    {
+        let slot = Arc::clone(&slot);
+        let started = Arc::clone(&started);
+        drop(thread::spawn(move || {
+            while !started.load(Ordering::Acquire) {
+                thread::yield_now();
+            }
+            *slot.0.lock().unwrap() = 42;
+            slot.1.notify_one();
+        }));
+    }
+    started.store(true, Ordering::Release);
+    let value = *slot.1.wait_while(slot.0.lock().unwrap(), |value| *value == 0).unwrap();
+    value
+}
+
+/// Threads see their own handle through `thread::current`. Each check sets a
+/// bit.
+pub fn test_current_thread() -> u8 {
+    let main = thread::current();
+    let child = thread::spawn(|| thread::current().id());
+    let child_id = child.thread().id();
+    let id = child.join().unwrap();
+    ((id == child_id) as u8)
+        | ((id != main.id()) as u8) << 1
+        | ((thread::current().id() == main.id()) as u8) << 2
+}
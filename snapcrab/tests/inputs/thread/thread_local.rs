@@ -0,0 +1,66 @@
+#![allow(unused)]
+#![feature(thread_local)]
+
+use std::cell::{Cell, RefCell};
+use std::hint::black_box;
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = Cell::new(black_box(100));
+    static CONST_COUNTER: Cell<u32> = const { Cell::new(7) };
+    static LOG: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    static PANIC_ON_DROP: RefCell<Option<PanicOnDrop>> = const { RefCell::new(None) };
+}
+
+#[thread_local]
+static mut RAW_COUNTER: u32 = 1;
+
+/// Lazily initialized `thread_local!` cache.
+pub fn test_lazy_thread_local() -> u64 {
+    let next_id = || NEXT_ID.with(|id| id.replace(id.get() + 1));
+    assert!(next_id() == 100);
+    assert!(next_id() == 101);
+    next_id()
+}
+
+/// `thread_local!` with a `const` initializer.
+pub fn test_const_thread_local() -> u32 {
+    CONST_COUNTER.set(CONST_COUNTER.get() * 6);
+    CONST_COUNTER.get()
+}
+
+/// `#[thread_local]` statics are initialized from the static's initializer.
+pub fn test_thread_local_attribute() -> u32 {
+    unsafe {
+        RAW_COUNTER += 41;
+        RAW_COUNTER
+    }
+}
+
+/// Values that need dropping are dropped when the thread exits, so the
+/// vector doesn't leak.
+pub fn test_thread_local_destructor() -> usize {
+    LOG.with_borrow_mut(|log| {
+        log.push("first".to_string());
+        log.push("second".to_string());
+    });
+    LOG.with_borrow(|log| log.len())
+}
+
+pub struct PanicOnDrop;
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("dropped at thread exit");
+    }
+}
+
+/// Destructors run after the thread's main function returns.
+pub fn test_destructor_runs_at_exit() {
+    PANIC_ON_DROP.set(Some(PanicOnDrop));
+}
+
+/// Destructors also run when a panic unwinds out of the thread.
+pub fn test_destructor_runs_after_panic() {
+    PANIC_ON_DROP.set(Some(PanicOnDrop));
+    panic!("main panicked");
+}
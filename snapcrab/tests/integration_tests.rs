@@ -4,10 +4,19 @@
 mod common;
 
 mod integration_tests {
+    mod cli_tests;
+    mod closure_tests;
+    mod drop_tests;
+    mod dyn_trait_tests;
     mod enum_tests;
+    mod float_tests;
+    mod fn_ptr_tests;
+    mod heap_tests;
     mod intrinsics_tests;
     mod native_tests;
+    mod panic_tests;
     mod std_tests;
+    mod thread_tests;
 }
 
 use common::TestResult;
@@ -170,6 +179,55 @@ check_custom_start!(
     result = TestResult::SuccessWithValue(vec![99, 0, 0, 0])
 );
 
+// References are checked when they are created, not when they are used.
+check_custom_start!(
+    test_misaligned_reference,
+    input = "reference_test.rs",
+    start_fn = "test_misaligned_ref",
+    // Skip the precondition check of `from_raw_parts`, which would abort first.
+    check_config = snapcrab::CheckConfig {
+        ub_checks: false,
+        ..Default::default()
+    },
+    result = TestResult::ErrorRegex(
+        r"(?s).*invalid reference to `\[u32\]`: Misaligned.*reference_test.rs:52:22.*".to_string()
+    )
+);
+
+check_custom_start!(
+    test_out_of_bounds_reference,
+    input = "reference_test.rs",
+    start_fn = "test_out_of_bounds_ref",
+    result = TestResult::ErrorRegex(
+        r"(?s).*invalid reference to `\[u32; 2\]`: 8 bytes.*out of bounds.*reference_test.rs:59:22.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_dangling_reference,
+    input = "reference_test.rs",
+    start_fn = "test_dangling_ref",
+    result = TestResult::ErrorRegex(
+        r"(?s).*invalid reference to `u64`: dangling reference.*reference_test.rs:66:22.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_dangling_raw_pointer,
+    input = "reference_test.rs",
+    start_fn = "test_dangling_raw_ptr",
+    result = TestResult::SuccessWithValue(0usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_zero_sized_reference,
+    input = "reference_test.rs",
+    start_fn = "test_zero_sized_ref",
+    result = TestResult::SuccessWithValue(0usize.to_le_bytes().to_vec())
+);
+
 check_custom_start!(
     test_tuple_field_sub,
     input = "tuple_operations.rs",
@@ -204,7 +262,7 @@ check_custom_start!(
     test_read_misaligned_ptr,
     input = "raw_pointer_test.rs",
     start_fn = "read_misaligned_ptr",
-    result = TestResult::ErrorRegex(r".*Unsupported.*Offset.*".to_string())
+    result = TestResult::SuccessWithValue(vec![0, 0, 0, 0])
 );
 
 check_custom_start!(
@@ -755,7 +813,6 @@ check_custom_start!(
 );
 
 check_custom_start!(
-    #[ignore]
     test_static_str_slice_len,
     input = "static_data.rs",
     start_fn = "test_static_str_slice_len",
@@ -767,6 +824,41 @@ check_custom_start!(
     start_fn = "test_byte_array_ref",
 );
 
+check_custom_start!(
+    test_static_mut_counter,
+    input = "static_data.rs",
+    start_fn = "test_static_mut_counter",
+    result = TestResult::SuccessWithValue(6u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_static_atomic,
+    input = "static_data.rs",
+    start_fn = "test_static_atomic",
+    result = TestResult::SuccessWithValue(14usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_static_cell,
+    input = "static_data.rs",
+    start_fn = "test_static_cell",
+    result = TestResult::SuccessWithValue(10u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_static_lazy_registry,
+    input = "static_data.rs",
+    start_fn = "test_static_lazy_registry",
+    result = TestResult::SuccessWithValue(4usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_write_immutable_static,
+    input = "static_data.rs",
+    start_fn = "test_write_immutable_static",
+    result = TestResult::ErrorRegex(r".*write to immutable static memory.*".to_string())
+);
+
 // Hello world test (exercises println! → fmt machinery → native calls)
 check_interpreter!(
     test_hello_world,
@@ -827,7 +919,6 @@ check_custom_start!(
 );
 
 check_custom_start!(
-    #[ignore]
     test_wrapper_dyn_debug,
     input = "wide_ptr.rs",
     start_fn = "test_wrapper_dyn_debug",
@@ -888,10 +979,146 @@ check_custom_start!(
     start_fn = "test_slice_from_end",
 );
 
-// Panic handling tests (exercises native call path via unwrap_failed)
+// Panic handling tests (unwrap_failed is served by the panic runtime)
 check_custom_start!(
     test_unwrap_none_panics,
     input = "unwrap_panic.rs",
     start_fn = "unwrap_none",
-    result = TestResult::ErrorRegex(r".*#\[track_caller\] are not yet supported.*".to_string())
+    result = TestResult::ErrorRegex(
+        r"(?s)thread 'main' panicked at .*unwrap_panic.rs:4:5:
+called `Option::unwrap\(\)` on a `None` value"
+            .to_string()
+    )
+);
+
+// Validity checks on typed loads
+fn load_validity_config() -> snapcrab::CheckConfig {
+    snapcrab::CheckConfig {
+        load_validity: snapcrab::CheckLevel::Error,
+        ..Default::default()
+    }
+}
+
+check_custom_start!(
+    test_load_invalid_bool_unchecked,
+    input = "load_validity.rs",
+    start_fn = "read_invalid_bool",
+    result = TestResult::SuccessWithValue(vec![3])
+);
+
+// Only the strict checks on loads reject values in the valid range of the layout.
+check_custom_start!(
+    test_transmute_surrogate_char_unchecked,
+    input = "load_validity.rs",
+    start_fn = "transmute_surrogate_char",
+    result = TestResult::SuccessWithValue(0xD800u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_load_invalid_bool,
+    input = "load_validity.rs",
+    start_fn = "read_invalid_bool",
+    check_config = load_validity_config(),
+    result =
+        TestResult::ErrorRegex(r".*read an invalid value of type `bool`.*value 0x3.*".to_string())
+);
+
+check_custom_start!(
+    test_load_surrogate_char,
+    input = "load_validity.rs",
+    start_fn = "read_surrogate_char",
+    check_config = load_validity_config(),
+    result = TestResult::ErrorRegex(r".*Invalid char value 0xd800.*".to_string())
+);
+
+check_custom_start!(
+    test_load_invalid_enum_tag,
+    input = "load_validity.rs",
+    start_fn = "read_invalid_enum_tag",
+    check_config = load_validity_config(),
+    result = TestResult::ErrorRegex(r".*Invalid enum tag 0x3 for type.*Sparse.*".to_string())
+);
+
+check_custom_start!(
+    test_load_null_ref,
+    input = "load_validity.rs",
+    start_fn = "assume_init_null_ref",
+    check_config = load_validity_config(),
+    result =
+        TestResult::ErrorRegex(r".*read an invalid value of type `&u8`.*value 0x0.*".to_string())
+);
+
+check_custom_start!(
+    test_load_zero_nonzero,
+    input = "load_validity.rs",
+    start_fn = "assume_init_zero_nonzero",
+    check_config = load_validity_config(),
+    result = TestResult::ErrorRegex(r".*read an invalid value of type.*NonZero.*".to_string())
+);
+
+check_custom_start!(
+    test_load_valid_values,
+    input = "load_validity.rs",
+    start_fn = "read_valid_values",
+    check_config = load_validity_config(),
+    result = TestResult::SuccessWithValue(110u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_zeroed_ref,
+    input = "load_validity.rs",
+    start_fn = "zeroed_ref",
+    result = TestResult::ErrorRegex(
+        r"(?s)panicked at .*load_validity.rs:62:35:\nattempted to zero-initialize type `&u8`, which is invalid\n".to_string()
+    )
+);
+
+// Packed structs
+check_custom_start!(
+    test_packed_fields,
+    input = "packed_struct.rs",
+    start_fn = "test_packed_fields",
+    result = TestResult::SuccessWithValue(216u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_packed_nested_fields,
+    input = "packed_struct.rs",
+    start_fn = "test_nested_fields",
+    result = TestResult::SuccessWithValue(160u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_packed_array,
+    input = "packed_struct.rs",
+    start_fn = "test_packed_array",
+    result = TestResult::SuccessWithValue(33u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_packed_n,
+    input = "packed_struct.rs",
+    start_fn = "test_packed_n",
+    result = TestResult::SuccessWithValue(42u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_packed_copy,
+    input = "packed_struct.rs",
+    start_fn = "test_packed_copy",
+    result = TestResult::SuccessWithValue(7u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_packed_pointer_field,
+    input = "packed_struct.rs",
+    start_fn = "test_packed_pointer_field",
+    result = TestResult::SuccessWithValue(33u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_packed_field_raw_ptr,
+    input = "packed_struct.rs",
+    start_fn = "test_packed_field_raw_ptr",
+    result = TestResult::SuccessWithValue(12u32.to_le_bytes().to_vec())
 );
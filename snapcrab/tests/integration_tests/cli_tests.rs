@@ -0,0 +1,155 @@
+//! Tests that run the `snapcrab` binary.
+//!
+//! `RUSTC_BOOTSTRAP` is cleared, so the flags we pass to the compiler must be
+//! accepted by a stable toolchain.
+
+use std::process::{Command, Output};
+
+/// Build the command that runs `snapcrab` on `input` with the given start
+/// function and flags.
+fn snapcrab_command(input: &str, start_fn: &str, flags: &[&str]) -> Command {
+    let input_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("inputs")
+        .join(input);
+    let mut command = Command::new(env!("CARGO_BIN_EXE_snapcrab"));
+    command
+        .args(flags)
+        .args(["--start-fn", start_fn])
+        .arg(input_path)
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .env_remove("RUSTC_BOOTSTRAP")
+        .env("SNAPCRAB_LOG", "info");
+    command
+}
+
+/// Run `snapcrab` on `input` with the given start function and flags.
+fn run_snapcrab(input: &str, start_fn: &str, flags: &[&str]) -> Output {
+    snapcrab_command(input, start_fn, flags)
+        .output()
+        .expect("Failed to run snapcrab")
+}
+
+#[test]
+fn test_cli_overflow_checks() {
+    let output = run_snapcrab("cli_checks.rs", "add_u8", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(101), "{stderr}");
+    assert!(stderr.contains("attempt to add with overflow"), "{stderr}");
+}
+
+#[test]
+fn test_cli_overflow_checks_disabled() {
+    let output = run_snapcrab("cli_checks.rs", "add_u8", &["--overflow-checks", "false"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Function 'add_u8' returned: 44"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_cli_ub_checks() {
+    let output = run_snapcrab("cli_checks.rs", "precondition", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(134), "{stderr}");
+    assert!(
+        stderr.contains("unsafe precondition(s) violated"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_cli_ub_checks_disabled() {
+    // The misaligned slice reference is still caught when it is created.
+    let flags = ["--ub-checks", "false", "--skip-check", "alignment"];
+    let output = run_snapcrab("cli_checks.rs", "precondition", &flags);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Function 'precondition' returned: 1"),
+        "{stdout}"
+    );
+}
+
+/// Return the seed of the `--schedule-seed` note printed by a failed run.
+fn replay_seed(stderr: &str) -> Option<u64> {
+    let note = stderr.lines().find_map(|line| {
+        line.strip_prefix("note: run with `--schedule-seed ")?
+            .strip_suffix("` to replay this thread interleaving")
+    })?;
+    Some(note.parse().expect("Invalid seed in the replay note"))
+}
+
+#[test]
+fn test_cli_schedule_seed_failure() {
+    let output = run_snapcrab("cli_checks.rs", "lost_update", &["--schedule-seed", "1"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(101), "{stderr}");
+    assert!(stderr.contains("lost an update"), "{stderr}");
+    assert_eq!(replay_seed(&stderr), Some(1), "{stderr}");
+}
+
+#[test]
+fn test_cli_schedule_seed_success() {
+    // This interleaving doesn't lose an update, and successful runs don't
+    // print the seed.
+    let output = run_snapcrab("cli_checks.rs", "lost_update", &["--schedule-seed", "4"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(replay_seed(&stderr), None, "{stderr}");
+}
+
+#[test]
+fn test_cli_random_schedule() {
+    // Most interleavings lose an update, and the seed of a failed run replays
+    // the same failure.
+    let failure = (0..10)
+        .map(|_| run_snapcrab("cli_checks.rs", "lost_update", &["--random-schedule"]))
+        .find(|output| !output.status.success())
+        .expect("No random schedule lost an update");
+    let stderr = String::from_utf8_lossy(&failure.stderr);
+    assert_eq!(failure.status.code(), Some(101), "{stderr}");
+    let seed = replay_seed(&stderr)
+        .expect("Missing the replay note")
+        .to_string();
+    let replay = run_snapcrab("cli_checks.rs", "lost_update", &["--schedule-seed", &seed]);
+    assert_eq!(
+        String::from_utf8_lossy(&replay.stderr),
+        stderr,
+        "Seed {seed} did not replay the failure"
+    );
+}
+
+#[test]
+fn test_cli_schedule_native_call() {
+    // Threads don't take turns while `println!` holds the lock of stdout, so
+    // the lines they print are not torn. Logs would share the lock too.
+    for seed in ["1", "2", "3"] {
+        let output = snapcrab_command("cli_checks.rs", "print_display", &["--schedule-seed", seed])
+            .env("SNAPCRAB_LOG", "off")
+            .output()
+            .expect("Failed to run snapcrab");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        for line in ["0000 0000", "1111 1111", "2222 2222"] {
+            assert!(
+                stdout.lines().any(|printed| printed == line),
+                "Seed {seed} did not print `{line}`:\n{stdout}"
+            );
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::common::TestResult;
+
+check_custom_start!(
+    test_direct_call,
+    input = "closure/closure.rs",
+    start_fn = "test_direct_call",
+    result = TestResult::SuccessWithValue(32u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_generic_fn,
+    input = "closure/closure.rs",
+    start_fn = "test_generic_fn",
+    result = TestResult::SuccessWithValue(14u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_fn_mut,
+    input = "closure/closure.rs",
+    start_fn = "test_fn_mut",
+    result = TestResult::SuccessWithValue(10u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_fn_once,
+    input = "closure/closure.rs",
+    start_fn = "test_fn_once",
+    result = TestResult::SuccessWithValue(8u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_dyn_fn,
+    input = "closure/closure.rs",
+    start_fn = "test_dyn_fn",
+    result = TestResult::SuccessWithValue(17u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_fn_item_as_closure,
+    input = "closure/closure.rs",
+    start_fn = "test_fn_item_as_closure",
+    result = TestResult::SuccessWithValue(18u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_box_dyn_fn_once,
+    input = "closure/closure.rs",
+    start_fn = "test_box_dyn_fn_once",
+    result = TestResult::SuccessWithValue(1u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_iterator_chain,
+    input = "closure/closure.rs",
+    start_fn = "test_iterator_chain",
+    result = TestResult::SuccessWithValue(180u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_nested_closure,
+    input = "closure/closure.rs",
+    start_fn = "test_nested_closure",
+    result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_range_chain,
+    input = "closure/closure.rs",
+    start_fn = "test_range_chain",
+    result = TestResult::SuccessWithValue(84u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_option_combinators,
+    input = "closure/closure.rs",
+    start_fn = "test_option_combinators",
+    result = TestResult::SuccessWithValue(20u32.to_le_bytes().to_vec())
+);
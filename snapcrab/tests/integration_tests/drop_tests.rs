@@ -0,0 +1,51 @@
+// --- Drop glue ---
+
+check_custom_start!(
+    test_drop_impl,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_drop_impl",
+);
+
+check_custom_start!(
+    test_explicit_drop,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_explicit_drop",
+);
+
+check_custom_start!(
+    test_drop_order,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_drop_order",
+);
+
+check_custom_start!(
+    test_field_drop_order,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_field_drop_order",
+);
+
+check_custom_start!(
+    test_array_drop,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_array_drop",
+);
+
+check_custom_start!(
+    test_option_drop,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_option_drop",
+);
+
+check_custom_start!(
+    test_moved_value_not_dropped,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_moved_value_not_dropped",
+);
+
+// --- Intrinsics ---
+
+check_custom_start!(
+    test_needs_drop,
+    input = "drop/drop_glue.rs",
+    start_fn = "test_needs_drop",
+);
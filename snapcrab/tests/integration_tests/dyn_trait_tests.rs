@@ -0,0 +1,57 @@
+use crate::common::TestResult;
+
+check_custom_start!(
+    test_dyn_ref_dispatch,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_dyn_ref_dispatch",
+    result = TestResult::SuccessWithValue(19u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_dyn_default_method,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_dyn_default_method",
+    result = TestResult::SuccessWithValue(14u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_box_dyn,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_box_dyn",
+    result = TestResult::SuccessWithValue(20usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_box_dyn_drop,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_box_dyn_drop",
+    result = TestResult::SuccessWithValue(17u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_rc_dyn,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_rc_dyn",
+    result = TestResult::SuccessWithValue(86u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_upcast,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_upcast",
+    result = TestResult::SuccessWithValue(44u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_drop_auto_trait,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_drop_auto_trait",
+    result = TestResult::SuccessWithValue(9u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_dyn_size_of_val,
+    input = "dyn_trait/dyn_trait.rs",
+    start_fn = "test_dyn_size_of_val",
+    result = TestResult::SuccessWithValue(18usize.to_le_bytes().to_vec())
+);
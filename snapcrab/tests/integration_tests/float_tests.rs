@@ -0,0 +1,64 @@
+use crate::common::TestResult;
+
+check_custom_start!(
+    test_f64_arithmetic,
+    input = "float/float.rs",
+    start_fn = "test_f64_arithmetic",
+    result = TestResult::SuccessWithValue(35.125f64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_f32_arithmetic,
+    input = "float/float.rs",
+    start_fn = "test_f32_arithmetic",
+    result = TestResult::SuccessWithValue(1.0000001f32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_nan_comparisons,
+    input = "float/float.rs",
+    start_fn = "test_nan_comparisons",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_infinity,
+    input = "float/float.rs",
+    start_fn = "test_infinity",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_float_to_int_saturates,
+    input = "float/float.rs",
+    start_fn = "test_float_to_int_saturates",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_float_to_int_truncates,
+    input = "float/float.rs",
+    start_fn = "test_float_to_int_truncates",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_int_to_float,
+    input = "float/float.rs",
+    start_fn = "test_int_to_float",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_float_to_float,
+    input = "float/float.rs",
+    start_fn = "test_float_to_float",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_f16_f128,
+    input = "float/float.rs",
+    start_fn = "test_f16_f128",
+    result = TestResult::SuccessWithValue(5u32.to_le_bytes().to_vec())
+);
@@ -0,0 +1,66 @@
+use crate::common::TestResult;
+
+check_custom_start!(
+    test_reify_fn_ptr,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_reify_fn_ptr",
+    result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_generic_fn_ptr,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_generic_fn_ptr",
+    result = TestResult::SuccessWithValue(42u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_closure_fn_ptr,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_closure_fn_ptr",
+    result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_fn_ptr_argument,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_fn_ptr_argument",
+    result = TestResult::SuccessWithValue(16u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_fn_ptr_in_struct,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_fn_ptr_in_struct",
+    result = TestResult::SuccessWithValue(26u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_fn_ptr_in_static,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_fn_ptr_in_static",
+    result = TestResult::SuccessWithValue(15u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_fn_ptr_in_const,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_fn_ptr_in_const",
+    result = TestResult::SuccessWithValue(15u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_native_fn_ptr,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_native_fn_ptr",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_invalid_fn_ptr,
+    input = "fn_ptr/fn_ptr.rs",
+    start_fn = "test_invalid_fn_ptr",
+    result = TestResult::ErrorRegex(
+        r"^error: Undefined behavior: call through invalid function pointer 0x10\n.*".to_string()
+    )
+);
@@ -0,0 +1,157 @@
+use crate::common::TestResult;
+
+// --- Allocator entry points ---
+
+check_custom_start!(
+    test_heap_box_leak,
+    input = "heap/alloc.rs",
+    start_fn = "test_box_leak",
+);
+
+check_custom_start!(
+    test_heap_alloc_write_read,
+    input = "heap/alloc.rs",
+    start_fn = "test_alloc_write_read",
+);
+
+check_custom_start!(
+    test_heap_alloc_zeroed,
+    input = "heap/alloc.rs",
+    start_fn = "test_alloc_zeroed",
+);
+
+check_custom_start!(
+    test_heap_realloc_preserves_contents,
+    input = "heap/alloc.rs",
+    start_fn = "test_realloc_preserves_contents",
+);
+
+check_custom_start!(
+    test_heap_read_out_of_bounds,
+    input = "heap/alloc.rs",
+    start_fn = "test_read_out_of_bounds",
+    result = TestResult::ErrorRegex(r".*Heap memory access out of bounds.*".to_string())
+);
+
+check_custom_start!(
+    test_heap_read_out_of_bounds_warn,
+    input = "heap/alloc.rs",
+    start_fn = "test_read_back_out_of_bounds",
+    check_config = snapcrab::CheckConfig {
+        bounds: snapcrab::CheckLevel::Warn,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(2u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_heap_read_unmapped_out_of_bounds_off,
+    input = "heap/alloc.rs",
+    start_fn = "test_read_unmapped_out_of_bounds",
+    check_config = snapcrab::CheckConfig {
+        bounds: snapcrab::CheckLevel::Off,
+        ..Default::default()
+    },
+    result = TestResult::ErrorRegex(r".*Heap memory access out of bounds.*".to_string())
+);
+
+check_custom_start!(
+    test_heap_write_out_of_bounds,
+    input = "heap/alloc.rs",
+    start_fn = "test_write_out_of_bounds",
+    result = TestResult::ErrorRegex(r".*Heap memory access out of bounds.*".to_string())
+);
+
+check_custom_start!(
+    test_heap_write_out_of_bounds_off,
+    input = "heap/alloc.rs",
+    start_fn = "test_write_out_of_bounds",
+    check_config = snapcrab::CheckConfig {
+        bounds: snapcrab::CheckLevel::Off,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(1u32.to_le_bytes().to_vec())
+);
+
+// --- Invalid heap usage ---
+
+check_custom_start!(
+    test_heap_use_after_free,
+    input = "heap/invalid_free.rs",
+    start_fn = "test_use_after_free",
+    result = TestResult::ErrorRegex(
+        r"(?s).*use after free.*allocated at .*invalid_free.rs:7:.*freed at .*invalid_free.rs:9:.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_heap_double_free,
+    input = "heap/invalid_free.rs",
+    start_fn = "test_double_free",
+    result = TestResult::ErrorRegex(
+        r"(?s).*double free.*allocated at .*invalid_free.rs:17:.*freed at .*invalid_free.rs:18:.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_heap_dealloc_wrong_size,
+    input = "heap/invalid_free.rs",
+    start_fn = "test_dealloc_wrong_size",
+    result = TestResult::ErrorRegex(
+        r"(?s).*with size 4 and alignment 4, but it was allocated at .*invalid_free.rs:25:.* with size 8 and alignment 8.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_heap_dealloc_wrong_align,
+    input = "heap/invalid_free.rs",
+    start_fn = "test_dealloc_wrong_align",
+    result = TestResult::ErrorRegex(
+        r"(?s).*with size 8 and alignment 2, but it was allocated .* with size 8 and alignment 8.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_heap_stale_ptr_after_realloc,
+    input = "heap/invalid_free.rs",
+    start_fn = "test_stale_ptr_after_realloc",
+    result = TestResult::ErrorRegex(r"(?s).*use after free.*".to_string())
+);
+
+// --- Leak reporting ---
+
+check_custom_start!(
+    test_heap_leak_is_warning_by_default,
+    input = "heap/leak.rs",
+    start_fn = "test_forget_box",
+    result = TestResult::SuccessWithValue(vec![])
+);
+
+check_custom_start!(
+    test_heap_deny_leaks,
+    input = "heap/leak.rs",
+    start_fn = "test_forget_box",
+    run_config = snapcrab::RunConfig {
+        deny_leaks: true,
+        ..Default::default()
+    },
+    result = TestResult::ErrorRegex(
+        r"(?s)1 heap allocation\(s\) leaked, 6 byte\(s\) in total.*size 6, align 2.*at leak::test_forget_box\n.*leak.rs:5:13.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_heap_deny_leaks_without_leaks,
+    input = "heap/leak.rs",
+    start_fn = "test_no_leak",
+    run_config = snapcrab::RunConfig {
+        deny_leaks: true,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(vec![])
+);
@@ -79,11 +79,498 @@ check_custom_start!(
     )
 );
 
+check_custom_start!(
+    test_transmute_invalid_bool_warn,
+    input = "intrinsics/transmute_invalid.rs",
+    start_fn = "test_invalid_bool",
+    check_config = snapcrab::CheckConfig {
+        validity: snapcrab::CheckLevel::Warn,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(vec![2])
+);
+
 check_custom_start!(
     test_transmute_invalid_enum_discriminant,
     input = "intrinsics/transmute_invalid.rs",
     start_fn = "test_invalid_enum_discriminant",
     result = TestResult::ErrorRegex(
-        r".*Assertion failed.*construct an enum from an invalid value.*".to_string()
+        r"(?s)panicked at .*transmute_invalid.rs:27:14:\ntrying to construct an enum from an invalid value 0x5".to_string()
+    )
+);
+
+// --- Integer intrinsics ---
+
+check_custom_start!(
+    test_int_bit_counts,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_bit_counts",
+    result = TestResult::SuccessWithValue(50808u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_int_zero_bit_counts,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_zero_bit_counts",
+    result = TestResult::SuccessWithValue(32u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_int_swap_and_reverse,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_swap_and_reverse",
+);
+
+check_custom_start!(
+    test_int_rotate,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_rotate",
+);
+
+check_custom_start!(
+    test_int_overflowing,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_overflowing",
+    result = TestResult::SuccessWithValue(vec![22])
+);
+
+check_custom_start!(
+    test_int_with_overflow_intrinsics,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_with_overflow_intrinsics",
+    result = TestResult::SuccessWithValue((-21i32).to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_int_saturating,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_saturating",
+);
+
+check_custom_start!(
+    test_int_three_way_compare,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_three_way_compare",
+    result = TestResult::SuccessWithValue((-1i8).to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_int_unchecked,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_unchecked",
+    result = TestResult::SuccessWithValue(1223u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_int_exact_div,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_exact_div",
+    result = TestResult::SuccessWithValue((-7i64).to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_int_unchecked_add_overflow,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_unchecked_add_overflow",
+    result =
+        TestResult::ErrorRegex(r".*Undefined behavior: overflow in `AddUnchecked`.*".to_string())
+);
+
+check_custom_start!(
+    test_int_unchecked_shr_overflow,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_unchecked_shr_overflow",
+    result = TestResult::ErrorRegex(
+        r".*Undefined behavior: unchecked shift with amount 32.*".to_string()
+    )
+);
+
+check_custom_start!(
+    test_int_unchecked_div_overflow,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_unchecked_div_overflow",
+    result = TestResult::ErrorRegex(r".*Undefined behavior: overflow in `Div`.*".to_string())
+);
+
+check_custom_start!(
+    test_int_unchecked_rem_zero,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_unchecked_rem_zero",
+    result =
+        TestResult::ErrorRegex(r".*Undefined behavior: division by zero in `Rem`.*".to_string())
+);
+
+check_custom_start!(
+    test_int_exact_div_remainder,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_exact_div_remainder",
+    result = TestResult::ErrorRegex(
+        r".*Undefined behavior: `exact_div` of 10 by 4 has a remainder.*".to_string()
+    )
+);
+
+check_custom_start!(
+    test_int_ctlz_nonzero_zero,
+    input = "intrinsics/int_intrinsics.rs",
+    start_fn = "test_ctlz_nonzero_zero",
+    result = TestResult::ErrorRegex(
+        r".*Undefined behavior: `ctlz_nonzero` called with zero.*".to_string()
+    )
+);
+
+// --- Memory intrinsics ---
+
+check_custom_start!(
+    test_mem_copy_nonoverlapping,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_copy_nonoverlapping",
+    result = TestResult::SuccessWithValue(234u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_mem_copy_overlapping,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_copy_overlapping",
+    result = TestResult::SuccessWithValue(vec![1, 2, 3, 4])
+);
+
+check_custom_start!(
+    test_mem_copy_nonoverlapping_overlap,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_copy_nonoverlapping_overlap",
+    result = TestResult::ErrorRegex(
+        r".*Undefined behavior: `copy_nonoverlapping` with overlapping ranges.*".to_string()
+    )
+);
+
+check_custom_start!(
+    test_mem_copy_out_of_bounds,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_copy_out_of_bounds",
+    result = TestResult::ErrorRegex(r".*Heap memory access out of bounds.*".to_string())
+);
+
+check_custom_start!(
+    test_mem_write_bytes,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_write_bytes",
+    result = TestResult::SuccessWithValue(0x0101_0101_0101_0101u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_mem_compare_bytes,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_compare_bytes",
+    result = TestResult::SuccessWithValue(1i32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_mem_raw_eq,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_raw_eq",
+    result = TestResult::SuccessWithValue(vec![0])
+);
+
+check_custom_start!(
+    test_mem_volatile,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_volatile",
+    result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_mem_volatile_misaligned,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_volatile_misaligned",
+    result = TestResult::ErrorRegex(r".*Misaligned memory access.*".to_string())
+);
+
+check_custom_start!(
+    test_mem_volatile_misaligned_off,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_volatile_misaligned",
+    check_config = snapcrab::CheckConfig {
+        alignment: snapcrab::CheckLevel::Off,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(0u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_mem_offset_from,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_offset_from",
+    result = TestResult::SuccessWithValue((-6isize).to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_mem_offset_from_different_allocations,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_offset_from_different_allocations",
+    result = TestResult::ErrorRegex(
+        r".*Undefined behavior: `ptr_offset_from` with pointers .* that are not in the same allocation.*"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_mem_offset_from_unsigned_negative,
+    input = "intrinsics/mem_intrinsics.rs",
+    start_fn = "test_offset_from_unsigned_negative",
+    result = TestResult::ErrorRegex(
+        r".*Undefined behavior: `ptr_offset_from_unsigned` with pointer .* before its base.*"
+            .to_string()
     )
 );
+
+// --- Atomic intrinsics ---
+
+check_custom_start!(
+    test_atomic_load_store,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_load_store",
+    result = TestResult::SuccessWithValue(710usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_atomic_compare_exchange,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_compare_exchange",
+    result = TestResult::SuccessWithValue(vec![6])
+);
+
+check_custom_start!(
+    test_atomic_fetch_ops,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_fetch_ops",
+    result = TestResult::SuccessWithValue((-98i32).to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_atomic_fetch_unsigned_max,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_fetch_unsigned_max",
+    result = TestResult::SuccessWithValue(vec![200])
+);
+
+check_custom_start!(
+    test_atomic_ptr,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_atomic_ptr",
+    result = TestResult::SuccessWithValue(12u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_atomic_arc,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_arc",
+    result = TestResult::SuccessWithValue(42usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_atomic_mutex,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_mutex",
+    result = TestResult::SuccessWithValue(15u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_atomic_once_lock,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_once_lock",
+    result = TestResult::SuccessWithValue(22u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_atomic_misaligned,
+    input = "intrinsics/atomics.rs",
+    start_fn = "test_misaligned_atomic",
+    result = TestResult::ErrorRegex(r".*Misaligned memory access.*".to_string())
+);
+
+// --- Reflection intrinsics ---
+
+check_custom_start!(
+    test_reflection_type_name,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_type_name",
+    result = TestResult::SuccessWithValue(17usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_reflection_type_id,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_type_id",
+    result = TestResult::SuccessWithValue(vec![0])
+);
+
+check_custom_start!(
+    test_reflection_downcast,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_downcast",
+    result = TestResult::SuccessWithValue(7u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_reflection_size_of_val,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_size_of_val",
+    result = TestResult::SuccessWithValue(19usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_reflection_size_of_val_aligned_dyn,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_size_of_val_aligned_dyn",
+    result = TestResult::SuccessWithValue(48usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_reflection_discriminant,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_discriminant",
+    result = TestResult::SuccessWithValue(vec![0])
+);
+
+check_custom_start!(
+    test_reflection_discriminant_value,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_discriminant_value",
+    result = TestResult::SuccessWithValue(2isize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_reflection_variant_count,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_variant_count",
+    result = TestResult::SuccessWithValue(32usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_reflection_runtime_variant_count,
+    input = "intrinsics/reflection.rs",
+    start_fn = "test_runtime_variant_count",
+    result = TestResult::SuccessWithValue(32usize.to_le_bytes().to_vec())
+);
+
+// --- Float intrinsics ---
+
+check_custom_start!(
+    test_float_sqrt_powi,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_sqrt_powi",
+    result = TestResult::SuccessWithValue(4652225937975638498u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_transcendental,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_transcendental",
+    result = TestResult::SuccessWithValue(18436134401059542459u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_transcendental_f32,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_transcendental_f32",
+    result = TestResult::SuccessWithValue(2131318047u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_mul_add,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_mul_add",
+    result = TestResult::SuccessWithValue(847249408u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_fmuladd,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_fmuladd",
+    result = TestResult::SuccessWithValue(0u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_rounding,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_rounding",
+    result = TestResult::SuccessWithValue(5i32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_min_max,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_min_max",
+    result = TestResult::SuccessWithValue(1.5f64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_f16_f128,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_f16_f128",
+    result = TestResult::SuccessWithValue(1073698721u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_algebraic,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_algebraic",
+    result = TestResult::SuccessWithValue(1.0f32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_float_to_int_unchecked_out_of_range,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_to_int_unchecked_out_of_range",
+    result =
+        TestResult::ErrorRegex(r".*Undefined behavior: `float_to_int_unchecked`.*".to_string())
+);
+
+check_custom_start!(
+    test_float_fast_math_infinity,
+    input = "intrinsics/float_intrinsics.rs",
+    start_fn = "test_fast_math_infinity",
+    result = TestResult::ErrorRegex(r".*Undefined behavior: `fadd_fast`.*".to_string())
+);
+
+// --- Runtime checks ---
+
+check_custom_start!(
+    test_runtime_checks_precondition,
+    input = "intrinsics/runtime_checks.rs",
+    start_fn = "test_precondition",
+    result = TestResult::ErrorRegex(
+        r"(?s)panicked at .*runtime_checks.rs:11:26:\nunsafe precondition\(s\) violated: slice::from_raw_parts".to_string()
+    )
+);
+
+check_custom_start!(
+    test_runtime_checks_precondition_skipped,
+    input = "intrinsics/runtime_checks.rs",
+    start_fn = "test_precondition",
+    check_config = snapcrab::CheckConfig {
+        ub_checks: false,
+        // The misaligned slice reference is still caught when it is created.
+        alignment: snapcrab::CheckLevel::Off,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(1usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_runtime_checks_overflow,
+    input = "intrinsics/runtime_checks.rs",
+    start_fn = "test_overflow_checks",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_runtime_checks_overflow_disabled,
+    input = "intrinsics/runtime_checks.rs",
+    start_fn = "test_overflow_checks",
+    check_config = snapcrab::CheckConfig {
+        overflow_checks: false,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(vec![0])
+);
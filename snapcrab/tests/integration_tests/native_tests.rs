@@ -65,7 +65,6 @@ check_native_call!(
 // --- Floating point ---
 
 check_native_call!(
-    #[ignore] // float Eq not yet supported in interpreter
     test_native_add_f64,
     native_lib = "native/lib_basic.rs",
     input = "native/call_basic.rs",
@@ -73,7 +72,6 @@ check_native_call!(
 );
 
 check_native_call!(
-    #[ignore] // float Eq not yet supported in interpreter
     test_native_add_f32,
     native_lib = "native/lib_basic.rs",
     input = "native/call_basic.rs",
@@ -430,7 +428,6 @@ check_extern_crate!(
 // --- Rust ABI: SIMD vectors (Direct with ValueAbi::Vector) ---
 
 check_extern_crate!(
-    #[ignore] // ctpop intrinsic not yet shimmed (interpreter limitation)
     test_rust_abi_simd_sum,
     dep = "native/dep_rust_abi.rs",
     input = "native/call_rust_abi.rs",
@@ -445,7 +442,6 @@ check_extern_crate!(
 );
 
 check_extern_crate!(
-    #[ignore] // ctpop intrinsic not yet shimmed (interpreter limitation)
     test_rust_abi_simd_add,
     dep = "native/dep_rust_abi.rs",
     input = "native/call_rust_abi.rs",
@@ -509,7 +505,6 @@ check_extern_crate!(
     dep = "native/dep_rust_abi.rs",
     input = "native/call_rust_abi.rs",
     start_fn = "test_external_mutable_static",
-    result = TestResult::ErrorRegex(r".*unsupported.*duplicated in native code.*".to_string()),
 );
 
 check_extern_crate!(
@@ -517,5 +512,39 @@ check_extern_crate!(
     dep = "native/dep_rust_abi.rs",
     input = "native/call_rust_abi.rs",
     start_fn = "test_static_with_mut_ptr",
-    result = TestResult::ErrorRegex(r".*unsupported.*duplicated in native code.*".to_string()),
+);
+
+// --- Rust ABI: Function pointers ---
+
+check_extern_crate!(
+    test_rust_abi_native_fn_ptr,
+    dep = "native/dep_rust_abi.rs",
+    input = "native/call_rust_abi.rs",
+    start_fn = "test_native_fn_ptr",
+);
+
+// --- Rust ABI: Panics ---
+
+check_extern_crate!(
+    test_rust_abi_native_panic,
+    dep = "native/dep_rust_abi.rs",
+    input = "native/call_rust_abi.rs",
+    start_fn = "test_native_panic",
+    result = TestResult::ErrorRegex(r"^thread 'main' panicked at .*:\nodd value$".to_string())
+);
+
+check_extern_crate!(
+    test_rust_abi_catch_native_panic,
+    dep = "native/dep_rust_abi.rs",
+    input = "native/call_rust_abi.rs",
+    start_fn = "test_catch_native_panic",
+);
+
+// --- Rust ABI: Track caller ---
+
+check_extern_crate!(
+    test_rust_abi_native_track_caller,
+    dep = "native/dep_rust_abi.rs",
+    input = "native/call_rust_abi.rs",
+    start_fn = "test_native_track_caller",
 );
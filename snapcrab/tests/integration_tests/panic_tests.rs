@@ -0,0 +1,168 @@
+use crate::common::TestResult;
+
+// --- Panics raised by the interpreted program ---
+
+check_custom_start!(
+    test_panic_explicit,
+    input = "panic/panics.rs",
+    start_fn = "test_explicit_panic",
+    result = TestResult::ErrorRegex(r"^thread 'main' panicked at .*:\nboom$".to_string())
+);
+
+check_custom_start!(
+    test_panic_unreachable,
+    input = "panic/panics.rs",
+    start_fn = "test_unreachable",
+    result = TestResult::ErrorRegex(
+        r"^thread 'main' panicked at .*:\ninternal error: entered unreachable code$".to_string()
+    )
+);
+
+check_custom_start!(
+    test_panic_unwrap_none,
+    input = "panic/panics.rs",
+    start_fn = "test_unwrap_none",
+    result = TestResult::ErrorRegex(
+        r"^thread 'main' panicked at .*panics.rs:14:5:\ncalled `Option::unwrap\(\)` on a `None` value$"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_panic_expect_none,
+    input = "panic/panics.rs",
+    start_fn = "test_expect_none",
+    result = TestResult::ErrorRegex(
+        r"^thread 'main' panicked at .*panics.rs:19:5:\nvalue must be present$".to_string()
+    )
+);
+
+check_custom_start!(
+    test_panic_assert,
+    input = "panic/panics.rs",
+    start_fn = "test_assert",
+    result = TestResult::ErrorRegex(
+        r"^thread 'main' panicked at .*panics.rs:24:5:\nassertion failed: x == 2$".to_string()
+    )
+);
+
+check_custom_start!(
+    test_panic_index_out_of_bounds,
+    input = "panic/panics.rs",
+    start_fn = "test_index_out_of_bounds",
+    result = TestResult::ErrorRegex(
+        r"^thread 'main' panicked at .*panics.rs:30:5:\nindex out of bounds: the len is 3 but the index is 5$"
+            .to_string()
+    )
+);
+
+check_custom_start!(
+    test_panic_add_overflow,
+    input = "panic/panics.rs",
+    start_fn = "test_add_overflow",
+    result = TestResult::ErrorRegex(
+        r"^thread 'main' panicked at .*panics.rs:35:5:\nattempt to add with overflow$".to_string()
+    )
+);
+
+check_custom_start!(
+    test_panic_track_caller_location,
+    input = "panic/panics.rs",
+    start_fn = "test_track_caller_location",
+    result =
+        TestResult::ErrorRegex(r"^thread 'main' panicked at .*panics.rs:48:5:\n.*".to_string())
+);
+
+check_custom_start!(
+    test_panic_format_arguments,
+    input = "panic/panics.rs",
+    start_fn = "test_format_panic",
+    result = TestResult::ErrorRegex(r"^thread 'main' panicked at .*:\nbad value 7$".to_string())
+);
+
+check_custom_start!(
+    test_panic_format_display_impl,
+    input = "panic/panics.rs",
+    start_fn = "test_format_panic_display",
+    result = TestResult::ErrorRegex(
+        r#"^thread 'main' panicked at .*:\nbad point \(1, -2\) Some\("s"\)   2\.2$"#.to_string()
+    )
+);
+
+check_custom_start!(
+    test_process_abort,
+    input = "panic/panics.rs",
+    start_fn = "test_abort",
+    result = TestResult::Error("abnormal termination: the program aborted execution".to_string())
+);
+
+check_custom_start!(
+    test_no_panic,
+    input = "panic/panics.rs",
+    start_fn = "test_no_panic",
+    result = TestResult::SuccessWithValue(vec![3, 0, 0, 0])
+);
+
+// --- Interpreter errors are not reported as panics ---
+
+check_custom_start!(
+    test_internal_error_is_not_a_panic,
+    input = "panic/panics.rs",
+    start_fn = "test_internal_error",
+    result = TestResult::ErrorRegex(
+        r"^error: Unsupported terminator: InlineAsm.*\n  --> .*panics.rs:61:.*".to_string()
+    )
+);
+
+// --- Unwinding ---
+
+check_custom_start!(
+    test_unwind_runs_drop,
+    input = "panic/unwind.rs",
+    start_fn = "test_unwind_runs_drop",
+    result = TestResult::Error("abnormal termination: the program aborted execution".to_string())
+);
+
+check_custom_start!(
+    test_unwind_drops_in_order,
+    input = "panic/unwind.rs",
+    start_fn = "test_unwind_drops_in_order",
+    result = TestResult::ErrorRegex(r"^thread 'main' panicked at .*:\nnested panic$".to_string())
+);
+
+check_custom_start!(
+    test_panic_in_cleanup_aborts,
+    input = "panic/unwind.rs",
+    start_fn = "test_panic_in_cleanup",
+    result = TestResult::ErrorRegex(
+        r"^abnormal termination: thread 'main' panicked at .*unwind.rs:61:1:\npanic in a destructor during cleanup\nthread caused non-unwinding panic. aborting.$".to_string()
+    )
+);
+
+check_custom_start!(
+    test_catch_unwind,
+    input = "panic/unwind.rs",
+    start_fn = "test_catch_unwind",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_catch_unwind_no_panic,
+    input = "panic/unwind.rs",
+    start_fn = "test_catch_unwind_no_panic",
+    result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_catch_unwind_payload,
+    input = "panic/unwind.rs",
+    start_fn = "test_catch_unwind_payload",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_resume_unwind,
+    input = "panic/unwind.rs",
+    start_fn = "test_resume_unwind",
+    result = TestResult::ErrorRegex(r"^thread 'main' panicked at .*:\nBox<dyn Any>$".to_string())
+);
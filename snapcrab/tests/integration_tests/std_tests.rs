@@ -13,14 +13,12 @@ check_custom_start!(
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_option_map,
     input = "std_ops/option.rs",
     start_fn = "test_option_map",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_option_and_then,
     input = "std_ops/option.rs",
     start_fn = "test_option_and_then",
@@ -59,21 +57,18 @@ check_custom_start!(
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_result_unwrap_or,
     input = "std_ops/result.rs",
     start_fn = "test_result_unwrap_or",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_result_map,
     input = "std_ops/result.rs",
     start_fn = "test_result_map",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_result_and_then,
     input = "std_ops/result.rs",
     start_fn = "test_result_and_then",
@@ -108,28 +103,24 @@ check_custom_start!(
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_saturating_add,
     input = "std_ops/numeric.rs",
     start_fn = "test_saturating_add",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_saturating_sub,
     input = "std_ops/numeric.rs",
     start_fn = "test_saturating_sub",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_checked_add_some,
     input = "std_ops/numeric.rs",
     start_fn = "test_checked_add_some",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_checked_add_overflow,
     input = "std_ops/numeric.rs",
     start_fn = "test_checked_add_overflow",
@@ -142,28 +133,24 @@ check_custom_start!(
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_leading_zeros,
     input = "std_ops/numeric.rs",
     start_fn = "test_leading_zeros",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_count_ones,
     input = "std_ops/numeric.rs",
     start_fn = "test_count_ones",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_swap_bytes,
     input = "std_ops/numeric.rs",
     start_fn = "test_swap_bytes",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_rotate_left,
     input = "std_ops/numeric.rs",
     start_fn = "test_rotate_left",
@@ -172,14 +159,12 @@ check_custom_start!(
 // --- Comparison operations ---
 
 check_custom_start!(
-    #[ignore]
     test_std_min,
     input = "std_ops/cmp.rs",
     start_fn = "test_min",
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_max,
     input = "std_ops/cmp.rs",
     start_fn = "test_max",
@@ -192,7 +177,6 @@ check_custom_start!(
 );
 
 check_custom_start!(
-    #[ignore]
     test_std_ord_methods,
     input = "std_ops/cmp.rs",
     start_fn = "test_ord_methods",
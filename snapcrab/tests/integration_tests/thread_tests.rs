@@ -0,0 +1,371 @@
+use crate::common::TestResult;
+
+// --- Thread-local statics ---
+
+check_custom_start!(
+    test_lazy_thread_local,
+    input = "thread/thread_local.rs",
+    start_fn = "test_lazy_thread_local",
+    result = TestResult::SuccessWithValue(102u64.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_const_thread_local,
+    input = "thread/thread_local.rs",
+    start_fn = "test_const_thread_local",
+    result = TestResult::SuccessWithValue(vec![42, 0, 0, 0])
+);
+
+check_custom_start!(
+    test_thread_local_attribute,
+    input = "thread/thread_local.rs",
+    start_fn = "test_thread_local_attribute",
+    result = TestResult::SuccessWithValue(vec![42, 0, 0, 0])
+);
+
+check_custom_start!(
+    test_thread_local_destructor,
+    input = "thread/thread_local.rs",
+    start_fn = "test_thread_local_destructor",
+    run_config = snapcrab::RunConfig {
+        deny_leaks: true,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(2usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_thread_local_destructor_runs_at_exit,
+    input = "thread/thread_local.rs",
+    start_fn = "test_destructor_runs_at_exit",
+    result = TestResult::Error("abnormal termination: thread local panicked on drop".to_string())
+);
+
+check_custom_start!(
+    test_thread_local_destructor_runs_after_panic,
+    input = "thread/thread_local.rs",
+    start_fn = "test_destructor_runs_after_panic",
+    result = TestResult::Error("abnormal termination: thread local panicked on drop".to_string())
+);
+
+// --- Spawning and joining threads ---
+
+check_custom_start!(
+    test_spawn_join,
+    input = "thread/spawn.rs",
+    start_fn = "test_spawn_join",
+    result = TestResult::SuccessWithValue(vec![42, 0, 0, 0])
+);
+
+check_custom_start!(
+    test_spawn_shared_counter,
+    input = "thread/spawn.rs",
+    start_fn = "test_spawn_shared_counter",
+    run_config = snapcrab::RunConfig {
+        deny_leaks: true,
+        ..Default::default()
+    },
+    result = TestResult::SuccessWithValue(6usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_scope,
+    input = "thread/spawn.rs",
+    start_fn = "test_scope",
+    result = TestResult::SuccessWithValue(10usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_scope_join,
+    input = "thread/spawn.rs",
+    start_fn = "test_scope_join",
+    result = TestResult::SuccessWithValue(3usize.to_le_bytes().to_vec())
+);
+
+check_custom_start!(
+    test_join_panic,
+    input = "thread/spawn.rs",
+    start_fn = "test_join_panic",
+    result = TestResult::SuccessWithValue(vec![1])
+);
+
+check_custom_start!(
+    test_scope_panic,
+    input = "thread/spawn.rs",
+    start_fn = "test_scope_panic",
+    result = TestResult::ErrorRegex(
+        r"^thread 'main' panicked at .*spawn.rs:62:5:\na scoped thread panicked$".to_string()
+    )
+);
+
+check_custom_start!(
+    test_thread_local_per_thread,
+    input = "thread/spawn.rs",
+    start_fn = "test_thread_local_per_thread",
+    result = TestResult::SuccessWithValue(vec![11, 0, 0, 0])
+);
+
+check_custom_start!(
+    test_current_thread,
+    input = "thread/spawn.rs",
+    start_fn = "test_current_thread",
+    result = TestResult::SuccessWithValue(vec![0b111])
+);
+
+/// Threads are created, joined and detached by functions private to `std`,
+/// which are intercepted by path. A toolchain that renames them must fail
+/// here, rather than run them natively.
+#[test]
+fn test_thread_fn_paths() {
+    let input_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("inputs")
+        .join("thread/spawn.rs");
+    let missing = crate::common::run_compiler_query(&input_path, snapcrab::ThreadFn::missing_paths);
+    assert!(
+        missing.is_empty(),
+        "Missing thread runtime functions: {missing:?}"
+    );
+}
+
+check_custom_start!(
+    test_relock_deadlock,
+    input = "thread/schedule.rs",
+    start_fn = "test_relock",
+    result = TestResult::ErrorRegex(
+        r"(?s)^error: deadlock: thread 'main' is waiting on the mutex at 0x[0-9a-f]+, and there is no other thread\n".to_string()
+    )
+);
+
+// --- Scheduling ---
+
+/// Coroutines need `swapcontext`, which is only available with glibc.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+mod schedule {
+    use crate::common::TestResult;
+
+    fn seeded(seed: u64) -> snapcrab::RunConfig {
+        snapcrab::RunConfig {
+            deny_leaks: true,
+            schedule_seed: Some(seed),
+            ..Default::default()
+        }
+    }
+
+    // Threads take turns when they block, or when they spin on an atomic.
+    check_custom_start!(
+        test_handoff,
+        input = "thread/spawn.rs",
+        start_fn = "test_handoff",
+        result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_detach,
+        input = "thread/spawn.rs",
+        start_fn = "test_detach",
+        result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_mutex_contention,
+        input = "thread/schedule.rs",
+        start_fn = "test_mutex_contention",
+        result = TestResult::SuccessWithValue(30usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_condvar,
+        input = "thread/schedule.rs",
+        start_fn = "test_condvar",
+        result = TestResult::SuccessWithValue(15u32.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_rwlock,
+        input = "thread/schedule.rs",
+        start_fn = "test_rwlock",
+        result = TestResult::SuccessWithValue(10usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_park_unpark,
+        input = "thread/schedule.rs",
+        start_fn = "test_park_unpark",
+        result = TestResult::SuccessWithValue(vec![1])
+    );
+
+    check_custom_start!(
+        test_sleep,
+        input = "thread/schedule.rs",
+        start_fn = "test_sleep",
+        result = TestResult::SuccessWithValue(vec![1])
+    );
+
+    check_custom_start!(
+        test_spin_wait,
+        input = "thread/schedule.rs",
+        start_fn = "test_spin_wait",
+        result = TestResult::SuccessWithValue(vec![1])
+    );
+
+    check_custom_start!(
+        test_join_deadlock,
+        input = "thread/schedule.rs",
+        start_fn = "test_join_deadlock",
+        result = TestResult::ErrorRegex(
+            r"^error: deadlock: every thread is blocked\nthread 'main' is joining thread '<unnamed>'\n".to_string()
+        )
+    );
+
+    // With a seed, threads also take turns at function calls.
+    check_custom_start!(
+        test_schedule_spawn_join,
+        input = "thread/spawn.rs",
+        start_fn = "test_spawn_join",
+        run_config = seeded(3),
+        result = TestResult::SuccessWithValue(vec![42, 0, 0, 0])
+    );
+
+    check_custom_start!(
+        test_schedule_shared_counter,
+        input = "thread/spawn.rs",
+        start_fn = "test_spawn_shared_counter",
+        run_config = seeded(3),
+        result = TestResult::SuccessWithValue(6usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_scope,
+        input = "thread/spawn.rs",
+        start_fn = "test_scope",
+        run_config = seeded(7),
+        result = TestResult::SuccessWithValue(10usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_join_panic,
+        input = "thread/spawn.rs",
+        start_fn = "test_join_panic",
+        run_config = seeded(3),
+        result = TestResult::SuccessWithValue(vec![1])
+    );
+
+    check_custom_start!(
+        test_schedule_thread_local_per_thread,
+        input = "thread/spawn.rs",
+        start_fn = "test_thread_local_per_thread",
+        run_config = seeded(3),
+        result = TestResult::SuccessWithValue(vec![11, 0, 0, 0])
+    );
+
+    check_custom_start!(
+        test_schedule_handoff,
+        input = "thread/spawn.rs",
+        start_fn = "test_handoff",
+        run_config = seeded(5),
+        result = TestResult::SuccessWithValue(42u32.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_mutex_contention,
+        input = "thread/schedule.rs",
+        start_fn = "test_mutex_contention",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(30usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_condvar,
+        input = "thread/schedule.rs",
+        start_fn = "test_condvar",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(15u32.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_rwlock,
+        input = "thread/schedule.rs",
+        start_fn = "test_rwlock",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(10usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_park_unpark,
+        input = "thread/schedule.rs",
+        start_fn = "test_park_unpark",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(vec![1])
+    );
+
+    check_custom_start!(
+        test_schedule_sleep,
+        input = "thread/schedule.rs",
+        start_fn = "test_sleep",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(vec![1])
+    );
+
+    check_custom_start!(
+        test_schedule_spin_wait,
+        input = "thread/schedule.rs",
+        start_fn = "test_spin_wait",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(vec![1])
+    );
+
+    // The same seed always replays the same interleaving, and other seeds
+    // explore other interleavings.
+    check_custom_start!(
+        test_schedule_interleaving_seed_1,
+        input = "thread/schedule.rs",
+        start_fn = "test_interleaving",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(0b0000_1111u32.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_interleaving_seed_2,
+        input = "thread/schedule.rs",
+        start_fn = "test_interleaving",
+        run_config = seeded(2),
+        result = TestResult::SuccessWithValue(0b0010_1011u32.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_lost_update_seed_1,
+        input = "thread/schedule.rs",
+        start_fn = "test_lost_update",
+        run_config = seeded(1),
+        result = TestResult::SuccessWithValue(17usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_lost_update_seed_4,
+        input = "thread/schedule.rs",
+        start_fn = "test_lost_update",
+        run_config = seeded(4),
+        result = TestResult::SuccessWithValue(20usize.to_le_bytes().to_vec())
+    );
+
+    check_custom_start!(
+        test_schedule_join_deadlock,
+        input = "thread/schedule.rs",
+        start_fn = "test_join_deadlock",
+        run_config = seeded(1),
+        result = TestResult::ErrorRegex(
+            r"(?s)^error: deadlock: every thread is blocked\nthread 'main' is joining thread '<unnamed>'\nstack backtrace:\n.*at schedule::test_join_deadlock\n.*\nthread '<unnamed>' is waiting on the mutex at 0x[0-9a-f]+\nstack backtrace:\n.*at schedule::test_join_deadlock::\{closure#0\}\n".to_string()
+        )
+    );
+
+    check_custom_start!(
+        test_schedule_relock_deadlock,
+        input = "thread/schedule.rs",
+        start_fn = "test_relock",
+        run_config = seeded(1),
+        result = TestResult::ErrorRegex(
+            r"^error: deadlock: thread 'main' is waiting on the mutex at 0x[0-9a-f]+, and there is no other thread\n".to_string()
+        )
+    );
+}